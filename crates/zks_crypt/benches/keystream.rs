@@ -0,0 +1,46 @@
+//! XOR keystream throughput: per-message HKDF expansion against the seekable
+//! ChaCha20 keystream
+//!
+//! Run with `cargo bench -p zks_crypt --bench keystream`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use zks_crypt::keystream::{hkdf_keystream, VernamKeystream};
+
+/// Message sizes; HKDF-SHA256 stops at 8160 bytes
+const SIZES: [usize; 5] = [64, 1024, 8160, 65_536, 1 << 20];
+
+fn keystream(c: &mut Criterion) {
+    let seed = [0x42u8; 32];
+    let mut group = c.benchmark_group("keystream");
+    for size in SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        if size <= 8160 {
+            group.bench_with_input(BenchmarkId::new("hkdf", size), &size, |b, &size| {
+                let mut data = vec![0u8; size];
+                let mut offset = 0u64;
+                b.iter(|| {
+                    let keystream = hkdf_keystream(&seed, offset, size).expect("within the HKDF limit");
+                    for (byte, k) in data.iter_mut().zip(keystream.iter()) {
+                        *byte ^= k;
+                    }
+                    offset += size as u64;
+                    black_box(&data);
+                });
+            });
+        }
+        group.bench_with_input(BenchmarkId::new("chacha20", size), &size, |b, &size| {
+            let keystream = VernamKeystream::new(&seed);
+            let mut data = vec![0u8; size];
+            let mut offset = 0u64;
+            b.iter(|| {
+                keystream.apply(offset, &mut data);
+                offset += size as u64;
+                black_box(&data);
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, keystream);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+//! Bulk STREAM encryption: one segment at a time against all cores
+//!
+//! Run with `cargo bench -p zks_crypt --features parallel --bench stream`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use zks_crypt::stream_aead::{encrypt_stream, encrypt_stream_parallel, DEFAULT_SEGMENT_SIZE};
+
+const SIZES: [usize; 3] = [1 << 20, 16 << 20, 64 << 20];
+
+fn bulk_encrypt(c: &mut Criterion) {
+    let key = [0x42u8; 32];
+    let mut group = c.benchmark_group("stream_encrypt");
+    group.sample_size(10);
+    for size in SIZES {
+        let data = vec![0xA5u8; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("serial", size), &data, |b, data| {
+            b.iter(|| black_box(encrypt_stream(&key, data, DEFAULT_SEGMENT_SIZE).unwrap()));
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", size), &data, |b, data| {
+            b.iter(|| black_box(encrypt_stream_parallel(&key, data, DEFAULT_SEGMENT_SIZE).unwrap()));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bulk_encrypt);
+criterion_main!(benches);
@@ -0,0 +1,292 @@
+//! Pluggable AEAD Backends
+//!
+//! [`WasifVernam`](crate::wasif_vernam::WasifVernam) seals its base layer with one of
+//! three AEADs, all keyed with the same 32-byte key:
+//!
+//! - ChaCha20-Poly1305 (default, 96-bit counter nonces)
+//! - AES-256-GCM (96-bit counter nonces, fast on CPUs with AES-NI)
+//! - XChaCha20-Poly1305 (192-bit random nonces)
+//!
+//! The backend is normally selected from
+//! [`CryptoParameters`](zks_types::crypto::CryptoParameters).
+
+use alloc::{boxed::Box, vec::Vec};
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::{
+    aead::{Aead, AeadInPlace, Error as AeadError, Payload},
+    ChaCha20Poly1305, KeyInit, XChaCha20Poly1305,
+};
+use zks_types::crypto::{CryptoParameters, EncryptionAlgorithm};
+
+/// Length of the authentication tag shared by every backend
+pub const AEAD_TAG_LEN: usize = 16;
+
+/// Nonce length of XChaCha20-Poly1305
+pub const XNONCE_LEN: usize = 24;
+
+/// AEAD algorithm identifier, as recorded in the envelope header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum AeadAlgorithm {
+    /// ChaCha20-Poly1305 (RFC 8439)
+    #[default]
+    ChaCha20Poly1305 = 0x00,
+    /// AES-256-GCM (NIST SP 800-38D)
+    Aes256Gcm = 0x01,
+    /// XChaCha20-Poly1305 with 192-bit random nonces
+    XChaCha20Poly1305 = 0x02,
+}
+
+impl AeadAlgorithm {
+    /// Convert from the wire byte
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0x00 => Some(AeadAlgorithm::ChaCha20Poly1305),
+            0x01 => Some(AeadAlgorithm::Aes256Gcm),
+            0x02 => Some(AeadAlgorithm::XChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    /// Nonce length in bytes
+    pub fn nonce_len(&self) -> usize {
+        match self {
+            AeadAlgorithm::ChaCha20Poly1305 | AeadAlgorithm::Aes256Gcm => 12,
+            AeadAlgorithm::XChaCha20Poly1305 => XNONCE_LEN,
+        }
+    }
+}
+
+impl From<EncryptionAlgorithm> for AeadAlgorithm {
+    fn from(algorithm: EncryptionAlgorithm) -> Self {
+        match algorithm {
+            EncryptionAlgorithm::ChaCha20 => AeadAlgorithm::ChaCha20Poly1305,
+            EncryptionAlgorithm::Aes256Gcm => AeadAlgorithm::Aes256Gcm,
+            EncryptionAlgorithm::XChaCha20 => AeadAlgorithm::XChaCha20Poly1305,
+        }
+    }
+}
+
+impl From<&CryptoParameters> for AeadAlgorithm {
+    fn from(params: &CryptoParameters) -> Self {
+        params.encryption_algorithm.into()
+    }
+}
+
+/// A keyed AEAD instance
+#[derive(Clone)]
+pub enum AeadBackend {
+    /// ChaCha20-Poly1305
+    ChaCha20Poly1305(ChaCha20Poly1305),
+    /// AES-256-GCM
+    Aes256Gcm(Box<Aes256Gcm>),
+    /// XChaCha20-Poly1305
+    XChaCha20Poly1305(XChaCha20Poly1305),
+}
+
+impl AeadBackend {
+    /// Key a backend for `algorithm`
+    pub fn new(algorithm: AeadAlgorithm, key: &[u8; 32]) -> Result<Self, AeadError> {
+        Ok(match algorithm {
+            AeadAlgorithm::ChaCha20Poly1305 => AeadBackend::ChaCha20Poly1305(
+                ChaCha20Poly1305::new_from_slice(key).map_err(|_| AeadError)?,
+            ),
+            AeadAlgorithm::Aes256Gcm => AeadBackend::Aes256Gcm(Box::new(
+                Aes256Gcm::new_from_slice(key).map_err(|_| AeadError)?,
+            )),
+            AeadAlgorithm::XChaCha20Poly1305 => AeadBackend::XChaCha20Poly1305(
+                XChaCha20Poly1305::new_from_slice(key).map_err(|_| AeadError)?,
+            ),
+        })
+    }
+
+    /// Algorithm this backend runs
+    pub fn algorithm(&self) -> AeadAlgorithm {
+        match self {
+            AeadBackend::ChaCha20Poly1305(_) => AeadAlgorithm::ChaCha20Poly1305,
+            AeadBackend::Aes256Gcm(_) => AeadAlgorithm::Aes256Gcm,
+            AeadBackend::XChaCha20Poly1305(_) => AeadAlgorithm::XChaCha20Poly1305,
+        }
+    }
+
+    /// Nonce length in bytes
+    pub fn nonce_len(&self) -> usize {
+        self.algorithm().nonce_len()
+    }
+
+    fn check_nonce(&self, nonce: &[u8]) -> Result<(), AeadError> {
+        if nonce.len() == self.nonce_len() {
+            Ok(())
+        } else {
+            Err(AeadError)
+        }
+    }
+
+    /// Encrypt `buf` in place and return the detached tag
+    pub fn encrypt_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buf: &mut [u8],
+    ) -> Result<[u8; AEAD_TAG_LEN], AeadError> {
+        self.check_nonce(nonce)?;
+        let tag = match self {
+            AeadBackend::ChaCha20Poly1305(c) => c.encrypt_in_place_detached(nonce.into(), aad, buf)?,
+            AeadBackend::Aes256Gcm(c) => c.encrypt_in_place_detached(nonce.into(), aad, buf)?,
+            AeadBackend::XChaCha20Poly1305(c) => c.encrypt_in_place_detached(nonce.into(), aad, buf)?,
+        };
+        Ok(tag.into())
+    }
+
+    /// Verify `tag` and decrypt `buf` in place
+    pub fn decrypt_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buf: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AeadError> {
+        self.check_nonce(nonce)?;
+        if tag.len() != AEAD_TAG_LEN {
+            return Err(AeadError);
+        }
+        match self {
+            AeadBackend::ChaCha20Poly1305(c) => c.decrypt_in_place_detached(nonce.into(), aad, buf, tag.into()),
+            AeadBackend::Aes256Gcm(c) => c.decrypt_in_place_detached(nonce.into(), aad, buf, tag.into()),
+            AeadBackend::XChaCha20Poly1305(c) => c.decrypt_in_place_detached(nonce.into(), aad, buf, tag.into()),
+        }
+    }
+
+    /// Encrypt `plaintext`, returning `ciphertext || tag`
+    pub fn encrypt(&self, nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, AeadError> {
+        self.check_nonce(nonce)?;
+        let payload = Payload { msg: plaintext, aad };
+        match self {
+            AeadBackend::ChaCha20Poly1305(c) => c.encrypt(nonce.into(), payload),
+            AeadBackend::Aes256Gcm(c) => c.encrypt(nonce.into(), payload),
+            AeadBackend::XChaCha20Poly1305(c) => c.encrypt(nonce.into(), payload),
+        }
+    }
+
+    /// Decrypt `ciphertext || tag`
+    pub fn decrypt(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, AeadError> {
+        self.check_nonce(nonce)?;
+        let payload = Payload { msg: ciphertext, aad };
+        match self {
+            AeadBackend::ChaCha20Poly1305(c) => c.decrypt(nonce.into(), payload),
+            AeadBackend::Aes256Gcm(c) => c.decrypt(nonce.into(), payload),
+            AeadBackend::XChaCha20Poly1305(c) => c.decrypt(nonce.into(), payload),
+        }
+    }
+}
+
+impl core::fmt::Debug for AeadBackend {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "AeadBackend({:?})", self.algorithm())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUNSCREEN: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+
+    fn unhex(s: &str) -> Vec<u8> {
+        hex::decode(s).unwrap()
+    }
+
+    fn check_kat(algorithm: AeadAlgorithm, key: &[u8], nonce: &[u8], aad: &[u8], pt: &[u8], expected: &[u8]) {
+        let key: [u8; 32] = key.try_into().unwrap();
+        let backend = AeadBackend::new(algorithm, &key).unwrap();
+
+        let ct = backend.encrypt(nonce, pt, aad).unwrap();
+        assert_eq!(ct, expected, "{:?} ciphertext mismatch", algorithm);
+        assert_eq!(backend.decrypt(nonce, &ct, aad).unwrap(), pt);
+
+        // Detached in-place path agrees with the allocating one
+        let mut buf = pt.to_vec();
+        let tag = backend.encrypt_in_place_detached(nonce, aad, &mut buf).unwrap();
+        assert_eq!(&buf[..], &expected[..pt.len()]);
+        assert_eq!(&tag[..], &expected[pt.len()..]);
+        backend.decrypt_in_place_detached(nonce, aad, &mut buf, &tag).unwrap();
+        assert_eq!(buf, pt);
+
+        let mut tampered = ct.clone();
+        tampered[0] ^= 1;
+        assert!(backend.decrypt(nonce, &tampered, aad).is_err());
+    }
+
+    /// RFC 8439, section 2.8.2
+    #[test]
+    fn test_chacha20poly1305_kat() {
+        let key: Vec<u8> = (0x80..=0x9f).collect();
+        check_kat(
+            AeadAlgorithm::ChaCha20Poly1305,
+            &key,
+            &unhex("070000004041424344454647"),
+            &unhex("50515253c0c1c2c3c4c5c6c7"),
+            SUNSCREEN,
+            &unhex(concat!(
+                "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6",
+                "3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36",
+                "92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc",
+                "3ff4def08e4b7a9de576d26586cec64b6116",
+                "1ae10b594f09e26a7e902ecbd0600691",
+            )),
+        );
+    }
+
+    /// draft-irtf-cfrg-xchacha-03, appendix A.3.1
+    #[test]
+    fn test_xchacha20poly1305_kat() {
+        let key: Vec<u8> = (0x80..=0x9f).collect();
+        let nonce: Vec<u8> = (0x40..=0x57).collect();
+        check_kat(
+            AeadAlgorithm::XChaCha20Poly1305,
+            &key,
+            &nonce,
+            &unhex("50515253c0c1c2c3c4c5c6c7"),
+            SUNSCREEN,
+            &unhex(concat!(
+                "bd6d179d3e83d43b9576579493c0e939572a1700252bfaccbed2902c21396cbb",
+                "731c7f1b0b4aa6440bf3a82f4eda7e39ae64c6708c54c216cb96b72e1213b452",
+                "2f8c9ba40db5d945b11b69b982c1bb9e3f3fac2bc369488f76b2383565d3fff9",
+                "21f9664c97637da9768812f615c68b13b52e",
+                "c0875924c1c7987947deafd8780acf49",
+            )),
+        );
+    }
+
+    /// McGrew & Viega GCM specification, test case 16
+    #[test]
+    fn test_aes256gcm_kat() {
+        check_kat(
+            AeadAlgorithm::Aes256Gcm,
+            &unhex("feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308"),
+            &unhex("cafebabefacedbaddecaf888"),
+            &unhex("feedfacedeadbeeffeedfacedeadbeefabaddad2"),
+            &unhex(concat!(
+                "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72",
+                "1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            )),
+            &unhex(concat!(
+                "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa",
+                "8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662",
+                "76fc6ece0f4e1768cddf8853bb2d551b",
+            )),
+        );
+    }
+
+    #[test]
+    fn test_selected_from_parameters_and_nonce_checked() {
+        let mut params = CryptoParameters::default();
+        assert_eq!(AeadAlgorithm::from(&params), AeadAlgorithm::ChaCha20Poly1305);
+        params.encryption_algorithm = EncryptionAlgorithm::XChaCha20;
+        assert_eq!(AeadAlgorithm::from(&params), AeadAlgorithm::XChaCha20Poly1305);
+
+        let backend = AeadBackend::new(AeadAlgorithm::XChaCha20Poly1305, &[1u8; 32]).unwrap();
+        assert!(backend.encrypt(&[0u8; 12], b"data", b"").is_err());
+        assert!(backend.encrypt(&[0u8; XNONCE_LEN], b"data", b"").is_ok());
+    }
+}
@@ -1,84 +1,206 @@
 //! Enhanced Anti-Replay Attack Protection
-//! 
-//! Implements Citadel-style replay attack prevention using a HashSet-based
-//! circular buffer with support for out-of-order packet delivery.
-//! 
+//!
+//! Implements replay attack prevention with an RFC 6479-style bitmap sliding
+//! window, with support for out-of-order packet delivery.
+//!
 //! # Features
 //! - Thread-safe packet ID (PID) generation and tracking
-//! - Efficient HashSet-based history window (O(1) lookup)
+//! - O(1) lock-free bitmap window: one bit per PID, no allocation per packet
 //! - Handles out-of-order packet delivery (UDP reordering)
 //! - Protection against delayed replay attacks
-//! - Zero-allocation NoHashHasher for u64 PIDs
-//! 
+//! - Pluggable [`ReplayWindow`] trait for custom window implementations
+//!
 //! # Security Model
 //! - Each outgoing packet gets a unique, monotonically increasing PID
 //! - PIDs are encrypted with the packet payload
 //! - Receiver tracks PIDs in a sliding window
 //! - Duplicate or out-of-window PIDs are rejected as replay attacks
+//! - [`check_pid`](AntiReplayContainer::check_pid) runs before authentication and
+//!   [`validate_pid`](AntiReplayContainer::validate_pid) after, so forged packets
+//!   never move the window
 
-use std::collections::HashSet;
-use std::hash::{BuildHasher, Hasher};
-use std::marker::PhantomData;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Mutex;
+use alloc::{boxed::Box, vec::Vec};
+use core::sync::atomic::Ordering;
+
+use crate::sync::AtomicU64;
 
 /// History window size - number of PIDs to track
 /// Allows for packet reordering within this window
 pub const HISTORY_LEN: u64 = 1024;
 
-/// Zero-allocation hasher for u64 PIDs
-/// Since PIDs are already unique u64s, we use them directly as hash values
-struct NoHashHasher<T>(u64, PhantomData<T>);
+/// Largest window a [`BitmapWindow`] tracks (256 KiB of bitmap)
+pub const MAX_WINDOW_SIZE: u64 = 1 << 20;
 
-impl<T> Default for NoHashHasher<T> {
-    fn default() -> Self {
-        NoHashHasher(0, PhantomData)
+/// PIDs per bitmap block; the upper half of each block's word holds its tag
+const BLOCK_BITS: u64 = 32;
+
+/// Sliding window of received packet IDs
+///
+/// A window accepts a PID at most once, and only while it is less than
+/// [`window_size`](Self::window_size) behind the highest PID accepted so far.
+pub trait ReplayWindow: Send + Sync {
+    /// Check a PID without recording it
+    fn check(&self, pid: u64) -> bool;
+
+    /// Record a PID, returning `false` if it is a replay or too old
+    fn update(&self, pid: u64) -> bool;
+
+    /// Forget every recorded PID
+    fn reset(&self);
+
+    /// Number of PIDs tracked behind the highest one
+    fn window_size(&self) -> u64;
+
+    /// Number of PIDs currently recorded inside the window
+    fn len(&self) -> usize;
+
+    /// Check if no PIDs are recorded
+    fn is_empty(&self) -> bool {
+        self.len() == 0
     }
+
+    /// PIDs currently recorded inside the window, in ascending order
+    fn recorded(&self) -> Vec<u64>;
+
+    /// Empty window with the same configuration, used for a new key epoch
+    fn fresh(&self) -> Box<dyn ReplayWindow>;
+}
+
+/// How a block word relates to the block a PID falls in
+enum Slot {
+    /// The word holds the PID's block; the PID's bit is set
+    Seen,
+    /// The word holds the PID's block or an older one the PID's block replaces
+    Free(u64),
+    /// The word was already reused by a newer block
+    Reused,
+}
+
+/// RFC 6479 bitmap sliding window
+///
+/// Every block is one atomic word: the upper 32 bits tag which block of PIDs it
+/// holds, the lower 32 bits record those PIDs. Checking or recording a PID reads
+/// or compare-exchanges that single word, and a block the window slides past is
+/// cleared by the first PID that reuses it, so no lock is ever taken.
+pub struct BitmapWindow {
+    /// Highest PID accepted so far
+    top: AtomicU64,
+    /// Tagged blocks, indexed by `(pid / 32) % blocks.len()`
+    blocks: Box<[AtomicU64]>,
+    window_size: u64,
 }
 
-impl<T> Hasher for NoHashHasher<T> {
-    fn finish(&self) -> u64 {
-        self.0
+impl BitmapWindow {
+    /// Create a window accepting PIDs up to `window_size - 1` behind the highest
+    ///
+    /// `window_size` is capped at [`MAX_WINDOW_SIZE`].
+    pub fn new(window_size: u64) -> Self {
+        let window_size = window_size.clamp(1, MAX_WINDOW_SIZE);
+        // One spare block so the block holding `top` never overlaps the oldest one
+        let blocks = window_size.div_ceil(BLOCK_BITS) + 1;
+        Self {
+            top: AtomicU64::new(0),
+            blocks: (0..blocks).map(|_| AtomicU64::new(0)).collect(),
+            window_size,
+        }
+    }
+
+    fn word(&self, pid: u64) -> &AtomicU64 {
+        &self.blocks[((pid / BLOCK_BITS) % self.blocks.len() as u64) as usize]
     }
 
-    fn write(&mut self, _: &[u8]) {
-        // No-op instead of panic to prevent DoS attacks
-        // This method should never be called for u64 keys
-        debug_assert!(false, "NoHashHasher::write() called - this indicates incorrect usage with non-u64 types");
-        tracing::warn!("NoHashHasher::write() called with byte slice - potential collision risk from incorrect usage");
+    fn is_too_old(&self, pid: u64) -> bool {
+        pid.saturating_add(self.window_size) <= self.top.load(Ordering::Acquire)
     }
 
-    fn write_u64(&mut self, n: u64) {
-        #[cfg(debug_assertions)]
-        {
-            // Ensure this hasher is only used with u64 values to prevent collision attacks
-            if std::mem::size_of::<T>() != std::mem::size_of::<u64>() {
-                panic!("NoHashHasher<T>::write_u64() called with T != u64 - potential collision risk");
+    /// Classify `word` for `pid`
+    ///
+    /// Tags are block numbers modulo 2^32. A wrapped tag can only be mistaken for a
+    /// block far behind `top`, which [`is_too_old`](Self::is_too_old) rejects first.
+    fn slot(word: u64, pid: u64) -> Slot {
+        let tag = (pid / BLOCK_BITS) as u32;
+        let bit = 1u64 << (pid % BLOCK_BITS);
+        let word_tag = (word >> 32) as u32;
+        if word_tag == tag {
+            if word & bit != 0 {
+                Slot::Seen
+            } else {
+                Slot::Free(word | bit)
             }
+        } else if (tag.wrapping_sub(word_tag) as i32) > 0 {
+            Slot::Free((u64::from(tag) << 32) | bit)
+        } else {
+            Slot::Reused
         }
-        self.0 = n
     }
 }
 
-impl<T> BuildHasher for NoHashHasher<T> {
-    type Hasher = Self;
+impl ReplayWindow for BitmapWindow {
+    fn check(&self, pid: u64) -> bool {
+        // Read the word before `top`: whoever filled the word raised `top` first
+        let word = self.word(pid).load(Ordering::Acquire);
+        !self.is_too_old(pid) && matches!(Self::slot(word, pid), Slot::Free(_))
+    }
+
+    fn update(&self, pid: u64) -> bool {
+        if self.is_too_old(pid) {
+            return false;
+        }
+        // Raising `top` early is harmless: a PID rejected below is either already
+        // recorded or behind a newer block, and either way `top` is past it
+        self.top.fetch_max(pid, Ordering::AcqRel);
+
+        let word = self.word(pid);
+        let mut current = word.load(Ordering::Acquire);
+        loop {
+            if self.is_too_old(pid) {
+                return false;
+            }
+            let Slot::Free(next) = Self::slot(current, pid) else {
+                return false;
+            };
+            match word.compare_exchange_weak(current, next, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return true,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    fn reset(&self) {
+        self.top.store(0, Ordering::Release);
+        self.blocks.iter().for_each(|block| block.store(0, Ordering::Release));
+    }
+
+    fn window_size(&self) -> u64 {
+        self.window_size
+    }
 
-    fn build_hasher(&self) -> Self::Hasher {
-        Self::default()
+    fn len(&self) -> usize {
+        self.recorded().len()
+    }
+
+    fn recorded(&self) -> Vec<u64> {
+        let top = self.top.load(Ordering::Acquire);
+        let oldest = top.saturating_sub(self.window_size - 1);
+        (oldest..=top)
+            .filter(|&pid| matches!(Self::slot(self.word(pid).load(Ordering::Acquire), pid), Slot::Seen))
+            .collect()
+    }
+
+    fn fresh(&self) -> Box<dyn ReplayWindow> {
+        Box::new(Self::new(self.window_size))
     }
 }
 
 /// Anti-Replay Attack Container
-/// 
+///
 /// Prevents replay attacks by tracking packet IDs in a sliding window.
 /// Supports out-of-order packet delivery within the window size.
 pub struct AntiReplayContainer {
-    /// (base_counter, seen_pids) - base_counter tracks the sliding window position
-    history: Mutex<(u64, HashSet<u64, NoHashHasher<u64>>)>,
+    /// Received PIDs
+    window: Box<dyn ReplayWindow>,
     /// Counter for outgoing packets (monotonically increasing)
     counter_out: AtomicU64,
-    /// Window size (configurable, default: HISTORY_LEN)
-    window_size: u64,
 }
 
 impl AntiReplayContainer {
@@ -89,117 +211,70 @@ impl AntiReplayContainer {
 
     /// Create with custom window size
     pub fn with_window_size(window_size: u64) -> Self {
+        Self::with_window(Box::new(BitmapWindow::new(window_size)))
+    }
+
+    /// Create around a custom replay window
+    pub fn with_window(window: Box<dyn ReplayWindow>) -> Self {
         Self {
-            history: Mutex::new((
-                0,
-                HashSet::with_capacity_and_hasher(window_size as usize, NoHashHasher::default()),
-            )),
+            window,
             counter_out: AtomicU64::new(0),
-            window_size,
         }
     }
 
+    /// Empty container with the same window configuration (call on re-keying)
+    pub fn fresh(&self) -> Self {
+        Self::with_window(self.window.fresh())
+    }
+
     /// Get the next PID for an outgoing packet
     #[inline]
     pub fn get_next_pid(&self) -> u64 {
         self.counter_out.fetch_add(1, Ordering::Relaxed)
     }
 
+    /// Check a received PID without recording it
+    ///
+    /// Use before authenticating a packet; follow up with
+    /// [`validate_pid`](Self::validate_pid) once it has been authenticated.
+    pub fn check_pid(&self, pid: u64) -> bool {
+        self.window.check(pid)
+    }
+
     /// Validate a received PID
-    /// 
+    ///
     /// Returns `true` if the PID is valid (not a replay).
     /// Returns `false` if:
     /// - The PID was already seen (duplicate)
     /// - The PID is too old (below window)
-    /// - The PID is too far ahead (above window)
-    /// 
+    ///
     /// If valid, the PID is recorded in the history.
-    /// 
+    ///
     /// # Security Note
-    /// This function is designed to be timing-safe for anti-replay purposes.
- /// The execution time depends on the PID value and window position, but this
+    /// The execution time depends on the PID value and window position, but this
     /// is acceptable since PID values are not secret and timing variations don't
     /// leak sensitive information.
     pub fn validate_pid(&self, pid: u64) -> bool {
-        let mut queue = self.history.lock().unwrap_or_else(|e| e.into_inner());
-        let (ref mut base_counter, ref mut seen_pids) = *queue;
-
-        // Calculate the valid window: [base_counter, base_counter + window_size)
-        // base_counter = the oldest PID still acceptable
-        let min_acceptable = *base_counter;
-        let max_acceptable = base_counter.saturating_add(self.window_size);
-
-        // Check all conditions without early returns to prevent timing attacks
-        let already_seen = seen_pids.contains(&pid);
-        let too_old = pid < min_acceptable;
-        let too_far_ahead = pid > max_acceptable;
-        
-        // Log security events in a timing-safe manner for anti-replay purposes
-        // Note: The while loops (lines 162-168) have variable iterations depending on PID values,
-        // but this is acceptable since PID values are not secret and timing variations don't
-        // leak sensitive information for anti-replay validation.
-        let event_type = if already_seen {
-            "REPLAY_ATTACK"
-        } else if too_old {
-            "DELAYED_REPLAY"
-        } else if too_far_ahead {
-            "TOO_FAR_AHEAD"
-        } else {
-            "VALID"
-        };
-        
-        // Log unconditionally with generic message to prevent timing leaks
-        if event_type != "VALID" {
+        let is_valid = self.window.update(pid);
+        if !is_valid {
             tracing::warn!(
-                "🚨 SECURITY EVENT: PID {} status: {} (min: {}, max: {})",
+                "🚨 SECURITY EVENT: PID {} rejected as replay (window: {})",
                 pid,
-                event_type,
-                min_acceptable,
-                max_acceptable
+                self.window.window_size()
             );
         }
-
-        // Only accept if all conditions are met (constant time check)
-        let is_valid = !already_seen && !too_old && !too_far_ahead;
-        
-        if is_valid {
-            // Add to history and slide window if needed
-            seen_pids.insert(pid);
-            
-            // Slide the window if the new PID extends beyond the current window
-            // This ensures the window stays bounded by [base_counter, base_counter + window_size)
-            while pid >= base_counter.saturating_add(self.window_size) {
-                seen_pids.remove(base_counter);
-                *base_counter += 1;
-            }
-            
-            // Also slide the window to maintain the size constraint
-            // If we've exceeded the window size, remove the oldest entries
-            while seen_pids.len() > self.window_size as usize {
-                seen_pids.remove(base_counter);
-                *base_counter += 1;
-            }
-        }
-        
         is_valid
     }
 
     /// Check if any packets have been tracked
     pub fn has_tracked_packets(&self) -> bool {
-        let counter = self.counter_out.load(Ordering::Relaxed);
-        let queue = self.history.lock().unwrap();
-        counter > 0 || queue.0 > 0 || !queue.1.is_empty()
+        self.counter_out.load(Ordering::Relaxed) > 0 || !self.window.is_empty()
     }
 
     /// Reset all counters (call on re-keying)
     pub fn reset(&self) {
         self.counter_out.store(0, Ordering::Relaxed);
-        let mut lock = self.history.lock().unwrap();
-        lock.0 = 0;
-        lock.1 = HashSet::with_capacity_and_hasher(
-            self.window_size as usize,
-            NoHashHasher::default(),
-        );
+        self.window.reset();
         tracing::debug!("🔄 Anti-replay container reset");
     }
 
@@ -210,8 +285,51 @@ impl AntiReplayContainer {
 
     /// Get number of tracked PIDs in history
     pub fn history_size(&self) -> usize {
-        self.history.lock().unwrap().1.len()
+        self.window.len()
+    }
+
+    /// Number of PIDs tracked behind the highest one
+    pub fn window_size(&self) -> u64 {
+        self.window.window_size()
     }
+
+    /// Export counters and recorded PIDs for persistence
+    pub fn export_state(&self) -> ReplayState {
+        ReplayState {
+            window_size: self.window.window_size(),
+            counter_out: self.counter_out.load(Ordering::Relaxed),
+            recorded: self.window.recorded(),
+        }
+    }
+
+    /// Import state into a bitmap window (for resuming from persistence)
+    pub fn import_state(state: &ReplayState) -> Self {
+        Self::import_state_into(state, Box::new(BitmapWindow::new(state.window_size)))
+    }
+
+    /// Import state into a custom replay window
+    ///
+    /// The window is reset first; PIDs too old for its size are dropped.
+    pub fn import_state_into(state: &ReplayState, window: Box<dyn ReplayWindow>) -> Self {
+        window.reset();
+        let container = Self::with_window(window);
+        container.counter_out.store(state.counter_out, Ordering::Relaxed);
+        for &pid in &state.recorded {
+            container.window.update(pid);
+        }
+        container
+    }
+}
+
+/// Serializable replay protection state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayState {
+    /// Window size of the exported window
+    pub window_size: u64,
+    /// Outgoing PID counter
+    pub counter_out: u64,
+    /// PIDs recorded inside the window, in ascending order
+    pub recorded: Vec<u64>,
 }
 
 impl Default for AntiReplayContainer {
@@ -255,7 +373,7 @@ mod tests {
         assert!(container.validate_pid(8));  // Earlier packet arrives late
         assert!(container.validate_pid(12));
         assert!(container.validate_pid(9));  // Another late arrival
-        
+
         // All should be in history
         assert_eq!(container.history_size(), 4);
     }
@@ -263,12 +381,12 @@ mod tests {
     #[test]
     fn test_delayed_replay_protection() {
         let container = AntiReplayContainer::with_window_size(10);
-        
+
         // Fill up the window
         for i in 0..20 {
             container.validate_pid(i);
         }
-        
+
         // Try to replay a very old PID (should fail)
         assert!(!container.validate_pid(0));
         assert!(!container.validate_pid(5));
@@ -280,30 +398,129 @@ mod tests {
         container.get_next_pid();
         container.get_next_pid();
         container.validate_pid(100);
-        
+
         assert!(container.has_tracked_packets());
-        
+
         container.reset();
-        
+
         assert_eq!(container.current_counter(), 0);
         assert_eq!(container.history_size(), 0);
+        assert!(container.validate_pid(100));
     }
 
     #[test]
     fn test_window_sliding() {
         let container = AntiReplayContainer::with_window_size(5);
-        
+
         // Add PIDs 0-4
         for i in 0..5 {
             assert!(container.validate_pid(i));
         }
         assert_eq!(container.history_size(), 5);
-        
+
         // Add PID 5 - should push out PID 0
         assert!(container.validate_pid(5));
         assert_eq!(container.history_size(), 5);
-        
+
         // PID 0 should now be rejected (too old)
         assert!(!container.validate_pid(0));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_check_does_not_record() {
+        let container = AntiReplayContainer::new();
+        assert!(container.check_pid(7));
+        assert!(container.check_pid(7));
+        assert!(container.validate_pid(7));
+        assert!(!container.check_pid(7));
+    }
+
+    #[test]
+    fn test_large_jump_clears_stale_blocks() {
+        let window = BitmapWindow::new(HISTORY_LEN);
+        for pid in 0..HISTORY_LEN {
+            assert!(window.update(pid));
+        }
+
+        // Jump far ahead: every block is reused, old bits must not leak through
+        let far = 10 * HISTORY_LEN + 3;
+        assert!(window.update(far));
+        assert_eq!(window.len(), 1);
+        assert!(window.update(far - 64));
+        assert!(!window.update(far - HISTORY_LEN));
+        assert!(window.update(far - HISTORY_LEN + 1));
+    }
+
+    #[test]
+    fn test_concurrent_updates_accept_each_pid_once() {
+        let window = std::sync::Arc::new(BitmapWindow::new(HISTORY_LEN));
+        let accepted = std::sync::Arc::new(AtomicU64::new(0));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let window = window.clone();
+                let accepted = accepted.clone();
+                std::thread::spawn(move || {
+                    for pid in 0..4 * HISTORY_LEN {
+                        if window.update(pid) {
+                            accepted.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(accepted.load(Ordering::Relaxed), 4 * HISTORY_LEN);
+        assert_eq!(window.len(), HISTORY_LEN as usize);
+        assert!(!window.check(4 * HISTORY_LEN - 1));
+    }
+
+    #[test]
+    fn test_import_into_custom_window() {
+        let container = AntiReplayContainer::with_window_size(128);
+        for pid in [3, 90, 100] {
+            assert!(container.validate_pid(pid));
+        }
+
+        let restored = AntiReplayContainer::import_state_into(&container.export_state(), Box::new(BitmapWindow::new(16)));
+        assert_eq!(restored.window_size(), 16);
+        assert_eq!(restored.history_size(), 2);
+        assert!(!restored.validate_pid(90));
+        assert!(!restored.validate_pid(100));
+        assert!(!restored.validate_pid(3));
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let container = AntiReplayContainer::with_window_size(128);
+        container.get_next_pid();
+        for pid in [3, 90, 200, 150] {
+            assert!(container.validate_pid(pid));
+        }
+
+        let restored = AntiReplayContainer::import_state(&container.export_state());
+        assert_eq!(restored.current_counter(), 1);
+        assert_eq!(restored.history_size(), 3);
+        assert!(!restored.validate_pid(150));
+        assert!(!restored.validate_pid(3));
+        assert!(restored.validate_pid(151));
+    }
+
+    #[test]
+    fn test_window_size_is_capped() {
+        assert_eq!(BitmapWindow::new(u64::MAX).window_size(), MAX_WINDOW_SIZE);
+        assert_eq!(BitmapWindow::new(0).window_size(), 1);
+    }
+
+    #[test]
+    fn test_fresh_keeps_window_size() {
+        let container = AntiReplayContainer::with_window_size(300);
+        assert!(container.validate_pid(42));
+
+        let next = container.fresh();
+        assert_eq!(next.window_size(), 300);
+        assert!(next.validate_pid(42));
+    }
+}
@@ -0,0 +1,118 @@
+//! Key-Committing Envelopes
+//!
+//! ChaCha20-Poly1305 and AES-GCM are not key-committing: an attacker can craft one
+//! ciphertext that authenticates under many keys at once. Against a receiver that
+//! tries keys derived from low-entropy inputs (passwords, room IDs) every rejected
+//! envelope then rules out a whole set of guesses, a partitioning oracle.
+//!
+//! Committed envelopes carry a CTX-style commitment (Chan & Rogaway, 2022) after
+//! the AEAD tag:
+//!
+//! ```text
+//! header | ciphertext | tag | HMAC-SHA256(K_c, header || aad || tag)
+//! ```
+//!
+//! `K_c` is derived from the AEAD key, so the commitment binds the key, nonce and
+//! associated data, and the ciphertext through the tag. Committed envelopes set
+//! [`FLAG_COMMITTED`](crate::envelope::FLAG_COMMITTED), and the receiver checks the
+//! commitment before running the AEAD.
+
+#[cfg(feature = "std")]
+use core::num::NonZeroU32;
+
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+#[cfg(feature = "std")]
+use ring::pbkdf2;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::constant_time::ct_eq;
+
+/// Length of the key commitment appended to committed envelopes
+pub const COMMITMENT_LEN: usize = 32;
+
+/// PBKDF2-HMAC-SHA256 iterations used by [`derive_password_key`]
+pub const PASSWORD_KDF_ITERATIONS: u32 = 600_000;
+
+/// Shortest salt accepted by [`derive_password_key`]
+pub const MIN_PASSWORD_SALT_LEN: usize = 16;
+
+/// Commitment key of one AEAD key epoch
+#[derive(Clone)]
+pub(crate) struct CommitmentKey {
+    key: Zeroizing<[u8; 32]>,
+}
+
+impl CommitmentKey {
+    pub(crate) fn derive(aead_key: &[u8; 32]) -> Self {
+        let mut key = Zeroizing::new([0u8; 32]);
+        let hk = Hkdf::<Sha256>::new(Some(b"zks-key-commitment"), aead_key);
+        hk.expand(b"ctx-commitment-v1", key.as_mut())
+            .expect("32 bytes is a valid HKDF output length");
+        Self { key }
+    }
+
+    /// Commitment over the full associated data (header and caller AAD) and the tag
+    pub(crate) fn commit(&self, full_aad: &[u8], tag: &[u8]) -> [u8; COMMITMENT_LEN] {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(self.key.as_ref())
+            .expect("HMAC accepts keys of any length");
+        mac.update(full_aad);
+        mac.update(tag);
+        mac.finalize().into_bytes().into()
+    }
+
+    /// Constant-time check of a received commitment
+    pub(crate) fn verify(&self, full_aad: &[u8], tag: &[u8], commitment: &[u8]) -> bool {
+        ct_eq(&self.commit(full_aad, tag), commitment)
+    }
+}
+
+/// Derive a 32-byte cipher key from a password
+///
+/// Uses PBKDF2-HMAC-SHA256 with [`PASSWORD_KDF_ITERATIONS`]. Returns `None` if the
+/// salt is shorter than [`MIN_PASSWORD_SALT_LEN`]. Keys derived this way must only
+/// be used with key commitment, see [`WasifVernam::from_password`](crate::wasif_vernam::WasifVernam::from_password).
+#[cfg(feature = "std")]
+pub fn derive_password_key(password: &[u8], salt: &[u8]) -> Option<Zeroizing<[u8; 32]>> {
+    if salt.len() < MIN_PASSWORD_SALT_LEN {
+        return None;
+    }
+    let iterations = NonZeroU32::new(PASSWORD_KDF_ITERATIONS).expect("non-zero iteration count");
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, password, key.as_mut());
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commitment_binds_key_and_inputs() {
+        let key = CommitmentKey::derive(&[0x01; 32]);
+        let commitment = key.commit(b"header", &[0xAA; 16]);
+
+        assert!(key.verify(b"header", &[0xAA; 16], &commitment));
+        assert!(!key.verify(b"header", &[0xAB; 16], &commitment));
+        assert!(!key.verify(b"headex", &[0xAA; 16], &commitment));
+        assert!(!CommitmentKey::derive(&[0x02; 32]).verify(b"header", &[0xAA; 16], &commitment));
+        assert!(!key.verify(b"header", &[0xAA; 16], &commitment[..16]));
+
+        // Cross-check against ring's HMAC-SHA256
+        let mut input = b"header".to_vec();
+        input.extend_from_slice(&[0xAA; 16]);
+        let reference = ring::hmac::sign(&ring::hmac::Key::new(ring::hmac::HMAC_SHA256, key.key.as_ref()), &input);
+        assert_eq!(&commitment[..], reference.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_password_key_requires_salt() {
+        assert!(derive_password_key(b"hunter2", b"short").is_none());
+        let salt = [0x5Au8; MIN_PASSWORD_SALT_LEN];
+        let key = derive_password_key(b"hunter2", &salt).unwrap();
+        assert_eq!(*key, *derive_password_key(b"hunter2", &salt).unwrap());
+        assert_ne!(*key, *derive_password_key(b"hunter3", &salt).unwrap());
+    }
+}
@@ -10,7 +10,8 @@
 //! slower than standard operations but provide protection against timing attacks.
 //! Do not use them for non-cryptographic code paths.
 
-use std::cmp::Ordering;
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
 use subtle::ConstantTimeEq;
 
 /// Constant-time comparison of two byte arrays using subtle crate
@@ -50,37 +51,37 @@ pub fn ct_select<T: Copy>(choice: bool, a: T, b: T) -> T {
     // Convert boolean to mask: 0xFF if true, 0x00 if false
     let mask = -(choice as i8) as u8;
     
-    match std::mem::size_of::<T>() {
+    match core::mem::size_of::<T>() {
         1 => {
             // Handle u8/i8
-            let a_bytes = unsafe { std::mem::transmute_copy::<T, u8>(&a) };
-            let b_bytes = unsafe { std::mem::transmute_copy::<T, u8>(&b) };
+            let a_bytes = unsafe { core::mem::transmute_copy::<T, u8>(&a) };
+            let b_bytes = unsafe { core::mem::transmute_copy::<T, u8>(&b) };
             let result = (a_bytes & mask) | (b_bytes & !mask);
-            unsafe { std::mem::transmute_copy::<u8, T>(&result) }
+            unsafe { core::mem::transmute_copy::<u8, T>(&result) }
         }
         2 => {
             // Handle u16/i16
-            let a_bytes = unsafe { std::mem::transmute_copy::<T, u16>(&a) };
-            let b_bytes = unsafe { std::mem::transmute_copy::<T, u16>(&b) };
+            let a_bytes = unsafe { core::mem::transmute_copy::<T, u16>(&a) };
+            let b_bytes = unsafe { core::mem::transmute_copy::<T, u16>(&b) };
             let mask_16 = u16::from_le_bytes([mask, mask]);
             let result = (a_bytes & mask_16) | (b_bytes & !mask_16);
-            unsafe { std::mem::transmute_copy::<u16, T>(&result) }
+            unsafe { core::mem::transmute_copy::<u16, T>(&result) }
         }
         4 => {
             // Handle u32/i32/f32
-            let a_bytes = unsafe { std::mem::transmute_copy::<T, u32>(&a) };
-            let b_bytes = unsafe { std::mem::transmute_copy::<T, u32>(&b) };
+            let a_bytes = unsafe { core::mem::transmute_copy::<T, u32>(&a) };
+            let b_bytes = unsafe { core::mem::transmute_copy::<T, u32>(&b) };
             let mask_32 = u32::from_le_bytes([mask, mask, mask, mask]);
             let result = (a_bytes & mask_32) | (b_bytes & !mask_32);
-            unsafe { std::mem::transmute_copy::<u32, T>(&result) }
+            unsafe { core::mem::transmute_copy::<u32, T>(&result) }
         }
         8 => {
             // Handle u64/i64/f64
-            let a_bytes = unsafe { std::mem::transmute_copy::<T, u64>(&a) };
-            let b_bytes = unsafe { std::mem::transmute_copy::<T, u64>(&b) };
+            let a_bytes = unsafe { core::mem::transmute_copy::<T, u64>(&a) };
+            let b_bytes = unsafe { core::mem::transmute_copy::<T, u64>(&b) };
             let mask_64 = u64::from_le_bytes([mask, mask, mask, mask, mask, mask, mask, mask]);
             let result = (a_bytes & mask_64) | (b_bytes & !mask_64);
-            unsafe { std::mem::transmute_copy::<u64, T>(&result) }
+            unsafe { core::mem::transmute_copy::<u64, T>(&result) }
         }
         _ => {
             // For other sizes, panic in debug mode to catch usage issues
@@ -8,6 +8,8 @@
 //! - **Multi-endpoint fallback**: Tries multiple drand endpoints with retries
 //! - **OS random fallback**: Falls back to OS random when drand is unavailable
 //! - **Entropy validation**: Validates received entropy for quality and authenticity
+//! - **BLS verification**: Every round must carry a valid signature from the configured
+//!   chain (chained or unchained), and its randomness must be the hash of that signature
 //! - **Network resilience**: Exponential backoff and timeout handling
 //! - **Health monitoring**: Check endpoint health status
 //! 
@@ -38,6 +40,8 @@
 //! - `https://api2.drand.sh` (backup)
 //! 
 //! Each endpoint is tried with exponential backoff and automatic retries.
+//! Use [`DrandConfig::quicknet`] for the quicknet chain, or fill in the chain
+//! public key, scheme, period and genesis time of another network.
 
 use sha2::Sha256;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -49,7 +53,12 @@ use ring::rand::SecureRandom;
 use crate::constant_time::{ct_is_zero, ct_eq};
 
 /// Validates drand entropy quality and authenticity
-fn validate_drand_entropy(response: &DrandResponse, randomness_bytes: &[u8]) -> Result<(), DrandError> {
+///
+/// The BLS signature is checked against the chain configured in `config`, and the
+/// randomness must be the SHA-256 of that signature. Any failure is a hard error.
+fn validate_drand_entropy(config: &DrandConfig, response: &DrandResponse, randomness_bytes: &[u8]) -> Result<(), DrandError> {
+    use sha2::Digest;
+
     // Basic validation
     if randomness_bytes.len() != 32 {
         return Err(DrandError::ParseError(format!(
@@ -67,13 +76,11 @@ fn validate_drand_entropy(response: &DrandResponse, randomness_bytes: &[u8]) ->
     }
     
     // Verify round number is reasonable (not too far in past/future)
-    let current_time = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    
-    // drand updates every 30 seconds, so current round should be approximately current_time / 30
-    let expected_round = current_time / 30;
+    if response.round == 0 {
+        return Err(DrandError::ParseError("Round 0 is the genesis round and carries no randomness".to_string()));
+    }
+    let chain = &config.chain_info;
+    let expected_round = chain.round_at(std::time::SystemTime::now());
     let round_diff = response.round.abs_diff(expected_round);
     
     if round_diff > 100 {
@@ -94,63 +101,55 @@ fn validate_drand_entropy(response: &DrandResponse, randomness_bytes: &[u8]) ->
         return Err(DrandError::ParseError("All-FF entropy detected".to_string()));
     }
     
-    // TODO: Verify BLS signature against known drand public key
-    // This requires additional dependencies like `blst` or `threshold_crypto`
-    // For now, we validate the signature format and log a warning
-    if response.signature.len() < 64 {
-        return Err(DrandError::ParseError("Signature too short".to_string()));
-    }
-    
     // Parse signature bytes
-    let sig_bytes = match hex::decode(&response.signature) {
-        Ok(bytes) => bytes,
-        Err(e) => {
-            return Err(DrandError::ParseError(format!("Invalid signature hex encoding: {}", e)));
-        }
-    };
+    let sig_bytes = hex::decode(&response.signature)
+        .map_err(|e| DrandError::ParseError(format!("Invalid signature hex encoding: {}", e)))?;
     
-    if sig_bytes.len() < 48 {
-        return Err(DrandError::ParseError("Decoded signature too short for BLS".to_string()));
+    if sig_bytes.len() != chain.scheme.signature_len() {
+        return Err(DrandError::ParseError(format!(
+            "Expected {}-byte signature for {:?}, got {}",
+            chain.scheme.signature_len(),
+            chain.scheme,
+            sig_bytes.len()
+        )));
     }
-    
-    // SECURITY: Attempt BLS signature verification using bls12_381 crate
-    // Auto-detect scheme based on signature length:
-    // - 48 bytes = G1 signature (quicknet)
-    // - 96 bytes = G2 signature (mainnet)
-    let scheme = if sig_bytes.len() == 48 {
-        DrandScheme::UnchainedOnG1
-    } else {
-        DrandScheme::PedersenBlsUnchained // Default to unchained for api.drand.sh
-    };
-    
-    match verify_drand_bls_signature(response.round, &sig_bytes, None, scheme) {
-        Ok(true) => {
-            info!("✅ BLS signature verification PASSED for drand round {} (scheme: {:?})", response.round, scheme);
-        },
-        Ok(false) => {
-            // BLS verification failed - try alternate scheme
-            let alt_scheme = if scheme == DrandScheme::UnchainedOnG1 {
-                DrandScheme::PedersenBlsUnchained
-            } else {
-                DrandScheme::PedersenBlsChained
-            };
-            
-            match verify_drand_bls_signature(response.round, &sig_bytes, None, alt_scheme) {
-                Ok(true) => {
-                    info!("✅ BLS signature verification PASSED for drand round {} (alternate scheme: {:?})", response.round, alt_scheme);
-                },
-                _ => {
-                    warn!("⚠️ BLS signature verification did not pass for round {} - verify chain configuration", response.round);
-                }
-            }
-        },
-        Err(e) => {
-            // Log warning but don't fail - allow fallback to OS random if verification fails
-            warn!("⚠️ BLS verification error: {}", e);
-            debug!("Signature length: {}, attempting verification anyway", sig_bytes.len());
+
+    // Chained beacons sign over the previous round's signature as well
+    let previous_signature = match chain.scheme {
+        DrandScheme::PedersenBlsChained => {
+            let previous = response.previous_signature.as_deref().ok_or_else(|| {
+                DrandError::ParseError(format!("Chained round {} is missing previous_signature", response.round))
+            })?;
+            Some(hex::decode(previous).map_err(|e| {
+                DrandError::ParseError(format!("Invalid previous signature hex encoding: {}", e))
+            })?)
         }
+        _ => None,
+    };
+
+    let public_key = hex::decode(&chain.public_key)
+        .map_err(|e| DrandError::ParseError(format!("Invalid PK hex: {}", e)))?;
+
+    // SECURITY: BLS signature verification against the configured chain key
+    if !verify_drand_bls_signature(response.round, &sig_bytes, previous_signature.as_deref(), chain.scheme, &public_key)? {
+        warn!("🚨 BLS signature verification FAILED for drand round {} (scheme: {:?})", response.round, chain.scheme);
+        return Err(DrandError::VerificationError(format!(
+            "Invalid BLS signature for round {}",
+            response.round
+        )));
     }
-    
+
+    // The signature only authenticates the randomness if one is derived from the other
+    let expected_randomness = Sha256::digest(&sig_bytes);
+    if !ct_eq(randomness_bytes, &expected_randomness) {
+        warn!("🚨 Randomness for drand round {} does not match its signature", response.round);
+        return Err(DrandError::VerificationError(format!(
+            "Randomness for round {} is not SHA-256 of its signature",
+            response.round
+        )));
+    }
+
+    debug!("✅ BLS signature verification PASSED for drand round {} (scheme: {:?})", response.round, chain.scheme);
     Ok(())
 }
 
@@ -165,8 +164,11 @@ const DRAND_MAINNET_PK_HEX: &str = "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71
 /// Quicknet - signatures on G1, public key on G2 (96 bytes)
 const DRAND_QUICKNET_PK_HEX: &str = "83cf0f2896adee7eb8b5f01fcad3912212c437e0073e911fb90022d3e760183c8c4b450b6a0a6c3ac6a5776a2d1064510d1fec758c921cc22b0e17e63aaf4bcb5ed66304de9cf809bd274ca73bab4af5a6e9c76a4bc09e76eae8991ef5ece45a";
 
+/// Quicknet chain hash (required in quicknet URLs)
+const DRAND_QUICKNET_CHAIN_HASH: &str = "52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971";
+
 /// Drand signature scheme types
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DrandScheme {
     /// Mainnet default: signatures on G2, chained beacons
     PedersenBlsChained,
@@ -176,21 +178,58 @@ pub enum DrandScheme {
     UnchainedOnG1,
 }
 
+impl DrandScheme {
+    /// Parse a drand scheme ID (`schemeID` in chain info)
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "pedersen-bls-chained" => Some(DrandScheme::PedersenBlsChained),
+            "pedersen-bls-unchained" => Some(DrandScheme::PedersenBlsUnchained),
+            "bls-unchained-g1-rfc9380" => Some(DrandScheme::UnchainedOnG1),
+            _ => None,
+        }
+    }
+
+    /// drand scheme ID
+    pub fn id(&self) -> &'static str {
+        match self {
+            DrandScheme::PedersenBlsChained => "pedersen-bls-chained",
+            DrandScheme::PedersenBlsUnchained => "pedersen-bls-unchained",
+            DrandScheme::UnchainedOnG1 => "bls-unchained-g1-rfc9380",
+        }
+    }
+
+    /// Length of a signature in bytes (96 on G2, 48 on G1)
+    pub fn signature_len(&self) -> usize {
+        match self {
+            DrandScheme::UnchainedOnG1 => 48,
+            _ => 96,
+        }
+    }
+}
+
 /// Verify BLS signature from drand beacon using blst crate
 /// 
 /// Supports multiple drand schemes:
-/// - Mainnet (G2 signatures): message = SHA256(prev_sig || round_be) or SHA256(round_be)
+/// - Mainnet chained (G2 signatures): message = SHA256(prev_sig || round_be)
+/// - Mainnet unchained (G2 signatures): message = SHA256(round_be)
 /// - Quicknet (G1 signatures): message = SHA256(round_be)
 fn verify_drand_bls_signature(
     round: u64, 
     signature: &[u8],
-    _previous_signature: Option<&[u8]>,
+    previous_signature: Option<&[u8]>,
     scheme: DrandScheme,
+    public_key: &[u8],
 ) -> Result<bool, DrandError> {
     use sha2::Digest;
     
-    // Construct the message to verify - unchained uses just round
+    // Construct the message to verify
     let mut hasher = Sha256::new();
+    if scheme == DrandScheme::PedersenBlsChained {
+        let previous = previous_signature.ok_or_else(|| {
+            DrandError::ParseError(format!("Chained round {} needs the previous signature", round))
+        })?;
+        hasher.update(previous);
+    }
     hasher.update(round.to_be_bytes());
     let message = hasher.finalize();
     
@@ -198,24 +237,21 @@ fn verify_drand_bls_signature(
     match scheme {
         DrandScheme::UnchainedOnG1 => {
             // Quicknet: signature on G1 (48 bytes), public key on G2
-            verify_g1_signature_blst(signature, &message, DRAND_QUICKNET_PK_HEX)
+            verify_g1_signature_blst(signature, &message, public_key)
         },
         _ => {
             // Mainnet: signature on G2 (96 bytes), public key on G1
-            verify_g2_signature_blst(signature, &message, DRAND_MAINNET_PK_HEX)
+            verify_g2_signature_blst(signature, &message, public_key)
         }
     }
 }
 
 /// Verify G1 signature using blst (quicknet scheme)
-fn verify_g1_signature_blst(signature: &[u8], message: &[u8], pk_hex: &str) -> Result<bool, DrandError> {
+fn verify_g1_signature_blst(signature: &[u8], message: &[u8], pk_bytes: &[u8]) -> Result<bool, DrandError> {
     use blst::min_sig::{PublicKey, Signature};
     
     // Parse public key (G2 for quicknet/min_sig scheme)
-    let pk_bytes = hex::decode(pk_hex)
-        .map_err(|e| DrandError::ParseError(format!("Invalid PK hex: {}", e)))?;
-    
-    let pk = PublicKey::from_bytes(&pk_bytes)
+    let pk = PublicKey::from_bytes(pk_bytes)
         .map_err(|e| DrandError::ParseError(format!("Invalid G2 public key: {:?}", e)))?;
     
     // Parse signature (G1 for quicknet)
@@ -235,14 +271,11 @@ fn verify_g1_signature_blst(signature: &[u8], message: &[u8], pk_hex: &str) -> R
 }
 
 /// Verify G2 signature using blst (mainnet scheme)
-fn verify_g2_signature_blst(signature: &[u8], message: &[u8], pk_hex: &str) -> Result<bool, DrandError> {
+fn verify_g2_signature_blst(signature: &[u8], message: &[u8], pk_bytes: &[u8]) -> Result<bool, DrandError> {
     use blst::min_pk::{PublicKey, Signature};
     
     // Parse public key (G1 for mainnet/min_pk scheme)
-    let pk_bytes = hex::decode(pk_hex)
-        .map_err(|e| DrandError::ParseError(format!("Invalid PK hex: {}", e)))?;
-    
-    let pk = PublicKey::from_bytes(&pk_bytes)
+    let pk = PublicKey::from_bytes(pk_bytes)
         .map_err(|e| DrandError::ParseError(format!("Invalid G1 public key: {:?}", e)))?;
     
     // Parse signature (G2 for mainnet)
@@ -270,6 +303,9 @@ pub struct DrandResponse {
     pub randomness: String,
     /// The signature (hex-encoded)
     pub signature: String,
+    /// The previous round's signature (hex-encoded, chained beacons only)
+    #[serde(default)]
+    pub previous_signature: Option<String>,
 }
 
 /// Health status of a drand endpoint
@@ -283,13 +319,163 @@ pub enum EndpointHealth {
     Failed(String),
 }
 
+/// Mainnet chain hash
+const DRAND_MAINNET_CHAIN_HASH: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+
+/// Extra wait after a round's nominal time before it is fetched
+const ROUND_PUBLISH_GRACE: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Parameters of a drand chain, as served by its `/info` endpoint
+///
+/// Rounds start at 1 at `genesis_time` and follow every `period_secs` seconds.
+/// When parsed from `/info`, `hash` is recomputed from the served parameters and
+/// info whose claimed hash differs is refused, so a pinned hash pins the public key.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(try_from = "RawChainInfo")]
+pub struct DrandChainInfo {
+    /// Chain hash (hex-encoded)
+    pub hash: String,
+    /// Chain public key (hex-encoded)
+    pub public_key: String,
+    /// Signature scheme of the chain
+    pub scheme: DrandScheme,
+    /// Seconds between rounds
+    pub period_secs: u64,
+    /// Unix time of round 1
+    pub genesis_time: u64,
+}
+
+/// `/info` response as sent by drand nodes
+#[derive(serde::Deserialize)]
+struct RawChainInfo {
+    public_key: String,
+    period: u64,
+    genesis_time: u64,
+    hash: String,
+    #[serde(rename = "groupHash")]
+    group_hash: String,
+    #[serde(rename = "schemeID", default)]
+    scheme_id: Option<String>,
+    #[serde(default)]
+    metadata: Option<RawChainMetadata>,
+}
+
+/// `metadata` of an `/info` response
+#[derive(serde::Deserialize)]
+struct RawChainMetadata {
+    #[serde(rename = "beaconID", default)]
+    beacon_id: String,
+}
+
+/// drand's chain hash: SHA-256 over period, genesis time, public key, group hash
+/// and the beacon ID (omitted for the default beacon)
+fn compute_chain_hash(period: u32, genesis_time: u64, public_key: &[u8], group_hash: &[u8], beacon_id: &str) -> [u8; 32] {
+    use sha2::Digest;
+
+    let mut hasher = Sha256::new();
+    hasher.update(period.to_be_bytes());
+    hasher.update(genesis_time.to_be_bytes());
+    hasher.update(public_key);
+    hasher.update(group_hash);
+    if !beacon_id.is_empty() && beacon_id != "default" {
+        hasher.update(beacon_id.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+impl TryFrom<RawChainInfo> for DrandChainInfo {
+    type Error = String;
+
+    fn try_from(raw: RawChainInfo) -> Result<Self, Self::Error> {
+        // Nodes predating scheme IDs only ran the chained scheme
+        let scheme = match raw.scheme_id.as_deref() {
+            None => DrandScheme::PedersenBlsChained,
+            Some(id) => DrandScheme::from_id(id).ok_or_else(|| format!("unsupported drand scheme {}", id))?,
+        };
+        if raw.period == 0 {
+            return Err("drand period must be non-zero".to_string());
+        }
+        let period = u32::try_from(raw.period).map_err(|_| "drand period out of range".to_string())?;
+        let public_key = hex::decode(&raw.public_key).map_err(|e| format!("invalid public key hex: {}", e))?;
+        let group_hash = hex::decode(&raw.group_hash).map_err(|e| format!("invalid group hash hex: {}", e))?;
+        let beacon_id = raw.metadata.as_ref().map_or("", |metadata| metadata.beacon_id.as_str());
+        let hash = hex::encode(compute_chain_hash(period, raw.genesis_time, &public_key, &group_hash, beacon_id));
+        if !raw.hash.eq_ignore_ascii_case(&hash) {
+            return Err(format!("chain hash {} does not match the served chain info ({})", raw.hash, hash));
+        }
+        Ok(Self {
+            hash,
+            public_key: raw.public_key,
+            scheme,
+            period_secs: raw.period,
+            genesis_time: raw.genesis_time,
+        })
+    }
+}
+
+impl DrandChainInfo {
+    /// League of Entropy mainnet (default chain, chained G2 signatures)
+    pub fn mainnet() -> Self {
+        Self {
+            hash: DRAND_MAINNET_CHAIN_HASH.to_string(),
+            public_key: DRAND_MAINNET_PK_HEX.to_string(),
+            scheme: DrandScheme::PedersenBlsChained,
+            period_secs: 30,
+            genesis_time: 1595431050,
+        }
+    }
+
+    /// League of Entropy quicknet (unchained G1 signatures, 3 second rounds)
+    pub fn quicknet() -> Self {
+        Self {
+            hash: DRAND_QUICKNET_CHAIN_HASH.to_string(),
+            public_key: DRAND_QUICKNET_PK_HEX.to_string(),
+            scheme: DrandScheme::UnchainedOnG1,
+            period_secs: 3,
+            genesis_time: 1692803367,
+        }
+    }
+
+    /// Round that is current at `time` (0 before genesis)
+    pub fn round_at(&self, time: std::time::SystemTime) -> u64 {
+        let now = time
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        match now.checked_sub(self.genesis_time) {
+            Some(elapsed) => elapsed / self.period_secs.max(1) + 1,
+            None => 0,
+        }
+    }
+
+    /// Time at which `round` is produced
+    pub fn time_of_round(&self, round: u64) -> std::time::SystemTime {
+        let offset = round.saturating_sub(1).saturating_mul(self.period_secs);
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs(self.genesis_time.saturating_add(offset))
+    }
+
+    /// Time left until the next round is produced
+    pub fn next_round_in(&self) -> std::time::Duration {
+        let now = std::time::SystemTime::now();
+        self.time_of_round(self.round_at(now) + 1)
+            .duration_since(now)
+            .unwrap_or_default()
+    }
+}
+
 /// drand entropy source configuration
+///
+/// `chain_info` must describe the network behind `api_urls`: every round is
+/// verified against its public key and scheme. Pin it here, or fetch it with
+/// [`DrandEntropy::discover`].
 #[derive(Debug, Clone)]
 pub struct DrandConfig {
     /// Base URLs for drand API (primary and fallbacks)
     pub api_urls: Vec<String>,
     /// Chain hash (identifies which drand network to use)
     pub chain_hash: Option<String>,
+    /// Chain parameters used for verification and round timing
+    pub chain_info: DrandChainInfo,
     /// Cache duration in seconds (default: 30)
     pub cache_duration_secs: u64,
     /// Maximum number of retry attempts for network failures
@@ -298,8 +484,9 @@ pub struct DrandConfig {
     pub timeout_secs: u64,
 }
 
-impl Default for DrandConfig {
-    fn default() -> Self {
+impl DrandConfig {
+    /// League of Entropy mainnet (default chain, chained G2 signatures)
+    pub fn mainnet() -> Self {
         Self {
             api_urls: vec![
                 "https://api.drand.sh".to_string(),
@@ -307,11 +494,28 @@ impl Default for DrandConfig {
                 "https://api2.drand.sh".to_string(),
             ],
             chain_hash: None,
+            chain_info: DrandChainInfo::mainnet(),
             cache_duration_secs: 30,
             max_retries: 3,
             timeout_secs: 10,
         }
     }
+
+    /// League of Entropy quicknet (unchained G1 signatures, 3 second rounds)
+    pub fn quicknet() -> Self {
+        Self {
+            chain_hash: Some(DRAND_QUICKNET_CHAIN_HASH.to_string()),
+            chain_info: DrandChainInfo::quicknet(),
+            cache_duration_secs: 3,
+            ..Self::mainnet()
+        }
+    }
+}
+
+impl Default for DrandConfig {
+    fn default() -> Self {
+        Self::mainnet()
+    }
 }
 
 /// Cached drand entropy value
@@ -361,6 +565,83 @@ impl DrandEntropy {
         }
     }
 
+    /// Create a drand entropy source whose chain info is fetched from `/info`
+    ///
+    /// The first endpoint that answers wins. If `config.chain_hash` is set, the hash
+    /// recomputed from the served info must equal it.
+    pub async fn discover(config: DrandConfig) -> Result<Self, DrandError> {
+        let mut drand = Self::with_config(config);
+        drand.config.chain_info = drand.fetch_chain_info().await?;
+        info!(
+            "Discovered drand chain {} ({:?}, period {}s)",
+            drand.config.chain_info.hash, drand.config.chain_info.scheme, drand.config.chain_info.period_secs
+        );
+        Ok(drand)
+    }
+
+    /// Chain parameters used for verification and round timing
+    pub fn chain_info(&self) -> &DrandChainInfo {
+        &self.config.chain_info
+    }
+
+    /// Fetch the chain info served by the configured endpoints
+    pub async fn fetch_chain_info(&self) -> Result<DrandChainInfo, DrandError> {
+        let mut last_error = None;
+
+        for base_url in &self.config.api_urls {
+            let url = match &self.config.chain_hash {
+                Some(hash) => format!("{}/{}/info", base_url, hash),
+                None => format!("{}/info", base_url),
+            };
+
+            let response = match self.client.get(&url).send().await {
+                Ok(response) if response.status().is_success() => response,
+                Ok(response) => {
+                    let error_msg = format!("drand API returned status: {} from {}", response.status(), base_url);
+                    warn!("{}", error_msg);
+                    last_error = Some(DrandError::ApiError(error_msg));
+                    continue;
+                }
+                Err(e) => {
+                    let error_msg = format!("Network error from {}: {}", base_url, e);
+                    warn!("{}", error_msg);
+                    last_error = Some(DrandError::NetworkError(error_msg));
+                    continue;
+                }
+            };
+
+            match response.json::<DrandChainInfo>().await {
+                Ok(info) => match &self.config.chain_hash {
+                    Some(hash) if !hash.eq_ignore_ascii_case(&info.hash) => {
+                        warn!("Endpoint {} served chain {} instead of {}", base_url, info.hash, hash);
+                        last_error = Some(DrandError::VerificationError(format!(
+                            "Expected chain {}, got {}",
+                            hash, info.hash
+                        )));
+                    }
+                    _ => return Ok(info),
+                },
+                Err(e) => {
+                    let error_msg = format!("Failed to parse chain info from {}: {}", base_url, e);
+                    warn!("{}", error_msg);
+                    last_error = Some(DrandError::ParseError(error_msg));
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| DrandError::NetworkError("All drand endpoints unavailable".to_string())))
+    }
+
+    /// Fetch entropy from a round, sleeping until it is produced if it lies in the future
+    pub async fn wait_for_round(&self, round: u64) -> Result<[u8; 32], DrandError> {
+        let due = self.config.chain_info.time_of_round(round);
+        if let Ok(delay) = due.duration_since(std::time::SystemTime::now()) {
+            debug!("Waiting {:?} for drand round {}", delay, round);
+            tokio::time::sleep(delay + ROUND_PUBLISH_GRACE).await;
+        }
+        self.fetch_round(round).await
+    }
+
     /// Fetch the latest drand randomness
     /// 
     /// This method caches the result and only fetches from the API
@@ -493,7 +774,7 @@ impl DrandEntropy {
             .map_err(|e| DrandError::ParseError(format!("Failed to decode hex: {}", e)))?;
 
         // Validate the entropy quality
-        validate_drand_entropy(drand_response, &randomness_bytes)?;
+        validate_drand_entropy(&self.config, drand_response, &randomness_bytes)?;
 
         if randomness_bytes.len() != 32 {
             return Err(DrandError::ParseError(format!(
@@ -559,6 +840,13 @@ impl DrandEntropy {
                         }
 
                         match response.json::<DrandResponse>().await {
+                            Ok(drand_response) if drand_response.round != round => {
+                                warn!("Endpoint {} answered round {} with round {}", base_url, round, drand_response.round);
+                                last_error = Some(DrandError::VerificationError(format!(
+                                    "Requested round {}, got round {}",
+                                    round, drand_response.round
+                                )));
+                            }
                             Ok(drand_response) => {
                                 // Validate the response
                                 match self.validate_and_process_response(&drand_response).await {
@@ -726,7 +1014,7 @@ impl DrandEntropy {
                                     }
                                 };
                                 
-                                match validate_drand_entropy(&drand_response, &randomness_bytes) {
+                                match validate_drand_entropy(&self.config, &drand_response, &randomness_bytes) {
                                     Ok(()) => results.push((base_url.clone(), EndpointHealth::Healthy)),
                                     Err(e) => results.push((base_url.clone(), EndpointHealth::Invalid(format!("Validation failed: {}", e)))),
                                 }
@@ -764,6 +1052,8 @@ pub enum DrandError {
     HkdfError,
     /// OS random generation failed (used in fallback mode)
     OsRandomError(String),
+    /// Beacon signature or randomness did not verify against the chain
+    VerificationError(String),
 }
 
 impl std::fmt::Display for DrandError {
@@ -774,6 +1064,7 @@ impl std::fmt::Display for DrandError {
             DrandError::ParseError(e) => write!(f, "drand parse error: {}", e),
             DrandError::HkdfError => write!(f, "HKDF expansion failed"),
             DrandError::OsRandomError(e) => write!(f, "OS random generation failed: {}", e),
+            DrandError::VerificationError(e) => write!(f, "drand verification failed: {}", e),
         }
     }
 }
@@ -856,4 +1147,262 @@ mod tests {
         
         assert_ne!(entropy1, entropy2, "Different users should get different entropy");
     }
+
+    type Signer = Box<dyn Fn(&[u8]) -> Vec<u8>>;
+
+    const MOCK_GROUP_HASH: [u8; 32] = [0xCD; 32];
+
+    /// Local drand chain that signs its rounds with a test key
+    struct MockChain {
+        config: DrandConfig,
+        rounds: Vec<serde_json::Value>,
+    }
+
+    impl MockChain {
+        fn new(scheme: DrandScheme, rounds: u64) -> Self {
+            use sha2::Digest;
+
+            let ikm = [0x42u8; 32];
+            let (public_key, sign): (Vec<u8>, Signer) = match scheme {
+                DrandScheme::UnchainedOnG1 => {
+                    let sk = blst::min_sig::SecretKey::key_gen(&ikm, &[]).unwrap();
+                    (sk.sk_to_pk().to_bytes().to_vec(), Box::new(move |m| sk.sign(m, DST_G1, &[]).to_bytes().to_vec()))
+                }
+                _ => {
+                    let sk = blst::min_pk::SecretKey::key_gen(&ikm, &[]).unwrap();
+                    (sk.sk_to_pk().to_bytes().to_vec(), Box::new(move |m| sk.sign(m, DST_G2, &[]).to_bytes().to_vec()))
+                }
+            };
+
+            let mut previous = vec![0x11u8; scheme.signature_len()];
+            let rounds = (1..=rounds)
+                .map(|round| {
+                    let mut hasher = Sha256::new();
+                    if scheme == DrandScheme::PedersenBlsChained {
+                        hasher.update(&previous);
+                    }
+                    hasher.update(round.to_be_bytes());
+                    let signature = sign(&hasher.finalize());
+                    let body = serde_json::json!({
+                        "round": round,
+                        "randomness": hex::encode(Sha256::digest(&signature)),
+                        "signature": hex::encode(&signature),
+                        "previous_signature": hex::encode(&previous),
+                    });
+                    previous = signature;
+                    body
+                })
+                .collect::<Vec<_>>();
+
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let config = DrandConfig {
+                api_urls: Vec::new(),
+                chain_hash: None,
+                chain_info: DrandChainInfo {
+                    hash: String::new(),
+                    public_key: hex::encode(public_key),
+                    scheme,
+                    period_secs: 30,
+                    genesis_time: now - 30 * (rounds.len() as u64 - 1),
+                },
+                cache_duration_secs: 30,
+                max_retries: 1,
+                timeout_secs: 5,
+            };
+            Self { config, rounds }
+        }
+
+        /// Serve the rounds over HTTP and return a client pointed at them
+        async fn serve(self) -> DrandEntropy {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let base_url = format!("http://{}", listener.local_addr().unwrap());
+            let rounds = Arc::new(self.rounds);
+            let mut config = self.config;
+            let chain = &mut config.chain_info;
+            chain.hash = hex::encode(compute_chain_hash(
+                chain.period_secs as u32,
+                chain.genesis_time,
+                &hex::decode(&chain.public_key).unwrap(),
+                &MOCK_GROUP_HASH,
+                "mock",
+            ));
+            let info = Arc::new(serde_json::json!({
+                "public_key": chain.public_key,
+                "period": chain.period_secs,
+                "genesis_time": chain.genesis_time,
+                "hash": chain.hash,
+                "groupHash": hex::encode(MOCK_GROUP_HASH),
+                "schemeID": chain.scheme.id(),
+                "metadata": { "beaconID": "mock" },
+            }));
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let rounds = rounds.clone();
+                    let info = info.clone();
+                    tokio::spawn(async move {
+                        let mut request = Vec::new();
+                        let mut chunk = [0u8; 1024];
+                        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                            match stream.read(&mut chunk).await {
+                                Ok(0) | Err(_) => return,
+                                Ok(n) => request.extend_from_slice(&chunk[..n]),
+                            }
+                        }
+                        let request = String::from_utf8_lossy(&request);
+                        let path = request.split_whitespace().nth(1).unwrap_or("");
+                        let body = match path.strip_prefix("/public/") {
+                            Some("latest") => rounds.last(),
+                            Some(n) => n.parse::<usize>().ok().and_then(|n| rounds.get(n.wrapping_sub(1))),
+                            None if path.ends_with("/info") => Some(&*info),
+                            None => None,
+                        };
+                        let response = match body {
+                            Some(body) => {
+                                let body = body.to_string();
+                                format!(
+                                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                                    body.len(),
+                                    body
+                                )
+                            }
+                            None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+                        };
+                        let _ = stream.write_all(response.as_bytes()).await;
+                    });
+                }
+            });
+
+            config.api_urls = vec![base_url];
+            DrandEntropy::with_config(config)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_chained_rounds_verify() {
+        let chain = MockChain::new(DrandScheme::PedersenBlsChained, 5);
+        let expected: [u8; 32] = hex::decode(chain.rounds[2]["randomness"].as_str().unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let drand = chain.serve().await;
+
+        assert!(drand.get_entropy().await.is_ok());
+        assert_eq!(drand.cached_round().await, Some(5));
+        assert_eq!(drand.fetch_round(3).await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_mock_unchained_rounds_verify() {
+        for scheme in [DrandScheme::PedersenBlsUnchained, DrandScheme::UnchainedOnG1] {
+            let drand = MockChain::new(scheme, 3).serve().await;
+            assert!(drand.fetch_round(2).await.is_ok(), "{:?} round must verify", scheme);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_rejects_bad_signatures() {
+        let is_verification_error = |r: Result<[u8; 32], DrandError>| matches!(r, Err(DrandError::VerificationError(_)));
+
+        // Chained round presented with the wrong previous signature
+        let mut chain = MockChain::new(DrandScheme::PedersenBlsChained, 3);
+        chain.rounds[1]["previous_signature"] = chain.rounds[2]["signature"].clone();
+        let drand = chain.serve().await;
+        assert!(is_verification_error(drand.fetch_round(2).await));
+        assert!(drand.fetch_round(3).await.is_ok());
+
+        // Signature from another chain
+        let mut chain = MockChain::new(DrandScheme::UnchainedOnG1, 2);
+        chain.config.chain_info.public_key = DRAND_QUICKNET_PK_HEX.to_string();
+        assert!(is_verification_error(chain.serve().await.get_entropy().await));
+
+        // Randomness not derived from the signature
+        let mut chain = MockChain::new(DrandScheme::PedersenBlsUnchained, 2);
+        chain.rounds[1]["randomness"] = serde_json::json!(hex::encode((0u8..32).collect::<Vec<_>>()));
+        assert!(is_verification_error(chain.serve().await.get_entropy().await));
+
+        // Endpoint answers a different round than requested
+        let mut chain = MockChain::new(DrandScheme::PedersenBlsUnchained, 2);
+        chain.rounds[0] = chain.rounds[1].clone();
+        assert!(is_verification_error(chain.serve().await.fetch_round(1).await));
+    }
+
+    #[test]
+    fn test_round_arithmetic() {
+        let quicknet = DrandChainInfo::quicknet();
+        let genesis = quicknet.time_of_round(1);
+        assert_eq!(quicknet.round_at(genesis), 1);
+        assert_eq!(quicknet.round_at(genesis + std::time::Duration::from_secs(2)), 1);
+        assert_eq!(quicknet.round_at(genesis + std::time::Duration::from_secs(3)), 2);
+        assert_eq!(quicknet.round_at(genesis - std::time::Duration::from_secs(1)), 0);
+        assert_eq!(quicknet.time_of_round(1001), genesis + std::time::Duration::from_secs(3000));
+        assert!(quicknet.next_round_in() <= std::time::Duration::from_secs(3));
+
+        let mainnet = DrandChainInfo::mainnet();
+        let round = mainnet.round_at(std::time::SystemTime::now());
+        assert_eq!(mainnet.round_at(mainnet.time_of_round(round)), round);
+    }
+
+    #[tokio::test]
+    async fn test_discover_and_wait_for_round() {
+        let mut chain = MockChain::new(DrandScheme::UnchainedOnG1, 4);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        // Round 4 is produced a second from now
+        chain.config.chain_info.period_secs = 1;
+        chain.config.chain_info.genesis_time = now - 2;
+        let mut config = chain.serve().await.config.clone();
+        let served = config.chain_info.clone();
+        config.chain_info = DrandChainInfo::mainnet();
+
+        let drand = DrandEntropy::discover(config.clone()).await.unwrap();
+        assert_eq!(*drand.chain_info(), served);
+
+        let started = std::time::Instant::now();
+        assert!(drand.wait_for_round(4).await.is_ok());
+        assert!(started.elapsed() >= ROUND_PUBLISH_GRACE, "Must wait for a future round");
+
+        // A pinned chain hash must match the served info
+        config.chain_hash = Some(served.hash.clone());
+        assert!(DrandEntropy::discover(config.clone()).await.is_ok());
+        config.chain_hash = Some(DRAND_QUICKNET_CHAIN_HASH.to_string());
+        assert!(matches!(
+            DrandEntropy::discover(config).await,
+            Err(DrandError::VerificationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_chain_hash_is_recomputed() {
+        let mainnet = DrandChainInfo::mainnet();
+        let quicknet = DrandChainInfo::quicknet();
+        let mainnet_group = hex::decode("176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a").unwrap();
+        let quicknet_group = hex::decode("f477d5c89f21a17c863a7f937c6a6d15859414d2be09cd448d4279af331c5d3e").unwrap();
+        assert_eq!(
+            hex::encode(compute_chain_hash(30, mainnet.genesis_time, &hex::decode(&mainnet.public_key).unwrap(), &mainnet_group, "default")),
+            DRAND_MAINNET_CHAIN_HASH
+        );
+        assert_eq!(
+            hex::encode(compute_chain_hash(3, quicknet.genesis_time, &hex::decode(&quicknet.public_key).unwrap(), &quicknet_group, "quicknet")),
+            DRAND_QUICKNET_CHAIN_HASH
+        );
+
+        // Info claiming the quicknet hash with another public key is refused
+        let forged = serde_json::json!({
+            "public_key": hex::encode([0x42u8; 96]),
+            "period": 3,
+            "genesis_time": quicknet.genesis_time,
+            "hash": DRAND_QUICKNET_CHAIN_HASH,
+            "groupHash": hex::encode(&quicknet_group),
+            "schemeID": DrandScheme::UnchainedOnG1.id(),
+            "metadata": { "beaconID": "quicknet" },
+        });
+        assert!(serde_json::from_value::<DrandChainInfo>(forged).is_err());
+    }
 }
@@ -0,0 +1,619 @@
+//! Entropy Sources and Combiner
+//!
+//! Every place that gathers randomness (drand, the Cloudflare worker, the swarm,
+//! peer contributions, the OS) implements [`EntropySource`]. An [`EntropyCombiner`]
+//! fetches a set of sources concurrently and merges whatever succeeded with an
+//! HKDF-SHA256 extractor, so the output is unpredictable as long as one
+//! contributing source is.
+//!
+//! Each output carries an [`EntropyProvenance`] recording which sources contributed
+//! and which failed, and the combiner refuses to produce output unless enough
+//! independent sources contributed. Every sample also passes through its source's
+//! continuous health tests ([`crate::health`]); a failing source is quarantined.
+//! Sources that hand out the same sample until refreshed (fixed values, cached
+//! beacons) declare it with [`EntropySource::repeats_samples`]: each distinct sample
+//! is tested once, and they skip startup since there is no stream of noise to warm up.
+//!
+//! ## Usage
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use zks_crypt::entropy::{EntropyCombiner, FixedEntropy, OsEntropy};
+//! use zks_crypt::drand::DrandEntropy;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let combiner = EntropyCombiner::new()
+//!     .with_source(Arc::new(OsEntropy))
+//!     .with_source(Arc::new(DrandEntropy::new()))
+//!     .with_source(Arc::new(FixedEntropy::new("peer", [7u8; 32])))
+//!     .require_independent(2);
+//!
+//! let output = combiner.combine().await?;
+//! println!("contributed: {:?}", output.provenance().contributed());
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use hkdf::Hkdf;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::{debug, warn};
+use crate::health::{HealthConfig, HealthFailure, HealthMetrics, HealthMonitor, HealthStatus};
+use zeroize::{Zeroize, Zeroizing};
+
+/// A source of 32-byte entropy samples
+#[async_trait]
+pub trait EntropySource: Send + Sync {
+    /// Short name recorded in provenance
+    fn name(&self) -> &str;
+
+    /// Sources with the same trust domain count as one independent source
+    ///
+    /// Defaults to [`name`](Self::name); override it when several sources share an
+    /// operator or device (e.g. two endpoints of the same service).
+    fn trust_domain(&self) -> &str {
+        self.name()
+    }
+
+    /// Whether `fetch` returns the same sample again until the source is refreshed
+    ///
+    /// Such samples are health tested once, when they first appear, rather than on
+    /// every fetch, where the repeats alone would fail the tests.
+    fn repeats_samples(&self) -> bool {
+        false
+    }
+
+    /// Fetch a fresh sample
+    async fn fetch(&self) -> Result<[u8; 32], EntropySourceError>;
+}
+
+/// Errors reported by an entropy source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntropySourceError {
+    /// Source could not be reached or is not ready
+    Unavailable(String),
+    /// Source answered with unusable data
+    Invalid(String),
+    /// Source failed its health tests
+    Unhealthy(HealthFailure),
+}
+
+impl std::fmt::Display for EntropySourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntropySourceError::Unavailable(e) => write!(f, "entropy source unavailable: {}", e),
+            EntropySourceError::Invalid(e) => write!(f, "entropy source returned invalid data: {}", e),
+            EntropySourceError::Unhealthy(failure) => write!(f, "entropy source unhealthy: {}", failure),
+        }
+    }
+}
+
+impl std::error::Error for EntropySourceError {}
+
+/// Which sources went into a combined output
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntropyProvenance {
+    contributed: Vec<(String, String)>,
+    failed: Vec<(String, EntropySourceError)>,
+}
+
+impl EntropyProvenance {
+    /// Names of the sources that contributed, in combiner order
+    pub fn contributed(&self) -> Vec<&str> {
+        self.contributed.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Sources that failed and why
+    pub fn failed(&self) -> &[(String, EntropySourceError)] {
+        &self.failed
+    }
+
+    /// Number of distinct trust domains among the contributors
+    pub fn independent_sources(&self) -> usize {
+        self.contributed
+            .iter()
+            .map(|(_, domain)| domain.as_str())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+}
+
+/// Output of an [`EntropyCombiner`]
+pub struct CombinedEntropy {
+    entropy: Zeroizing<[u8; 32]>,
+    provenance: EntropyProvenance,
+}
+
+impl CombinedEntropy {
+    /// The combined 32 bytes
+    pub fn entropy(&self) -> &[u8; 32] {
+        &self.entropy
+    }
+
+    /// Which sources contributed and which failed
+    pub fn provenance(&self) -> &EntropyProvenance {
+        &self.provenance
+    }
+}
+
+impl std::fmt::Debug for CombinedEntropy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CombinedEntropy")
+            .field("entropy", &"<redacted>")
+            .field("provenance", &self.provenance)
+            .finish()
+    }
+}
+
+/// Errors that can occur when combining entropy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CombineError {
+    /// Fewer independent sources contributed than the policy requires
+    InsufficientSources {
+        /// Independent sources required
+        required: usize,
+        /// What was gathered
+        provenance: EntropyProvenance,
+    },
+}
+
+impl std::fmt::Display for CombineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CombineError::InsufficientSources { required, provenance } => write!(
+                f,
+                "{} independent entropy sources required, {} contributed ({} failed)",
+                required,
+                provenance.independent_sources(),
+                provenance.failed().len()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CombineError {}
+
+/// Samples from one source's fetches in a `combine` call, startup blocks first
+type FetchedSamples = Zeroizing<Vec<[u8; 32]>>;
+
+/// Health test state of one combiner source
+struct SourceHealth {
+    monitor: HealthMonitor,
+    /// Digest of the last sample tested, for sources that repeat samples
+    last_tested: Option<[u8; 32]>,
+}
+
+impl SourceHealth {
+    fn new(config: HealthConfig) -> Self {
+        Self {
+            monitor: HealthMonitor::new(config),
+            last_tested: None,
+        }
+    }
+
+    /// Fetches needed before a sample can be used: the startup blocks plus one
+    fn fetches_needed(&self, repeats: bool) -> usize {
+        match self.monitor.status() {
+            HealthStatus::Startup if !repeats => self.monitor.startup_remaining().div_ceil(32).max(1) + 1,
+            _ => 1,
+        }
+    }
+
+    /// Health test one fetch and return the sample that may be used
+    ///
+    /// All but the last sample are startup blocks, which are tested and dropped.
+    fn admit(&mut self, repeats: bool, samples: &[[u8; 32]]) -> Result<[u8; 32], EntropySourceError> {
+        let Some((sample, startup)) = samples.split_last() else {
+            return Err(EntropySourceError::Unavailable("no sample".to_string()));
+        };
+
+        if repeats {
+            let digest: [u8; 32] = Sha256::digest(sample).into();
+            if self.last_tested != Some(digest) {
+                self.monitor.check(sample).map_err(EntropySourceError::Unhealthy)?;
+                self.last_tested = Some(digest);
+            }
+            return Ok(*sample);
+        }
+
+        for block in startup {
+            self.monitor.check(block).map_err(EntropySourceError::Unhealthy)?;
+        }
+        match self.monitor.check(sample) {
+            Ok(HealthStatus::Healthy) => Ok(*sample),
+            // Startup samples are tested but never used
+            Ok(_) => Err(EntropySourceError::Unavailable("health tests still in startup".to_string())),
+            Err(failure) => Err(EntropySourceError::Unhealthy(failure)),
+        }
+    }
+}
+
+/// Merges any set of entropy sources into one 32-byte output
+pub struct EntropyCombiner {
+    sources: Vec<Arc<dyn EntropySource>>,
+    /// Health test state, one per source
+    health: Vec<Mutex<SourceHealth>>,
+    health_config: HealthConfig,
+    min_independent: usize,
+}
+
+impl EntropyCombiner {
+    /// Create a combiner without sources that requires one contributor
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            health: Vec::new(),
+            health_config: HealthConfig::default(),
+            min_independent: 1,
+        }
+    }
+
+    /// Add a source
+    pub fn with_source(mut self, source: Arc<dyn EntropySource>) -> Self {
+        self.sources.push(source);
+        self.health.push(Mutex::new(SourceHealth::new(self.health_config)));
+        self
+    }
+
+    /// Use custom health test parameters (resets every source's health state)
+    pub fn with_health_config(mut self, config: HealthConfig) -> Self {
+        self.health_config = config;
+        self.health = self.sources.iter().map(|_| Mutex::new(SourceHealth::new(config))).collect();
+        self
+    }
+
+    /// Health test metrics per source, in combiner order
+    pub fn health_metrics(&self) -> Vec<(String, HealthMetrics)> {
+        self.sources
+            .iter()
+            .zip(&self.health)
+            .map(|(source, health)| (source.name().to_string(), lock(health).monitor.metrics()))
+            .collect()
+    }
+
+    /// Lift the quarantine of every source named `name`
+    pub fn release(&self, name: &str) {
+        for (source, health) in self.sources.iter().zip(&self.health) {
+            if source.name() == name {
+                let mut health = lock(health);
+                health.monitor.release();
+                health.last_tested = None;
+            }
+        }
+    }
+
+    /// Require at least `count` independent sources to contribute
+    pub fn require_independent(mut self, count: usize) -> Self {
+        self.min_independent = count.max(1);
+        self
+    }
+
+    /// Fetch all sources concurrently and combine those that succeed
+    ///
+    /// A source still in startup is fetched repeatedly within the call until its
+    /// startup blocks have passed, then once more for the sample that is used.
+    pub async fn combine(&self) -> Result<CombinedEntropy, CombineError> {
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, source) in self.sources.iter().enumerate() {
+            let fetches = {
+                let health = lock(&self.health[index]);
+                // Quarantined sources are not even asked
+                if health.monitor.status() == HealthStatus::Quarantined {
+                    continue;
+                }
+                health.fetches_needed(source.repeats_samples())
+            };
+            let source = source.clone();
+            tasks.spawn(async move {
+                let mut samples = Zeroizing::new(Vec::with_capacity(fetches));
+                for _ in 0..fetches {
+                    match source.fetch().await {
+                        Ok(sample) => samples.push(sample),
+                        Err(e) => return (index, Err(e)),
+                    }
+                }
+                (index, Ok(samples))
+            });
+        }
+
+        let mut results: Vec<Option<Result<FetchedSamples, EntropySourceError>>> =
+            (0..self.sources.len()).map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, result)) => results[index] = Some(result),
+                Err(e) => warn!("Entropy source task failed: {}", e),
+            }
+        }
+
+        // Extractor input: each contributor's name and sample, in combiner order
+        let mut provenance = EntropyProvenance::default();
+        let mut ikm = Zeroizing::new(Vec::with_capacity(self.sources.len() * 64));
+        for ((source, result), health) in self.sources.iter().zip(results).zip(&self.health) {
+            let mut health = lock(health);
+            let result = match result {
+                Some(Ok(samples)) => health.admit(source.repeats_samples(), &samples),
+                Some(Err(e)) => Err(e),
+                None if health.monitor.status() == HealthStatus::Quarantined => {
+                    Err(EntropySourceError::Unhealthy(HealthFailure::Quarantined))
+                }
+                // A source whose task panicked never reported back
+                None => Err(EntropySourceError::Unavailable("task aborted".to_string())),
+            };
+            match result {
+                Ok(mut sample) => {
+                    ikm.extend_from_slice(&(source.name().len() as u32).to_be_bytes());
+                    ikm.extend_from_slice(source.name().as_bytes());
+                    ikm.extend_from_slice(&sample);
+                    sample.zeroize();
+                    provenance
+                        .contributed
+                        .push((source.name().to_string(), source.trust_domain().to_string()));
+                }
+                Err(e) => {
+                    warn!("Entropy source {} failed: {}", source.name(), e);
+                    provenance.failed.push((source.name().to_string(), e));
+                }
+            }
+        }
+
+        if provenance.independent_sources() < self.min_independent {
+            warn!(
+                "🚨 Only {} independent entropy sources contributed, {} required",
+                provenance.independent_sources(),
+                self.min_independent
+            );
+            return Err(CombineError::InsufficientSources {
+                required: self.min_independent,
+                provenance,
+            });
+        }
+
+        let hk = Hkdf::<Sha256>::new(Some(b"zks-entropy-combiner"), &ikm);
+        let mut entropy = Zeroizing::new([0u8; 32]);
+        hk.expand(b"combined-entropy", entropy.as_mut())
+            .expect("HKDF expansion should not fail");
+
+        debug!("🎲 Combined entropy from {:?}", provenance.contributed());
+        Ok(CombinedEntropy { entropy, provenance })
+    }
+}
+
+fn lock(health: &Mutex<SourceHealth>) -> MutexGuard<'_, SourceHealth> {
+    health.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Default for EntropyCombiner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Operating system CSPRNG
+pub struct OsEntropy;
+
+#[async_trait]
+impl EntropySource for OsEntropy {
+    fn name(&self) -> &str {
+        "os"
+    }
+
+    async fn fetch(&self) -> Result<[u8; 32], EntropySourceError> {
+        let mut sample = [0u8; 32];
+        getrandom::getrandom(&mut sample)
+            .map_err(|e| EntropySourceError::Unavailable(format!("CSPRNG unavailable: {}", e)))?;
+        Ok(sample)
+    }
+}
+
+/// Entropy already in hand, such as a peer contribution or ML-KEM shared secret
+pub struct FixedEntropy {
+    name: String,
+    sample: Zeroizing<[u8; 32]>,
+}
+
+impl FixedEntropy {
+    /// Wrap a sample under the given name
+    pub fn new(name: impl Into<String>, sample: [u8; 32]) -> Self {
+        Self {
+            name: name.into(),
+            sample: Zeroizing::new(sample),
+        }
+    }
+}
+
+#[async_trait]
+impl EntropySource for FixedEntropy {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn repeats_samples(&self) -> bool {
+        true
+    }
+
+    async fn fetch(&self) -> Result<[u8; 32], EntropySourceError> {
+        Ok(*self.sample)
+    }
+}
+
+#[async_trait]
+impl EntropySource for crate::drand::DrandEntropy {
+    fn name(&self) -> &str {
+        "drand"
+    }
+
+    /// The latest round is cached and served until the next one
+    fn repeats_samples(&self) -> bool {
+        true
+    }
+
+    async fn fetch(&self) -> Result<[u8; 32], EntropySourceError> {
+        self.get_entropy().await.map_err(|e| match e {
+            crate::drand::DrandError::NetworkError(e) | crate::drand::DrandError::ApiError(e) => {
+                EntropySourceError::Unavailable(e)
+            }
+            e => EntropySourceError::Invalid(e.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Failing;
+
+    /// A fixed sample that passes the health tests
+    fn sample(seed: u8) -> [u8; 32] {
+        std::array::from_fn(|i| seed.wrapping_add((i as u8).wrapping_mul(37)))
+    }
+
+    #[async_trait]
+    impl EntropySource for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn fetch(&self) -> Result<[u8; 32], EntropySourceError> {
+            Err(EntropySourceError::Unavailable("offline".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_combiner_records_provenance() {
+        let combiner = EntropyCombiner::new()
+            .with_source(Arc::new(FixedEntropy::new("peer-a", sample(1))))
+            .with_source(Arc::new(Failing))
+            .with_source(Arc::new(FixedEntropy::new("peer-b", sample(2))));
+
+        let first = combiner.combine().await.unwrap();
+        assert_eq!(first.provenance().contributed(), vec!["peer-a", "peer-b"]);
+        assert_eq!(first.provenance().failed()[0].0, "failing");
+        assert_eq!(first.provenance().independent_sources(), 2);
+
+        // Deterministic for fixed inputs, and every contributor matters
+        assert_eq!(first.entropy(), combiner.combine().await.unwrap().entropy());
+        let other = EntropyCombiner::new()
+            .with_source(Arc::new(FixedEntropy::new("peer-a", sample(1))))
+            .with_source(Arc::new(FixedEntropy::new("peer-b", sample(3))))
+            .combine()
+            .await
+            .unwrap();
+        assert_ne!(first.entropy(), other.entropy());
+    }
+
+    #[tokio::test]
+    async fn test_combiner_enforces_independent_sources() {
+        struct SameOperator(&'static str);
+
+        #[async_trait]
+        impl EntropySource for SameOperator {
+            fn name(&self) -> &str {
+                self.0
+            }
+
+            fn trust_domain(&self) -> &str {
+                "worker"
+            }
+
+            async fn fetch(&self) -> Result<[u8; 32], EntropySourceError> {
+                Ok(sample(9))
+            }
+        }
+
+        let combiner = EntropyCombiner::new()
+            .with_source(Arc::new(SameOperator("worker-eu")))
+            .with_source(Arc::new(SameOperator("worker-us")))
+            .with_source(Arc::new(Failing))
+            .require_independent(2);
+        match combiner.combine().await {
+            Err(CombineError::InsufficientSources { required, provenance }) => {
+                assert_eq!(required, 2);
+                assert_eq!(provenance.contributed().len(), 2);
+                assert_eq!(provenance.independent_sources(), 1);
+            }
+            other => panic!("Expected InsufficientSources, got {:?}", other),
+        }
+
+        let combiner = combiner.with_source(Arc::new(OsEntropy));
+        assert_eq!(combiner.combine().await.unwrap().provenance().independent_sources(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_samples_are_tested_once() {
+        // seed[0] recurs in the seed, so every repeat would feed the APT window
+        let mut seed = sample(5);
+        seed[17] = seed[0];
+        let combiner = EntropyCombiner::new().with_source(Arc::new(FixedEntropy::new("swarm", seed)));
+        for _ in 0..40 {
+            assert_eq!(combiner.combine().await.unwrap().provenance().contributed(), vec!["swarm"]);
+        }
+        let metrics = combiner.health_metrics();
+        assert_eq!(metrics[0].1.samples_tested, 32);
+        assert_eq!(metrics[0].1.status, HealthStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_startup_blocks_are_not_used() {
+        struct Counting(std::sync::atomic::AtomicU8);
+
+        #[async_trait]
+        impl EntropySource for Counting {
+            fn name(&self) -> &str {
+                "counting"
+            }
+
+            async fn fetch(&self) -> Result<[u8; 32], EntropySourceError> {
+                Ok(sample(self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst)))
+            }
+        }
+
+        let source = Arc::new(Counting(Default::default()));
+        let combiner = EntropyCombiner::new().with_source(source.clone());
+        let first = combiner.combine().await.unwrap();
+        assert_eq!(source.0.load(std::sync::atomic::Ordering::SeqCst), 2, "Startup block fetched and dropped");
+        assert_eq!(combiner.health_metrics()[0].1.samples_tested, 64);
+
+        // The output matches a combiner fed only the second block
+        let expected = EntropyCombiner::new()
+            .with_source(Arc::new(FixedEntropy::new("counting", sample(1))))
+            .combine()
+            .await
+            .unwrap();
+        assert_eq!(first.entropy(), expected.entropy());
+
+        combiner.combine().await.unwrap();
+        assert_eq!(source.0.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_stuck_source_is_quarantined() {
+        let combiner = EntropyCombiner::new()
+            .with_source(Arc::new(FixedEntropy::new("stuck", [0x42; 32])))
+            .with_source(Arc::new(FixedEntropy::new("peer", sample(4))));
+
+        let combined = combiner.combine().await.unwrap();
+        assert_eq!(combined.provenance().contributed(), vec!["peer"]);
+        assert!(matches!(
+            combined.provenance().failed()[0].1,
+            EntropySourceError::Unhealthy(HealthFailure::RepetitionCount { .. })
+        ));
+
+        // Quarantined sources stay out until released
+        let combined = combiner.combine().await.unwrap();
+        assert!(matches!(
+            combined.provenance().failed()[0].1,
+            EntropySourceError::Unhealthy(HealthFailure::Quarantined)
+        ));
+        let metrics = combiner.health_metrics();
+        assert_eq!(metrics[0].0, "stuck");
+        assert_eq!(metrics[0].1.status, HealthStatus::Quarantined);
+        assert_eq!(metrics[0].1.samples_tested, 6);
+        assert_eq!(metrics[1].1.status, HealthStatus::Healthy);
+
+        combiner.release("stuck");
+        assert_eq!(combiner.health_metrics()[0].1.status, HealthStatus::Startup);
+    }
+}
@@ -0,0 +1,373 @@
+//! Versioned Ciphertext Envelope
+//!
+//! Self-describing header that prefixes every [`WasifVernam`](crate::wasif_vernam::WasifVernam)
+//! ciphertext so the receiver never has to guess how a message was built.
+//!
+//! # Layout (version 1)
+//! ```text
+//! version (1) | mode (1) | flags (1) | aead (1) | key_epoch (4, BE) | nonce (12) | key_offset (8, BE)
+//! ```
+//!
+//! The 12-byte nonce is always the sender's packet counter. XChaCha20-Poly1305
+//! envelopes append their 24-byte random AEAD nonce after `key_offset`.
+//!
+//! # Legacy envelopes (version 0)
+//! Envelopes produced before versioning were `nonce (12) || key_offset (8)`.
+//! Their nonce always started with four zero bytes, so a leading `0x00`
+//! unambiguously identifies them. They are still accepted for decryption so
+//! mixed deployments can upgrade, but they carry no mode, epoch or flags.
+//!
+//! Any other version byte is rejected.
+
+use alloc::{vec, vec::Vec};
+use crate::aead_backend::{AeadAlgorithm, XNONCE_LEN};
+
+/// Current envelope version
+pub const ENVELOPE_VERSION: u8 = 0x01;
+
+/// Version reported for pre-versioning envelopes
+pub const LEGACY_ENVELOPE_VERSION: u8 = 0x00;
+
+/// Length of a version 1 envelope header
+pub const ENVELOPE_HEADER_LEN: usize = 28;
+
+/// Length of a version 1 envelope header carrying an XChaCha20 nonce
+pub const XNONCE_ENVELOPE_HEADER_LEN: usize = ENVELOPE_HEADER_LEN + XNONCE_LEN;
+
+/// Length of a legacy (version 0) envelope header
+pub const LEGACY_ENVELOPE_HEADER_LEN: usize = 20;
+
+/// Flag: ciphertext bytes were permuted by the scrambler
+pub const FLAG_SCRAMBLED: u8 = 0x01;
+
+/// Flag: body is a [`KeyUpdate`](crate::rekey::KeyUpdate), not application data
+pub const FLAG_KEY_UPDATE: u8 = 0x02;
+
+/// Flag: body is length-prefixed and padded, see [`crate::padding`]
+pub const FLAG_PADDED: u8 = 0x04;
+
+/// Flag: a key commitment follows the tag, see [`crate::commitment`]
+pub const FLAG_COMMITTED: u8 = 0x08;
+
+/// All flags understood by this build; anything else is rejected
+pub const KNOWN_FLAGS: u8 = FLAG_SCRAMBLED | FLAG_KEY_UPDATE | FLAG_PADDED | FLAG_COMMITTED;
+
+/// XOR layer applied underneath the AEAD
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CipherMode {
+    /// No XOR layer, AEAD only
+    AeadOnly = 0x00,
+    /// TRUE OTP keystream from the synchronized Vernam buffer
+    TrueOtp = 0x01,
+    /// HKDF keystream derived from the swarm seed (legacy, decryption only)
+    HkdfXor = 0x02,
+    /// Genuine one-time pad from a pre-shared [`PadFile`](crate::pad::PadFile)
+    PadOtp = 0x03,
+    /// ChaCha20 keystream keyed from the swarm seed, see [`crate::keystream`]
+    StreamXor = 0x04,
+}
+
+impl CipherMode {
+    /// Convert from the wire byte
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0x00 => Some(CipherMode::AeadOnly),
+            0x01 => Some(CipherMode::TrueOtp),
+            0x02 => Some(CipherMode::HkdfXor),
+            0x03 => Some(CipherMode::PadOtp),
+            0x04 => Some(CipherMode::StreamXor),
+            _ => None,
+        }
+    }
+}
+
+/// Errors produced while parsing an envelope header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvelopeError {
+    /// Data is shorter than the header it claims to carry
+    TooShort,
+    /// Version byte is not understood by this build
+    UnsupportedVersion(u8),
+    /// Mode byte is not understood by this build
+    UnknownMode(u8),
+    /// Flags contain bits not understood by this build
+    UnknownFlags(u8),
+    /// AEAD byte is not understood by this build
+    UnknownAead(u8),
+}
+
+impl core::fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EnvelopeError::TooShort => write!(f, "Envelope is too short"),
+            EnvelopeError::UnsupportedVersion(v) => write!(f, "Unsupported envelope version: 0x{:02x}", v),
+            EnvelopeError::UnknownMode(m) => write!(f, "Unknown envelope mode: 0x{:02x}", m),
+            EnvelopeError::UnknownFlags(bits) => write!(f, "Unknown envelope flags: 0x{:02x}", bits),
+            EnvelopeError::UnknownAead(a) => write!(f, "Unknown envelope AEAD: 0x{:02x}", a),
+        }
+    }
+}
+
+impl core::error::Error for EnvelopeError {}
+
+/// Parsed envelope header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvelopeHeader {
+    /// Envelope version ([`LEGACY_ENVELOPE_VERSION`] for pre-versioning envelopes)
+    pub version: u8,
+    /// XOR layer, `None` for legacy envelopes which did not record it
+    pub mode: Option<CipherMode>,
+    /// Flag bits (see `FLAG_*`)
+    pub flags: u8,
+    /// AEAD that sealed the body
+    pub aead: AeadAlgorithm,
+    /// Key epoch the message was sealed under
+    pub key_epoch: u32,
+    /// AEAD nonce
+    pub nonce: [u8; 12],
+    /// Keystream offset of the XOR layer
+    pub key_offset: u64,
+    /// Random 192-bit AEAD nonce, present only for XChaCha20-Poly1305
+    pub xnonce: Option<[u8; XNONCE_LEN]>,
+}
+
+impl EnvelopeHeader {
+    /// Create a current-version header sealed with ChaCha20-Poly1305
+    pub fn new(mode: CipherMode, flags: u8, key_epoch: u32, nonce: [u8; 12], key_offset: u64) -> Self {
+        Self {
+            version: ENVELOPE_VERSION,
+            mode: Some(mode),
+            flags,
+            aead: AeadAlgorithm::ChaCha20Poly1305,
+            key_epoch,
+            nonce,
+            key_offset,
+            xnonce: None,
+        }
+    }
+
+    /// Record the AEAD that sealed the body
+    ///
+    /// `xnonce` must be `Some` exactly when `aead` is XChaCha20-Poly1305.
+    pub fn with_aead(mut self, aead: AeadAlgorithm, xnonce: Option<[u8; XNONCE_LEN]>) -> Self {
+        self.aead = aead;
+        self.xnonce = xnonce;
+        self
+    }
+
+    /// Length of this header on the wire
+    pub fn header_len(&self) -> usize {
+        if self.version == LEGACY_ENVELOPE_VERSION {
+            LEGACY_ENVELOPE_HEADER_LEN
+        } else {
+            Self::encoded_len(self.aead)
+        }
+    }
+
+    /// Length of a current-version header for `aead`
+    pub fn encoded_len(aead: AeadAlgorithm) -> usize {
+        match aead {
+            AeadAlgorithm::XChaCha20Poly1305 => XNONCE_ENVELOPE_HEADER_LEN,
+            _ => ENVELOPE_HEADER_LEN,
+        }
+    }
+
+    /// Nonce handed to the AEAD
+    pub fn aead_nonce(&self) -> &[u8] {
+        match self.xnonce {
+            Some(ref xnonce) => xnonce,
+            None => &self.nonce,
+        }
+    }
+
+    /// Whether a flag bit is set
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+
+    /// Packet ID used for replay protection (counter part of the nonce)
+    pub fn packet_id(&self) -> u64 {
+        let mut counter = [0u8; 8];
+        counter.copy_from_slice(&self.nonce[4..12]);
+        u64::from_be_bytes(counter)
+    }
+
+    /// Write a current-version header into the first [`header_len`](Self::header_len) bytes of `out`
+    ///
+    /// # Panics
+    /// Panics if `out` is shorter than the header.
+    pub fn write_to(&self, out: &mut [u8]) {
+        out[0] = ENVELOPE_VERSION;
+        out[1] = self.mode.unwrap_or(CipherMode::AeadOnly) as u8;
+        out[2] = self.flags;
+        out[3] = self.aead as u8;
+        out[4..8].copy_from_slice(&self.key_epoch.to_be_bytes());
+        out[8..20].copy_from_slice(&self.nonce);
+        out[20..28].copy_from_slice(&self.key_offset.to_be_bytes());
+        if let Some(ref xnonce) = self.xnonce {
+            out[ENVELOPE_HEADER_LEN..XNONCE_ENVELOPE_HEADER_LEN].copy_from_slice(xnonce);
+        }
+    }
+
+    /// Serialize a current-version header
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![0u8; Self::encoded_len(self.aead)];
+        self.write_to(&mut out);
+        out
+    }
+
+    /// Parse the header at the start of `data`
+    ///
+    /// Use [`header_len`](Self::header_len) on the result to find where the ciphertext starts.
+    pub fn parse(data: &[u8]) -> Result<Self, EnvelopeError> {
+        let version = *data.first().ok_or(EnvelopeError::TooShort)?;
+        match version {
+            LEGACY_ENVELOPE_VERSION => Self::parse_legacy(data),
+            ENVELOPE_VERSION => Self::parse_v1(data),
+            other => Err(EnvelopeError::UnsupportedVersion(other)),
+        }
+    }
+
+    fn parse_v1(data: &[u8]) -> Result<Self, EnvelopeError> {
+        if data.len() < ENVELOPE_HEADER_LEN {
+            return Err(EnvelopeError::TooShort);
+        }
+        let mode = CipherMode::from_u8(data[1]).ok_or(EnvelopeError::UnknownMode(data[1]))?;
+        let flags = data[2];
+        if flags & !KNOWN_FLAGS != 0 {
+            return Err(EnvelopeError::UnknownFlags(flags & !KNOWN_FLAGS));
+        }
+        let aead = AeadAlgorithm::from_u8(data[3]).ok_or(EnvelopeError::UnknownAead(data[3]))?;
+        let xnonce = if aead == AeadAlgorithm::XChaCha20Poly1305 {
+            if data.len() < XNONCE_ENVELOPE_HEADER_LEN {
+                return Err(EnvelopeError::TooShort);
+            }
+            let mut xnonce = [0u8; XNONCE_LEN];
+            xnonce.copy_from_slice(&data[ENVELOPE_HEADER_LEN..XNONCE_ENVELOPE_HEADER_LEN]);
+            Some(xnonce)
+        } else {
+            None
+        };
+
+        let mut epoch = [0u8; 4];
+        epoch.copy_from_slice(&data[4..8]);
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&data[8..20]);
+        let mut offset = [0u8; 8];
+        offset.copy_from_slice(&data[20..28]);
+
+        Ok(Self {
+            version: ENVELOPE_VERSION,
+            mode: Some(mode),
+            flags,
+            aead,
+            key_epoch: u32::from_be_bytes(epoch),
+            nonce,
+            key_offset: u64::from_be_bytes(offset),
+            xnonce,
+        })
+    }
+
+    fn parse_legacy(data: &[u8]) -> Result<Self, EnvelopeError> {
+        if data.len() < LEGACY_ENVELOPE_HEADER_LEN {
+            return Err(EnvelopeError::TooShort);
+        }
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&data[0..12]);
+        let mut offset = [0u8; 8];
+        offset.copy_from_slice(&data[12..20]);
+
+        Ok(Self {
+            version: LEGACY_ENVELOPE_VERSION,
+            mode: None,
+            flags: 0,
+            aead: AeadAlgorithm::ChaCha20Poly1305,
+            key_epoch: 0,
+            nonce,
+            key_offset: u64::from_be_bytes(offset),
+            xnonce: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_nonce() -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[4..12].copy_from_slice(&42u64.to_be_bytes());
+        nonce
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let header = EnvelopeHeader::new(CipherMode::HkdfXor, FLAG_SCRAMBLED, 7, sample_nonce(), 1234);
+        let bytes = header.to_bytes();
+        let parsed = EnvelopeHeader::parse(&bytes).unwrap();
+
+        assert_eq!(parsed, header);
+        assert_eq!(parsed.header_len(), ENVELOPE_HEADER_LEN);
+        assert_eq!(parsed.packet_id(), 42);
+        assert!(parsed.has_flag(FLAG_SCRAMBLED));
+    }
+
+    #[test]
+    fn test_unknown_version_rejected() {
+        let mut bytes = EnvelopeHeader::new(CipherMode::AeadOnly, 0, 0, sample_nonce(), 0).to_bytes();
+        bytes[0] = 0x02;
+        assert_eq!(EnvelopeHeader::parse(&bytes), Err(EnvelopeError::UnsupportedVersion(0x02)));
+    }
+
+    #[test]
+    fn test_unknown_mode_and_flags_rejected() {
+        let mut bytes = EnvelopeHeader::new(CipherMode::AeadOnly, 0, 0, sample_nonce(), 0).to_bytes();
+        bytes[1] = 0x7F;
+        assert_eq!(EnvelopeHeader::parse(&bytes), Err(EnvelopeError::UnknownMode(0x7F)));
+
+        let mut bytes = EnvelopeHeader::new(CipherMode::AeadOnly, 0, 0, sample_nonce(), 0).to_bytes();
+        bytes[2] = 0x80;
+        assert_eq!(EnvelopeHeader::parse(&bytes), Err(EnvelopeError::UnknownFlags(0x80)));
+
+        let mut bytes = EnvelopeHeader::new(CipherMode::AeadOnly, 0, 0, sample_nonce(), 0).to_bytes();
+        bytes[3] = 0x7E;
+        assert_eq!(EnvelopeHeader::parse(&bytes), Err(EnvelopeError::UnknownAead(0x7E)));
+    }
+
+    #[test]
+    fn test_xchacha_header_carries_random_nonce() {
+        let xnonce = [0xA5; XNONCE_LEN];
+        let header = EnvelopeHeader::new(CipherMode::AeadOnly, 0, 3, sample_nonce(), 0)
+            .with_aead(AeadAlgorithm::XChaCha20Poly1305, Some(xnonce));
+        let bytes = header.to_bytes();
+        assert_eq!(bytes.len(), XNONCE_ENVELOPE_HEADER_LEN);
+
+        let parsed = EnvelopeHeader::parse(&bytes).unwrap();
+        assert_eq!(parsed, header);
+        assert_eq!(parsed.aead_nonce(), &xnonce[..]);
+        assert_eq!(parsed.packet_id(), 42);
+        assert_eq!(EnvelopeHeader::parse(&bytes[..ENVELOPE_HEADER_LEN]), Err(EnvelopeError::TooShort));
+    }
+
+    #[test]
+    fn test_legacy_envelope_parsed() {
+        let mut legacy = Vec::new();
+        legacy.extend_from_slice(&sample_nonce());
+        legacy.extend_from_slice(&99u64.to_be_bytes());
+
+        let parsed = EnvelopeHeader::parse(&legacy).unwrap();
+        assert_eq!(parsed.version, LEGACY_ENVELOPE_VERSION);
+        assert_eq!(parsed.mode, None);
+        assert_eq!(parsed.key_offset, 99);
+        assert_eq!(parsed.packet_id(), 42);
+        assert_eq!(parsed.header_len(), LEGACY_ENVELOPE_HEADER_LEN);
+    }
+
+    #[test]
+    fn test_truncated_header_rejected() {
+        let bytes = EnvelopeHeader::new(CipherMode::TrueOtp, 0, 1, sample_nonce(), 5).to_bytes();
+        assert_eq!(EnvelopeHeader::parse(&bytes[..ENVELOPE_HEADER_LEN - 1]), Err(EnvelopeError::TooShort));
+        assert_eq!(EnvelopeHeader::parse(&[]), Err(EnvelopeError::TooShort));
+    }
+}
@@ -0,0 +1,647 @@
+//! Group Sender-Key Encryption
+//!
+//! [`WasifVernam`] is pairwise. Encrypting a room broadcast for N members that way
+//! costs N encryptions per message. With sender keys each member keeps one sending
+//! chain, hands it to every other member once over the existing pairwise sessions,
+//! and then encrypts each broadcast a single time.
+//!
+//! # Sender Chains
+//! ```text
+//! MK_i     = KDF(CK_i, "message-key")   -- key for message i, used once
+//! CK_(i+1) = KDF(CK_i, "chain-key")     -- old chain keys are erased
+//! ```
+//! A [`SenderKeyDistribution`] carries the chain key at the sender's current
+//! iteration, so a member who joins later cannot read earlier messages.
+//!
+//! # Membership
+//! Removing a member rotates our chain to a new generation with a fresh random
+//! chain key. The new distribution must reach every remaining member, and each
+//! of them must remove the member and rotate too. Adding a member only requires
+//! sending them the current distribution.
+//!
+//! A receiver keeps only the newest generation of each chain: once it processes
+//! a rotated distribution, messages still in flight from the previous generation
+//! fail with [`GroupError::UnknownGeneration`] and must be resent.
+//!
+//! # Message Format
+//! ```text
+//! version (1) | generation (4, BE) | iteration (4, BE) | sender len (1) | sender | ciphertext | tag (16) | commitment (32)
+//! ```
+//! The header and the room ID are authenticated. Messages are
+//! [key-committing](crate::commitment), since every member holds the key.
+//!
+//! Any member holding a sender's chain can forge messages under that sender's
+//! name; sender keys provide confidentiality toward non-members, not
+//! authenticity between members.
+
+use std::collections::HashMap;
+
+use hkdf::Hkdf;
+use sha2::Sha256;
+use tracing::{debug, info, warn};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::aead_backend::{AeadAlgorithm, AeadBackend, AEAD_TAG_LEN};
+use crate::commitment::{CommitmentKey, COMMITMENT_LEN};
+use crate::wasif_vernam::WasifVernam;
+
+/// Current group message format version
+pub const GROUP_MESSAGE_VERSION: u8 = 0x01;
+
+/// Largest gap in iterations a receiver derives ahead to
+pub const MAX_SKIPPED_MESSAGES: u32 = 1000;
+
+/// Message keys of skipped messages kept per sender before the oldest are dropped
+pub const MAX_STORED_SKIPPED_KEYS: usize = 2000;
+
+/// Longest member or room ID
+pub const MAX_GROUP_ID_LEN: usize = 255;
+
+/// Associated data binding distribution envelopes to their purpose
+const DISTRIBUTION_AAD: &[u8] = b"zks-group-sender-key";
+
+/// Every message key encrypts exactly one message, so a fixed nonce is safe
+const MESSAGE_NONCE: [u8; 12] = [0u8; 12];
+
+/// Symmetric ratchet of one sender
+#[derive(Clone)]
+struct SenderChain {
+    generation: u32,
+    iteration: u32,
+    chain_key: Zeroizing<[u8; 32]>,
+}
+
+impl SenderChain {
+    fn random(generation: u32) -> Result<Self, GroupError> {
+        let mut chain_key = Zeroizing::new([0u8; 32]);
+        getrandom::getrandom(chain_key.as_mut()).map_err(|_| {
+            warn!("RNG unavailable for group sender chain");
+            GroupError::Rng
+        })?;
+        Ok(Self {
+            generation,
+            iteration: 0,
+            chain_key,
+        })
+    }
+
+    /// Message key for the current iteration; moves the chain one step forward
+    fn next_message_key(&mut self) -> Result<Zeroizing<[u8; 32]>, GroupError> {
+        if self.iteration == u32::MAX {
+            return Err(GroupError::ChainExhausted);
+        }
+        let hk = Hkdf::<Sha256>::new(Some(b"zks-group-sender-chain"), self.chain_key.as_ref());
+        let mut message_key = Zeroizing::new([0u8; 32]);
+        hk.expand(b"message-key", message_key.as_mut())
+            .expect("32 bytes is a valid HKDF output length");
+        hk.expand(b"chain-key", self.chain_key.as_mut())
+            .expect("32 bytes is a valid HKDF output length");
+        self.iteration += 1;
+        Ok(message_key)
+    }
+}
+
+/// A member's chain as seen by the other members
+#[derive(Clone)]
+struct ReceiverChain {
+    chain: SenderChain,
+    /// Keys of messages skipped over, by iteration
+    skipped: HashMap<u32, Zeroizing<[u8; 32]>>,
+}
+
+impl ReceiverChain {
+    /// Message key for `iteration`, deriving ahead and storing skipped keys as needed
+    fn message_key(&mut self, generation: u32, iteration: u32) -> Result<Zeroizing<[u8; 32]>, GroupError> {
+        if generation != self.chain.generation {
+            return Err(GroupError::UnknownGeneration {
+                expected: self.chain.generation,
+                actual: generation,
+            });
+        }
+        if iteration < self.chain.iteration {
+            return self.skipped.remove(&iteration).ok_or(GroupError::Duplicate(iteration));
+        }
+        if iteration - self.chain.iteration > MAX_SKIPPED_MESSAGES {
+            return Err(GroupError::TooFarAhead(iteration));
+        }
+        while self.chain.iteration < iteration {
+            let skipped_iteration = self.chain.iteration;
+            let key = self.chain.next_message_key()?;
+            self.skipped.insert(skipped_iteration, key);
+        }
+        while self.skipped.len() > MAX_STORED_SKIPPED_KEYS {
+            let oldest = self.skipped.keys().copied().min().expect("non-empty map");
+            self.skipped.remove(&oldest);
+        }
+        self.chain.next_message_key()
+    }
+}
+
+/// Sender chain handed to another member over a pairwise session
+///
+/// Layout: `generation (4, BE) | iteration (4, BE) | chain key (32) | room len (1) | room | sender len (1) | sender`
+#[derive(Clone, PartialEq, Eq)]
+pub struct SenderKeyDistribution {
+    /// Room the chain belongs to
+    pub room_id: String,
+    /// Member sending on this chain
+    pub sender: String,
+    /// Chain generation, bumped on every rotation
+    pub generation: u32,
+    /// Iteration the chain key belongs to
+    pub iteration: u32,
+    /// Chain key at `iteration`
+    pub chain_key: [u8; 32],
+}
+
+impl SenderKeyDistribution {
+    /// Serialize the distribution
+    ///
+    /// Fails if the room or sender ID is longer than [`MAX_GROUP_ID_LEN`].
+    pub fn to_bytes(&self) -> Result<Vec<u8>, GroupError> {
+        let mut out = Vec::with_capacity(42 + self.room_id.len() + self.sender.len());
+        out.extend_from_slice(&self.generation.to_be_bytes());
+        out.extend_from_slice(&self.iteration.to_be_bytes());
+        out.extend_from_slice(&self.chain_key);
+        write_id(&mut out, &self.room_id)?;
+        write_id(&mut out, &self.sender)?;
+        Ok(out)
+    }
+
+    /// Parse a distribution
+    pub fn from_bytes(data: &[u8]) -> Result<Self, GroupError> {
+        if data.len() < 40 {
+            return Err(GroupError::Malformed);
+        }
+        let generation = u32::from_be_bytes(data[0..4].try_into().expect("4-byte slice"));
+        let iteration = u32::from_be_bytes(data[4..8].try_into().expect("4-byte slice"));
+        let (room_id, rest) = read_id(&data[40..])?;
+        let (sender, rest) = read_id(rest)?;
+        if !rest.is_empty() {
+            return Err(GroupError::Malformed);
+        }
+        let mut chain_key = [0u8; 32];
+        chain_key.copy_from_slice(&data[8..40]);
+        Ok(Self {
+            room_id,
+            sender,
+            generation,
+            iteration,
+            chain_key,
+        })
+    }
+
+    /// Encrypt the distribution for one member over our pairwise session with them
+    pub fn seal(&self, pairwise: &mut WasifVernam) -> Result<Vec<u8>, GroupError> {
+        let body = Zeroizing::new(self.to_bytes()?);
+        pairwise.encrypt_with_aad(&body, DISTRIBUTION_AAD).map_err(|_| GroupError::Crypto)
+    }
+
+    /// Decrypt a distribution received over a pairwise session
+    pub fn open(pairwise: &WasifVernam, envelope: &[u8]) -> Result<Self, GroupError> {
+        let body = Zeroizing::new(
+            pairwise
+                .decrypt_with_aad(envelope, DISTRIBUTION_AAD)
+                .map_err(|_| GroupError::Crypto)?,
+        );
+        Self::from_bytes(&body)
+    }
+}
+
+impl Drop for SenderKeyDistribution {
+    fn drop(&mut self) {
+        self.chain_key.zeroize();
+    }
+}
+
+/// Secure debug implementation that doesn't expose the chain key
+impl std::fmt::Debug for SenderKeyDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SenderKeyDistribution({} in {}, generation {}, iteration {})",
+            self.sender, self.room_id, self.generation, self.iteration
+        )
+    }
+}
+
+/// Our view of one room: our sender chain plus the chains of the other members
+pub struct GroupSession {
+    room_id: String,
+    member_id: String,
+    own: SenderChain,
+    /// Members and their chains, `None` until their distribution arrives
+    members: HashMap<String, Option<ReceiverChain>>,
+}
+
+impl GroupSession {
+    /// Start a session in `room_id` as `member_id` with a fresh sender chain
+    pub fn new(room_id: impl Into<String>, member_id: impl Into<String>) -> Result<Self, GroupError> {
+        let room_id = room_id.into();
+        let member_id = member_id.into();
+        if room_id.len() > MAX_GROUP_ID_LEN || member_id.len() > MAX_GROUP_ID_LEN {
+            return Err(GroupError::IdTooLong);
+        }
+        Ok(Self {
+            room_id,
+            member_id,
+            own: SenderChain::random(0)?,
+            members: HashMap::new(),
+        })
+    }
+
+    /// Room this session belongs to
+    pub fn room_id(&self) -> &str {
+        &self.room_id
+    }
+
+    /// Our member ID
+    pub fn member_id(&self) -> &str {
+        &self.member_id
+    }
+
+    /// Other members of the room
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.members.keys().map(String::as_str)
+    }
+
+    /// Generation of our sender chain
+    pub fn generation(&self) -> u32 {
+        self.own.generation
+    }
+
+    /// Our current sender chain, to be sealed for each member
+    pub fn distribution(&self) -> SenderKeyDistribution {
+        SenderKeyDistribution {
+            room_id: self.room_id.clone(),
+            sender: self.member_id.clone(),
+            generation: self.own.generation,
+            iteration: self.own.iteration,
+            chain_key: *self.own.chain_key,
+        }
+    }
+
+    /// Admit a member; returns our distribution to seal for them
+    ///
+    /// The new member only learns our chain from its current iteration on.
+    pub fn add_member(&mut self, member: impl Into<String>) -> Result<SenderKeyDistribution, GroupError> {
+        let member = member.into();
+        if member.len() > MAX_GROUP_ID_LEN {
+            return Err(GroupError::IdTooLong);
+        }
+        if member != self.member_id {
+            self.members.entry(member.clone()).or_insert(None);
+            info!("👥 {} joined room {}", member, self.room_id);
+        }
+        Ok(self.distribution())
+    }
+
+    /// Remove a member and rotate our chain
+    ///
+    /// Returns the new distribution, which must be sealed for every remaining member.
+    pub fn remove_member(&mut self, member: &str) -> Result<SenderKeyDistribution, GroupError> {
+        if self.members.remove(member).is_none() {
+            return Err(GroupError::NotMember(member.to_string()));
+        }
+        info!("👥 {} left room {}", member, self.room_id);
+        self.rotate()
+    }
+
+    /// Replace our chain with a fresh one of the next generation
+    pub fn rotate(&mut self) -> Result<SenderKeyDistribution, GroupError> {
+        let generation = self.own.generation.checked_add(1).ok_or(GroupError::ChainExhausted)?;
+        self.own = SenderChain::random(generation)?;
+        debug!("👥 Sender chain for room {} rotated to generation {}", self.room_id, generation);
+        Ok(self.distribution())
+    }
+
+    /// Install a member's chain received from `from` over our pairwise session with them
+    ///
+    /// Newer generations replace older ones; an older or already known chain
+    /// position is ignored, so a chain never moves backwards. Messages from the
+    /// replaced generation are rejected with [`GroupError::UnknownGeneration`].
+    pub fn process_distribution(&mut self, from: &str, distribution: &SenderKeyDistribution) -> Result<(), GroupError> {
+        if distribution.room_id != self.room_id {
+            return Err(GroupError::WrongRoom(distribution.room_id.clone()));
+        }
+        if distribution.sender != from {
+            warn!("Sender key for {} relayed by {}", distribution.sender, from);
+            return Err(GroupError::NotMember(distribution.sender.clone()));
+        }
+        let slot = self
+            .members
+            .get_mut(from)
+            .ok_or_else(|| GroupError::NotMember(from.to_string()))?;
+        if let Some(ref current) = slot {
+            let known = (current.chain.generation, current.chain.iteration);
+            if (distribution.generation, distribution.iteration) <= known {
+                return Ok(());
+            }
+            if distribution.generation == current.chain.generation {
+                // Same chain further along: keep the keys we already skipped over
+                let mut current = current.clone();
+                current.chain.iteration = distribution.iteration;
+                current.chain.chain_key = Zeroizing::new(distribution.chain_key);
+                *slot = Some(current);
+                return Ok(());
+            }
+        }
+        *slot = Some(ReceiverChain {
+            chain: SenderChain {
+                generation: distribution.generation,
+                iteration: distribution.iteration,
+                chain_key: Zeroizing::new(distribution.chain_key),
+            },
+            skipped: HashMap::new(),
+        });
+        debug!("👥 Installed sender chain of {} (generation {})", from, distribution.generation);
+        Ok(())
+    }
+
+    /// Encrypt a broadcast for every member holding our distribution
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, GroupError> {
+        let header = encode_header(&self.member_id, self.own.generation, self.own.iteration)?;
+        let message_key = self.own.next_message_key()?;
+        let cipher = AeadBackend::new(AeadAlgorithm::ChaCha20Poly1305, &message_key).map_err(|_| GroupError::Crypto)?;
+        let aad = message_aad(&header, &self.room_id);
+
+        let mut message = Vec::with_capacity(header.len() + plaintext.len() + AEAD_TAG_LEN + COMMITMENT_LEN);
+        message.extend_from_slice(&header);
+        message.extend_from_slice(plaintext);
+        let tag = cipher
+            .encrypt_in_place_detached(&MESSAGE_NONCE, &aad, &mut message[header.len()..])
+            .map_err(|_| GroupError::Crypto)?;
+        message.extend_from_slice(&tag);
+        message.extend_from_slice(&CommitmentKey::derive(&message_key).commit(&aad, &tag));
+        Ok(message)
+    }
+
+    /// Decrypt a broadcast, returning the sender and the plaintext
+    ///
+    /// Messages may arrive out of order within [`MAX_SKIPPED_MESSAGES`]; each
+    /// message decrypts at most once.
+    pub fn decrypt(&mut self, message: &[u8]) -> Result<(String, Vec<u8>), GroupError> {
+        let (sender, generation, iteration, header_len) = decode_header(message)?;
+        if message.len() < header_len + AEAD_TAG_LEN + COMMITMENT_LEN {
+            return Err(GroupError::Malformed);
+        }
+        let chain = match self.members.get(&sender) {
+            Some(Some(chain)) => chain,
+            Some(None) => return Err(GroupError::NoSenderKey(sender)),
+            None => return Err(GroupError::NotMember(sender)),
+        };
+
+        // Work on a copy so a forged message cannot advance the chain
+        let mut chain = chain.clone();
+        let message_key = chain.message_key(generation, iteration)?;
+        let aad = message_aad(&message[..header_len], &self.room_id);
+        let (body, commitment) = message[header_len..].split_at(message.len() - header_len - COMMITMENT_LEN);
+        let (ciphertext, tag) = body.split_at(body.len() - AEAD_TAG_LEN);
+        if !CommitmentKey::derive(&message_key).verify(&aad, tag, commitment) {
+            warn!("Group message from {} failed key commitment", sender);
+            return Err(GroupError::Crypto);
+        }
+        let cipher = AeadBackend::new(AeadAlgorithm::ChaCha20Poly1305, &message_key).map_err(|_| GroupError::Crypto)?;
+        let mut plaintext = ciphertext.to_vec();
+        if cipher.decrypt_in_place_detached(&MESSAGE_NONCE, &aad, &mut plaintext, tag).is_err() {
+            warn!("Group message from {} failed authentication", sender);
+            return Err(GroupError::Crypto);
+        }
+
+        self.members.insert(sender.clone(), Some(chain));
+        Ok((sender, plaintext))
+    }
+}
+
+fn encode_header(sender: &str, generation: u32, iteration: u32) -> Result<Vec<u8>, GroupError> {
+    let mut header = Vec::with_capacity(10 + sender.len());
+    header.push(GROUP_MESSAGE_VERSION);
+    header.extend_from_slice(&generation.to_be_bytes());
+    header.extend_from_slice(&iteration.to_be_bytes());
+    write_id(&mut header, sender)?;
+    Ok(header)
+}
+
+/// Returns sender, generation, iteration and header length
+fn decode_header(message: &[u8]) -> Result<(String, u32, u32, usize), GroupError> {
+    if message.len() < 10 {
+        return Err(GroupError::Malformed);
+    }
+    if message[0] != GROUP_MESSAGE_VERSION {
+        return Err(GroupError::UnsupportedVersion(message[0]));
+    }
+    let generation = u32::from_be_bytes(message[1..5].try_into().expect("4-byte slice"));
+    let iteration = u32::from_be_bytes(message[5..9].try_into().expect("4-byte slice"));
+    let (sender, rest) = read_id(&message[9..])?;
+    Ok((sender, generation, iteration, message.len() - rest.len()))
+}
+
+fn message_aad(header: &[u8], room_id: &str) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(header.len() + room_id.len());
+    aad.extend_from_slice(header);
+    aad.extend_from_slice(room_id.as_bytes());
+    aad
+}
+
+/// Append a length-prefixed ID, refusing one whose length does not fit the prefix
+fn write_id(out: &mut Vec<u8>, id: &str) -> Result<(), GroupError> {
+    let len = u8::try_from(id.len()).map_err(|_| GroupError::IdTooLong)?;
+    out.push(len);
+    out.extend_from_slice(id.as_bytes());
+    Ok(())
+}
+
+/// Read a length-prefixed UTF-8 ID, returning it and the remaining bytes
+fn read_id(data: &[u8]) -> Result<(String, &[u8]), GroupError> {
+    let (&len, rest) = data.split_first().ok_or(GroupError::Malformed)?;
+    let id = rest.get(..len as usize).ok_or(GroupError::Malformed)?;
+    let id = String::from_utf8(id.to_vec()).map_err(|_| GroupError::Malformed)?;
+    Ok((id, &rest[len as usize..]))
+}
+
+/// Errors produced by group sessions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupError {
+    /// Member or room ID longer than [`MAX_GROUP_ID_LEN`]
+    IdTooLong,
+    /// Sender is not a member of the room
+    NotMember(String),
+    /// Member's distribution has not arrived yet
+    NoSenderKey(String),
+    /// Distribution belongs to another room
+    WrongRoom(String),
+    /// Message sealed under a chain generation we do not hold
+    UnknownGeneration {
+        /// Generation we hold for the sender
+        expected: u32,
+        /// Generation carried by the message
+        actual: u32,
+    },
+    /// Message iteration already decrypted or its key was dropped
+    Duplicate(u32),
+    /// Message iteration lies more than [`MAX_SKIPPED_MESSAGES`] ahead
+    TooFarAhead(u32),
+    /// Sender chain ran out of iterations or generations
+    ChainExhausted,
+    /// Message written by an unknown format version
+    UnsupportedVersion(u8),
+    /// Message or distribution could not be parsed
+    Malformed,
+    /// Authentication or key commitment failed
+    Crypto,
+    /// RNG unavailable for a fresh chain key
+    Rng,
+}
+
+impl std::fmt::Display for GroupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupError::IdTooLong => write!(f, "ID longer than {} bytes", MAX_GROUP_ID_LEN),
+            GroupError::NotMember(member) => write!(f, "{} is not a member of the room", member),
+            GroupError::NoSenderKey(member) => write!(f, "No sender key from {} yet", member),
+            GroupError::WrongRoom(room) => write!(f, "Sender key belongs to room {}", room),
+            GroupError::UnknownGeneration { expected, actual } => {
+                write!(f, "Message from generation {} (holding {})", actual, expected)
+            }
+            GroupError::Duplicate(iteration) => write!(f, "Message {} already received or expired", iteration),
+            GroupError::TooFarAhead(iteration) => write!(f, "Message {} is too far ahead", iteration),
+            GroupError::ChainExhausted => write!(f, "Sender chain exhausted"),
+            GroupError::UnsupportedVersion(version) => write!(f, "Unsupported group message version {}", version),
+            GroupError::Malformed => write!(f, "Malformed group message"),
+            GroupError::Crypto => write!(f, "Group message failed authentication"),
+            GroupError::Rng => write!(f, "RNG unavailable for sender chain"),
+        }
+    }
+}
+
+impl std::error::Error for GroupError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three members of one room with pairwise sessions between each pair
+    fn room() -> (GroupSession, GroupSession, GroupSession) {
+        let mut alice = GroupSession::new("room-1", "alice").unwrap();
+        let mut bob = GroupSession::new("room-1", "bob").unwrap();
+        let mut carol = GroupSession::new("room-1", "carol").unwrap();
+        for (session, others) in [(&mut alice, ["bob", "carol"]), (&mut bob, ["alice", "carol"]), (&mut carol, ["alice", "bob"])] {
+            for other in others {
+                session.add_member(other).unwrap();
+            }
+        }
+        let members = [alice, bob, carol];
+        let distributions: Vec<_> = members.iter().map(GroupSession::distribution).collect();
+        let [mut alice, mut bob, mut carol] = members;
+        for distribution in &distributions {
+            for session in [&mut alice, &mut bob, &mut carol] {
+                if session.member_id() != distribution.sender {
+                    session.process_distribution(&distribution.sender, distribution).unwrap();
+                }
+            }
+        }
+        (alice, bob, carol)
+    }
+
+    #[test]
+    fn test_distribution_over_pairwise_session() {
+        let mut alice = GroupSession::new("room-1", "alice").unwrap();
+        let mut bob = GroupSession::new("room-1", "bob").unwrap();
+        bob.add_member("alice").unwrap();
+        let distribution = alice.add_member("bob").unwrap();
+
+        let mut alice_to_bob = WasifVernam::new([0x31; 32]).unwrap();
+        let bob_from_alice = WasifVernam::new([0x31; 32]).unwrap();
+        let sealed = distribution.seal(&mut alice_to_bob).unwrap();
+        let opened = SenderKeyDistribution::open(&bob_from_alice, &sealed).unwrap();
+        assert_eq!(opened, distribution);
+
+        // Only the member the distribution names may hand it over
+        assert_eq!(bob.process_distribution("mallory", &opened), Err(GroupError::NotMember("alice".into())));
+        bob.process_distribution("alice", &opened).unwrap();
+
+        let message = alice.encrypt(b"hello room").unwrap();
+        assert_eq!(bob.decrypt(&message).unwrap(), ("alice".to_string(), b"hello room".to_vec()));
+    }
+
+    #[test]
+    fn test_overlong_ids_are_refused() {
+        assert!(matches!(GroupSession::new("r".repeat(256), "alice"), Err(GroupError::IdTooLong)));
+
+        let mut distribution = GroupSession::new("room-1", "alice").unwrap().distribution();
+        distribution.sender = "a".repeat(MAX_GROUP_ID_LEN + 1);
+        assert_eq!(distribution.to_bytes(), Err(GroupError::IdTooLong));
+        let mut pairwise = WasifVernam::new([0x32; 32]).unwrap();
+        assert_eq!(distribution.seal(&mut pairwise), Err(GroupError::IdTooLong));
+    }
+
+    #[test]
+    fn test_rotation_drops_in_flight_messages() {
+        let (mut alice, mut bob, _) = room();
+        let in_flight = alice.encrypt(b"sent before rotating").unwrap();
+        let next = alice.rotate().unwrap();
+        bob.process_distribution("alice", &next).unwrap();
+
+        assert_eq!(bob.decrypt(&in_flight), Err(GroupError::UnknownGeneration { expected: 1, actual: 0 }));
+    }
+
+    #[test]
+    fn test_broadcast_out_of_order_and_replay() {
+        let (mut alice, mut bob, mut carol) = room();
+
+        let messages: Vec<_> = (0..5u8).map(|i| alice.encrypt(&[i; 10]).unwrap()).collect();
+        for i in [4, 0, 2, 1, 3] {
+            assert_eq!(bob.decrypt(&messages[i]).unwrap().1, vec![i as u8; 10]);
+        }
+        assert_eq!(carol.decrypt(&messages[3]).unwrap().1, vec![3u8; 10]);
+        assert_eq!(bob.decrypt(&messages[2]), Err(GroupError::Duplicate(2)));
+
+        // A tampered message neither decrypts nor consumes its key
+        let mut tampered = messages[0].clone();
+        tampered[16] ^= 1;
+        assert_eq!(carol.decrypt(&tampered), Err(GroupError::Crypto));
+        assert_eq!(carol.decrypt(&messages[0]).unwrap().1, vec![0u8; 10]);
+
+        let reply = carol.encrypt(b"reply").unwrap();
+        assert_eq!(alice.decrypt(&reply).unwrap().0, "carol");
+        assert_eq!(bob.decrypt(&reply).unwrap().0, "carol");
+    }
+
+    #[test]
+    fn test_too_far_ahead_is_refused() {
+        let (mut alice, mut bob, _) = room();
+        for _ in 0..=MAX_SKIPPED_MESSAGES {
+            alice.own.next_message_key().unwrap();
+        }
+        let message = alice.encrypt(b"late").unwrap();
+        assert_eq!(bob.decrypt(&message), Err(GroupError::TooFarAhead(MAX_SKIPPED_MESSAGES + 1)));
+    }
+
+    #[test]
+    fn test_removed_member_loses_access() {
+        let (mut alice, mut bob, mut carol) = room();
+
+        // Everyone removes carol and rotates; new chains go to the remaining members only
+        let alice_next = alice.remove_member("carol").unwrap();
+        let bob_next = bob.remove_member("carol").unwrap();
+        assert_eq!(alice_next.generation, 1);
+        bob.process_distribution("alice", &alice_next).unwrap();
+        alice.process_distribution("bob", &bob_next).unwrap();
+
+        let message = alice.encrypt(b"after carol left").unwrap();
+        assert_eq!(bob.decrypt(&message).unwrap().1, b"after carol left".to_vec());
+        assert_eq!(
+            carol.decrypt(&message),
+            Err(GroupError::UnknownGeneration { expected: 0, actual: 1 })
+        );
+
+        // Messages from removed members are refused
+        let from_carol = carol.encrypt(b"still here?").unwrap();
+        assert_eq!(alice.decrypt(&from_carol), Err(GroupError::NotMember("carol".into())));
+
+        // Stale distributions never roll a chain back
+        let stale = GroupSession::new("room-1", "alice").unwrap().distribution();
+        bob.process_distribution("alice", &stale).unwrap();
+        assert!(bob.decrypt(&alice.encrypt(b"still gen 1").unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,422 @@
+//! Single-Shot Sealing to an ML-KEM Public Key
+//!
+//! HPKE-style (RFC 9180) public-key encryption without a handshake: the sender
+//! encapsulates to the recipient's ML-KEM-768 key, derives an AEAD key with HKDF
+//! and seals one message. Useful for offline file encryption, mailbox messages or
+//! encrypting exported [`ChainState`](crate::recursive_chain::ChainState).
+//!
+//! # Format
+//! ```text
+//! magic "ZKSB" (4) | version (1) | mode (1) | aead (1) | key len (2, BE) | signature len (2, BE)
+//! | ML-KEM ciphertext (1088) | sender verifying key | ciphertext | tag (16) | signature
+//! ```
+//! In [`SealMode::Base`] both lengths are zero. Everything before the ciphertext
+//! is authenticated as associated data, alongside the caller's `aad`.
+//!
+//! # Key Schedule
+//! ```text
+//! context  = SHA-256(prefix || recipient public key || len(info) || info)
+//! prk      = HKDF-Extract("zks-sealed-box-v1", shared secret)
+//! key      = HKDF-Expand(prk, "key"   || context)
+//! nonce    = HKDF-Expand(prk, "nonce" || context)
+//! exporter = HKDF-Expand(prk, "exp"   || context)
+//! ```
+//! `prefix` is the header, KEM ciphertext and sender key, so the derived keys are
+//! bound to the mode, the AEAD, both parties and `info`.
+//!
+//! # Sender Authentication
+//! [`SealMode::Auth`] appends an ML-DSA signature over the context and the
+//! ciphertext. [`open_from`] only accepts boxes signed by the expected key.
+
+use hkdf::Hkdf;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use zeroize::Zeroizing;
+use zks_pqcrypto::ml_kem::CIPHERTEXT_SIZE as KEM_CIPHERTEXT_SIZE;
+use zks_pqcrypto::{MlDsa, MlDsaKeypair, MlKem, MlKemKeypair};
+
+use crate::aead_backend::{AeadAlgorithm, AeadBackend, AEAD_TAG_LEN};
+use crate::constant_time::ct_eq;
+
+/// Magic bytes identifying a sealed box
+pub const SEALED_BOX_MAGIC: [u8; 4] = *b"ZKSB";
+
+/// Current sealed box format version
+pub const SEALED_BOX_VERSION: u8 = 0x01;
+
+/// Length of the fixed sealed box header
+pub const SEALED_BOX_HEADER_LEN: usize = 11;
+
+/// Longest secret an [`Exporter`] can derive per context
+pub const MAX_EXPORT_LEN: usize = 255 * 32;
+
+const SIGNATURE_LABEL: &[u8] = b"zks-sealed-box-v1 signature";
+
+/// Whether a sealed box is signed by its sender
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SealMode {
+    /// Anonymous sender
+    Base = 0x00,
+    /// Sender signs with ML-DSA
+    Auth = 0x01,
+}
+
+/// Contents of an opened sealed box
+pub struct Opened {
+    /// Decrypted message
+    pub plaintext: Vec<u8>,
+    /// Sender's ML-DSA verifying key in [`SealMode::Auth`]
+    pub sender: Option<Vec<u8>>,
+    /// Secrets shared with the sender beyond the message
+    pub exporter: Exporter,
+}
+
+/// Derives further secrets shared by sender and recipient of one box
+pub struct Exporter {
+    secret: Zeroizing<[u8; 32]>,
+}
+
+impl Exporter {
+    /// Derive `len` bytes for `context`; different contexts give independent secrets
+    pub fn export(&self, context: &[u8], len: usize) -> Result<Zeroizing<Vec<u8>>, SealError> {
+        if len > MAX_EXPORT_LEN {
+            return Err(SealError::ExportTooLong(len));
+        }
+        let hk = Hkdf::<Sha256>::from_prk(self.secret.as_ref()).map_err(|_| SealError::Crypto)?;
+        let mut out = Zeroizing::new(vec![0u8; len]);
+        hk.expand(context, &mut out).map_err(|_| SealError::Crypto)?;
+        Ok(out)
+    }
+}
+
+/// Seal `plaintext` for the holder of `recipient_public_key`
+///
+/// `info` identifies the application and `aad` is authenticated without being
+/// stored; the recipient must supply the same bytes for both.
+pub fn seal(recipient_public_key: &[u8], info: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<(Vec<u8>, Exporter), SealError> {
+    seal_box(recipient_public_key, None, info, aad, plaintext)
+}
+
+/// Seal and sign with the sender's ML-DSA key
+pub fn seal_auth(
+    recipient_public_key: &[u8],
+    sender: &MlDsaKeypair,
+    info: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<(Vec<u8>, Exporter), SealError> {
+    seal_box(recipient_public_key, Some(sender), info, aad, plaintext)
+}
+
+/// Open a sealed box in either mode
+///
+/// In [`SealMode::Auth`] the signature is verified and the sender key returned in
+/// [`Opened::sender`]; use [`open_from`] to require a particular sender.
+pub fn open(recipient: &MlKemKeypair, info: &[u8], aad: &[u8], sealed: &[u8]) -> Result<Opened, SealError> {
+    let parsed = ParsedBox::parse(sealed)?;
+    let shared_secret = MlKem::decapsulate(parsed.kem_ciphertext, recipient.secret_key()).map_err(|_| SealError::Kem)?;
+    let schedule = KeySchedule::derive(&shared_secret, parsed.prefix, &recipient.public_key, info, parsed.algorithm)?;
+
+    if parsed.mode == SealMode::Auth {
+        let message = signed_message(&schedule.context, parsed.body);
+        MlDsa::verify(&message, parsed.signature, parsed.sender_key).map_err(|_| {
+            warn!("Sealed box signature rejected");
+            SealError::Signature
+        })?;
+    }
+
+    let (ciphertext, tag) = parsed.body.split_at(parsed.body.len() - AEAD_TAG_LEN);
+    let mut plaintext = ciphertext.to_vec();
+    let full_aad = [parsed.prefix, aad].concat();
+    schedule
+        .cipher
+        .decrypt_in_place_detached(&schedule.nonce, &full_aad, &mut plaintext, tag)
+        .map_err(|_| SealError::Crypto)?;
+
+    Ok(Opened {
+        plaintext,
+        sender: (parsed.mode == SealMode::Auth).then(|| parsed.sender_key.to_vec()),
+        exporter: schedule.exporter,
+    })
+}
+
+/// Open a box that must be signed by `sender_verifying_key`
+pub fn open_from(
+    recipient: &MlKemKeypair,
+    sender_verifying_key: &[u8],
+    info: &[u8],
+    aad: &[u8],
+    sealed: &[u8],
+) -> Result<Opened, SealError> {
+    let opened = open(recipient, info, aad, sealed)?;
+    match opened.sender {
+        Some(ref sender) if ct_eq(sender, sender_verifying_key) => Ok(opened),
+        _ => {
+            warn!("Sealed box not signed by the expected sender");
+            Err(SealError::UnexpectedSender)
+        }
+    }
+}
+
+fn seal_box(
+    recipient_public_key: &[u8],
+    sender: Option<&MlDsaKeypair>,
+    info: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<(Vec<u8>, Exporter), SealError> {
+    let algorithm = AeadAlgorithm::default();
+    let mode = if sender.is_some() { SealMode::Auth } else { SealMode::Base };
+    let sender_key = sender.map(MlDsaKeypair::verifying_key).unwrap_or_default();
+    let signature_len = match sender {
+        Some(_) => zks_pqcrypto::ml_dsa::SIGNATURE_SIZE,
+        None => 0,
+    };
+    let encapsulation = MlKem::encapsulate(recipient_public_key).map_err(|_| SealError::Kem)?;
+
+    let mut sealed = Vec::with_capacity(
+        SEALED_BOX_HEADER_LEN + KEM_CIPHERTEXT_SIZE + sender_key.len() + plaintext.len() + AEAD_TAG_LEN + signature_len,
+    );
+    sealed.extend_from_slice(&SEALED_BOX_MAGIC);
+    sealed.push(SEALED_BOX_VERSION);
+    sealed.push(mode as u8);
+    sealed.push(algorithm as u8);
+    sealed.extend_from_slice(&(sender_key.len() as u16).to_be_bytes());
+    sealed.extend_from_slice(&(signature_len as u16).to_be_bytes());
+    sealed.extend_from_slice(&encapsulation.ciphertext);
+    sealed.extend_from_slice(sender_key);
+    let prefix_len = sealed.len();
+
+    let schedule = KeySchedule::derive(
+        &encapsulation.shared_secret,
+        &sealed,
+        recipient_public_key,
+        info,
+        algorithm,
+    )?;
+    let full_aad = [&sealed[..], aad].concat();
+    sealed.extend_from_slice(plaintext);
+    let tag = schedule
+        .cipher
+        .encrypt_in_place_detached(&schedule.nonce, &full_aad, &mut sealed[prefix_len..])
+        .map_err(|_| SealError::Crypto)?;
+    sealed.extend_from_slice(&tag);
+
+    if let Some(sender) = sender {
+        let message = signed_message(&schedule.context, &sealed[prefix_len..]);
+        let signature = MlDsa::sign(&message, sender.signing_key()).map_err(|_| SealError::Signature)?;
+        if signature.len() != signature_len {
+            return Err(SealError::Signature);
+        }
+        sealed.extend_from_slice(&signature);
+    }
+    Ok((sealed, schedule.exporter))
+}
+
+fn signed_message(context: &[u8; 32], body: &[u8]) -> Vec<u8> {
+    [SIGNATURE_LABEL, context, body].concat()
+}
+
+/// Keys derived for one sealed box
+struct KeySchedule {
+    context: [u8; 32],
+    cipher: AeadBackend,
+    nonce: Vec<u8>,
+    exporter: Exporter,
+}
+
+impl KeySchedule {
+    fn derive(
+        shared_secret: &[u8],
+        prefix: &[u8],
+        recipient_public_key: &[u8],
+        info: &[u8],
+        algorithm: AeadAlgorithm,
+    ) -> Result<Self, SealError> {
+        let mut hasher = Sha256::new();
+        hasher.update(prefix);
+        hasher.update(recipient_public_key);
+        hasher.update((info.len() as u32).to_be_bytes());
+        hasher.update(info);
+        let context: [u8; 32] = hasher.finalize().into();
+
+        let hk = Hkdf::<Sha256>::new(Some(b"zks-sealed-box-v1"), shared_secret);
+        let expand = |label: &[u8], out: &mut [u8]| {
+            hk.expand_multi_info(&[label, &context], out).map_err(|_| SealError::Crypto)
+        };
+        let mut key = Zeroizing::new([0u8; 32]);
+        expand(b"key", key.as_mut())?;
+        let mut nonce = vec![0u8; algorithm.nonce_len()];
+        expand(b"nonce", &mut nonce)?;
+        let mut secret = Zeroizing::new([0u8; 32]);
+        expand(b"exp", secret.as_mut())?;
+
+        Ok(Self {
+            context,
+            cipher: AeadBackend::new(algorithm, &key).map_err(|_| SealError::Crypto)?,
+            nonce,
+            exporter: Exporter { secret },
+        })
+    }
+}
+
+/// Borrowed fields of a sealed box
+struct ParsedBox<'a> {
+    mode: SealMode,
+    algorithm: AeadAlgorithm,
+    /// Header, KEM ciphertext and sender key
+    prefix: &'a [u8],
+    kem_ciphertext: &'a [u8],
+    sender_key: &'a [u8],
+    /// Ciphertext and tag
+    body: &'a [u8],
+    signature: &'a [u8],
+}
+
+impl<'a> ParsedBox<'a> {
+    fn parse(sealed: &'a [u8]) -> Result<Self, SealError> {
+        if sealed.len() < SEALED_BOX_HEADER_LEN {
+            return Err(SealError::Malformed);
+        }
+        if sealed[..4] != SEALED_BOX_MAGIC {
+            return Err(SealError::BadMagic);
+        }
+        if sealed[4] != SEALED_BOX_VERSION {
+            return Err(SealError::UnsupportedVersion(sealed[4]));
+        }
+        let mode = match sealed[5] {
+            0x00 => SealMode::Base,
+            0x01 => SealMode::Auth,
+            _ => return Err(SealError::Malformed),
+        };
+        let algorithm = AeadAlgorithm::from_u8(sealed[6]).ok_or(SealError::Malformed)?;
+        let key_len = u16::from_be_bytes([sealed[7], sealed[8]]) as usize;
+        let signature_len = u16::from_be_bytes([sealed[9], sealed[10]]) as usize;
+        if (mode == SealMode::Base) != (key_len == 0 && signature_len == 0) {
+            return Err(SealError::Malformed);
+        }
+
+        let prefix_len = SEALED_BOX_HEADER_LEN + KEM_CIPHERTEXT_SIZE + key_len;
+        if sealed.len() < prefix_len + AEAD_TAG_LEN + signature_len {
+            return Err(SealError::Malformed);
+        }
+        let body_end = sealed.len() - signature_len;
+        Ok(Self {
+            mode,
+            algorithm,
+            prefix: &sealed[..prefix_len],
+            kem_ciphertext: &sealed[SEALED_BOX_HEADER_LEN..SEALED_BOX_HEADER_LEN + KEM_CIPHERTEXT_SIZE],
+            sender_key: &sealed[SEALED_BOX_HEADER_LEN + KEM_CIPHERTEXT_SIZE..prefix_len],
+            body: &sealed[prefix_len..body_end],
+            signature: &sealed[body_end..],
+        })
+    }
+}
+
+/// Errors produced when sealing or opening a box
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SealError {
+    /// Not a sealed box
+    BadMagic,
+    /// Box written by an unknown format version
+    UnsupportedVersion(u8),
+    /// Box is truncated or its header is inconsistent
+    Malformed,
+    /// ML-KEM encapsulation or decapsulation failed
+    Kem,
+    /// ML-DSA signing or verification failed
+    Signature,
+    /// Box is unsigned or signed by another key
+    UnexpectedSender,
+    /// Decryption or key derivation failed
+    Crypto,
+    /// Requested export exceeds [`MAX_EXPORT_LEN`]
+    ExportTooLong(usize),
+}
+
+impl std::fmt::Display for SealError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SealError::BadMagic => write!(f, "Not a sealed box"),
+            SealError::UnsupportedVersion(version) => write!(f, "Unsupported sealed box version {}", version),
+            SealError::Malformed => write!(f, "Malformed sealed box"),
+            SealError::Kem => write!(f, "ML-KEM operation failed"),
+            SealError::Signature => write!(f, "Sender signature invalid"),
+            SealError::UnexpectedSender => write!(f, "Sealed box not signed by the expected sender"),
+            SealError::Crypto => write!(f, "Sealed box failed to decrypt"),
+            SealError::ExportTooLong(len) => write!(f, "Export of {} bytes exceeds {}", len, MAX_EXPORT_LEN),
+        }
+    }
+}
+
+impl std::error::Error for SealError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_mode_round_trip_and_exports() {
+        let recipient = MlKem::generate_keypair().unwrap();
+        let (sealed, sender_exporter) = seal(&recipient.public_key, b"mailbox", b"msg-7", b"offline message").unwrap();
+        assert_eq!(sealed.len(), SEALED_BOX_HEADER_LEN + KEM_CIPHERTEXT_SIZE + 15 + AEAD_TAG_LEN);
+
+        let opened = open(&recipient, b"mailbox", b"msg-7", &sealed).unwrap();
+        assert_eq!(opened.plaintext, b"offline message".to_vec());
+        assert!(opened.sender.is_none());
+
+        // Exported secrets agree per context and differ across contexts
+        let ours = sender_exporter.export(b"file key", 32).unwrap();
+        assert_eq!(*ours, *opened.exporter.export(b"file key", 32).unwrap());
+        assert_ne!(*ours, *opened.exporter.export(b"other", 32).unwrap());
+        assert!(opened.exporter.export(b"file key", MAX_EXPORT_LEN + 1).is_err());
+
+        // info and aad must match, and only the recipient can open
+        assert_eq!(open(&recipient, b"files", b"msg-7", &sealed).err(), Some(SealError::Crypto));
+        assert_eq!(open(&recipient, b"mailbox", b"msg-8", &sealed).err(), Some(SealError::Crypto));
+        let stranger = MlKem::generate_keypair().unwrap();
+        assert!(open(&stranger, b"mailbox", b"msg-7", &sealed).is_err());
+        assert_eq!(open_from(&recipient, &[0u8; 32], b"mailbox", b"msg-7", &sealed).err(), Some(SealError::UnexpectedSender));
+    }
+
+    #[test]
+    fn test_auth_mode_binds_sender() {
+        let recipient = MlKem::generate_keypair().unwrap();
+        let alice = MlDsa::generate_keypair().unwrap();
+        let mallory = MlDsa::generate_keypair().unwrap();
+        let (sealed, _) = seal_auth(&recipient.public_key, &alice, b"mailbox", b"", b"signed").unwrap();
+
+        let opened = open_from(&recipient, alice.verifying_key(), b"mailbox", b"", &sealed).unwrap();
+        assert_eq!(opened.plaintext, b"signed".to_vec());
+        assert_eq!(
+            open_from(&recipient, mallory.verifying_key(), b"mailbox", b"", &sealed).err(),
+            Some(SealError::UnexpectedSender)
+        );
+
+        // The signature covers the ciphertext
+        let mut tampered = sealed.clone();
+        let body_start = SEALED_BOX_HEADER_LEN + KEM_CIPHERTEXT_SIZE + alice.verifying_key().len();
+        tampered[body_start] ^= 1;
+        assert_eq!(open(&recipient, b"mailbox", b"", &tampered).err(), Some(SealError::Signature));
+
+        // Downgrading to an anonymous box breaks the header checks
+        let mut downgraded = sealed;
+        downgraded[5] = SealMode::Base as u8;
+        assert_eq!(open(&recipient, b"mailbox", b"", &downgraded).err(), Some(SealError::Malformed));
+    }
+
+    #[test]
+    fn test_rejects_malformed_boxes() {
+        let recipient = MlKem::generate_keypair().unwrap();
+        let (sealed, _) = seal(&recipient.public_key, b"", b"", b"x").unwrap();
+
+        assert_eq!(open(&recipient, b"", b"", &sealed[..40]).err(), Some(SealError::Malformed));
+        let mut bad_magic = sealed.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(open(&recipient, b"", b"", &bad_magic).err(), Some(SealError::BadMagic));
+        let mut bad_version = sealed;
+        bad_version[4] = 0x09;
+        assert_eq!(open(&recipient, b"", b"", &bad_version).err(), Some(SealError::UnsupportedVersion(0x09)));
+    }
+}
@@ -0,0 +1,644 @@
+//! Password-Protected Keystore
+//!
+//! Long-term identities (an ML-KEM keypair for receiving and an ML-DSA keypair for
+//! signing) live in one encrypted file per identity, `<dir>/<name>.zkskey`.
+//!
+//! # File Format
+//! ```text
+//! magic "ZKSK" (4) | version (1) | memory KiB (4, BE) | iterations (4, BE) | parallelism (4, BE)
+//! | salt (16) | nonce (24) | ciphertext | tag (16)
+//! ```
+//! The body is sealed with XChaCha20-Poly1305 under an Argon2id key derived from
+//! the password, with the header as associated data. It holds every key generation
+//! of the identity: the current one and the retired ones kept as rotation history,
+//! so data sealed to an old key can still be opened.
+//!
+//! Files are replaced atomically (temporary file, then rename) and created with
+//! owner-only permissions on Unix. Decrypted key material only lives in zeroizing
+//! buffers.
+
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+use zks_pqcrypto::{MlDsa, MlDsaKeypair, MlKem, MlKemKeypair};
+
+use crate::aead_backend::{AeadAlgorithm, AeadBackend, AEAD_TAG_LEN, XNONCE_LEN};
+
+/// Magic bytes identifying a keystore file
+pub const KEYSTORE_MAGIC: [u8; 4] = *b"ZKSK";
+
+/// Current keystore file format version
+pub const KEYSTORE_VERSION: u8 = 0x01;
+
+/// File extension of identity files
+pub const KEYSTORE_EXTENSION: &str = "zkskey";
+
+/// Longest identity name
+pub const MAX_IDENTITY_NAME_LEN: usize = 64;
+
+/// Largest Argon2id memory cost accepted, in KiB (4 GiB)
+pub const MAX_KDF_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+
+/// Most Argon2id passes accepted
+pub const MAX_KDF_ITERATIONS: u32 = 64;
+
+/// Most Argon2id lanes accepted
+pub const MAX_KDF_PARALLELISM: u32 = 64;
+
+const SALT_LEN: usize = 16;
+
+/// Length of the authenticated file header
+const HEADER_LEN: usize = 4 + 1 + 12 + SALT_LEN + XNONCE_LEN;
+
+/// Argon2id cost parameters
+///
+/// Parameters above [`MAX_KDF_MEMORY_KIB`], [`MAX_KDF_ITERATIONS`] or
+/// [`MAX_KDF_PARALLELISM`] are refused, so a planted file cannot make loading
+/// allocate or compute without bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory in KiB
+    pub memory_kib: u32,
+    /// Number of passes
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// OWASP baseline for Argon2id: 19 MiB, 2 passes, 1 lane
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// One generation of an identity's keys
+pub struct IdentityKeys {
+    generation: u32,
+    created_at: u64,
+    retired_at: Option<u64>,
+    kem: MlKemKeypair,
+    signing: MlDsaKeypair,
+}
+
+impl IdentityKeys {
+    fn generate(generation: u32) -> Result<Self, KeystoreError> {
+        Ok(Self {
+            generation,
+            created_at: unix_now(),
+            retired_at: None,
+            kem: MlKem::generate_keypair().map_err(|e| KeystoreError::KeyGeneration(e.to_string()))?,
+            signing: MlDsa::generate_keypair().map_err(|e| KeystoreError::KeyGeneration(e.to_string()))?,
+        })
+    }
+
+    /// Generation number, starting at 0 and bumped by every rotation
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Creation time in seconds since the Unix epoch
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    /// Retirement time in seconds since the Unix epoch, `None` for the current keys
+    pub fn retired_at(&self) -> Option<u64> {
+        self.retired_at
+    }
+
+    /// ML-KEM keypair for receiving (handshakes, sealed boxes)
+    pub fn kem(&self) -> &MlKemKeypair {
+        &self.kem
+    }
+
+    /// ML-DSA keypair for signing
+    pub fn signing(&self) -> &MlDsaKeypair {
+        &self.signing
+    }
+}
+
+impl std::fmt::Debug for IdentityKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdentityKeys")
+            .field("generation", &self.generation)
+            .field("created_at", &self.created_at)
+            .field("retired_at", &self.retired_at)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A named identity with its current keys and rotation history
+#[derive(Debug)]
+pub struct Identity {
+    name: String,
+    /// Oldest first; the last entry is current
+    generations: Vec<IdentityKeys>,
+}
+
+impl Identity {
+    /// Identity name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Keys in use
+    pub fn current(&self) -> &IdentityKeys {
+        self.generations.last().expect("identity has at least one generation")
+    }
+
+    /// Retired keys, oldest first
+    pub fn history(&self) -> &[IdentityKeys] {
+        &self.generations[..self.generations.len() - 1]
+    }
+
+    /// Keys of a given generation, current or retired
+    pub fn generation(&self, generation: u32) -> Option<&IdentityKeys> {
+        self.generations.iter().find(|keys| keys.generation == generation)
+    }
+
+    fn encode(&self) -> Zeroizing<Vec<u8>> {
+        let mut out = Zeroizing::new(Vec::new());
+        out.push(self.name.len() as u8);
+        out.extend_from_slice(self.name.as_bytes());
+        out.extend_from_slice(&(self.generations.len() as u32).to_be_bytes());
+        for keys in &self.generations {
+            out.extend_from_slice(&keys.generation.to_be_bytes());
+            out.extend_from_slice(&keys.created_at.to_be_bytes());
+            out.extend_from_slice(&keys.retired_at.unwrap_or(0).to_be_bytes());
+            for field in [
+                keys.kem.public_key(),
+                keys.kem.secret_key(),
+                keys.signing.verifying_key(),
+                keys.signing.signing_key(),
+            ] {
+                out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+                out.extend_from_slice(field);
+            }
+        }
+        out
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, KeystoreError> {
+        let mut r = Reader { data };
+        let name_len = r.u8()? as usize;
+        let name = String::from_utf8(r.take(name_len)?.to_vec()).map_err(|_| KeystoreError::Malformed)?;
+        let count = r.u32()? as usize;
+        if count == 0 || count > r.data.len() {
+            return Err(KeystoreError::Malformed);
+        }
+        let mut generations = Vec::with_capacity(count);
+        for _ in 0..count {
+            let generation = r.u32()?;
+            let created_at = r.u64()?;
+            let retired_at = Some(r.u64()?).filter(|&t| t != 0);
+            let kem_public = r.field()?.to_vec();
+            let mut kem_secret = Zeroizing::new(r.field()?.to_vec());
+            let kem = MlKemKeypair::from_bytes(kem_public, core::mem::take(&mut *kem_secret))
+                .map_err(|_| KeystoreError::Malformed)?;
+            let verifying = r.field()?.to_vec();
+            let mut signing = Zeroizing::new(r.field()?.to_vec());
+            let signing = MlDsaKeypair::from_bytes(verifying, core::mem::take(&mut *signing))
+                .map_err(|_| KeystoreError::Malformed)?;
+            generations.push(IdentityKeys {
+                generation,
+                created_at,
+                retired_at,
+                kem,
+                signing,
+            });
+        }
+        if !r.data.is_empty() {
+            return Err(KeystoreError::Malformed);
+        }
+        Ok(Self { name, generations })
+    }
+}
+
+/// Directory of password-protected identity files
+pub struct Keystore {
+    dir: PathBuf,
+    params: KdfParams,
+}
+
+impl Keystore {
+    /// Open the keystore in `dir`, creating the directory if needed
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, KeystoreError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            params: KdfParams::default(),
+        })
+    }
+
+    /// Argon2id parameters for files written from now on
+    ///
+    /// Existing files record their own parameters and keep loading.
+    pub fn with_kdf_params(mut self, params: KdfParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Names of all identities in the keystore
+    pub fn list(&self) -> Result<Vec<String>, KeystoreError> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == KEYSTORE_EXTENSION) {
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    if valid_name(name) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Whether an identity named `name` exists
+    pub fn contains(&self, name: &str) -> bool {
+        valid_name(name) && self.path(name).exists()
+    }
+
+    /// Generate a new identity and store it under `password`
+    pub fn create(&self, name: &str, password: &[u8]) -> Result<Identity, KeystoreError> {
+        check_name(name)?;
+        let identity = self.store_new(name, password, IdentityKeys::generate(0)?)?;
+        info!("🔑 Created identity {}", name);
+        Ok(identity)
+    }
+
+    /// Store existing keys as a new identity, e.g. keys recovered from a backup
+    pub fn import(
+        &self,
+        name: &str,
+        password: &[u8],
+        kem: MlKemKeypair,
+        signing: MlDsaKeypair,
+    ) -> Result<Identity, KeystoreError> {
+        check_name(name)?;
+        let keys = IdentityKeys {
+            generation: 0,
+            created_at: unix_now(),
+            retired_at: None,
+            kem,
+            signing,
+        };
+        let identity = self.store_new(name, password, keys)?;
+        info!("🔑 Imported identity {}", name);
+        Ok(identity)
+    }
+
+    /// Decrypt an identity
+    pub fn load(&self, name: &str, password: &[u8]) -> Result<Identity, KeystoreError> {
+        check_name(name)?;
+        let path = self.path(name);
+        let sealed = match std::fs::read(&path) {
+            Ok(sealed) => sealed,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(KeystoreError::NotFound(name.to_string())),
+            Err(e) => return Err(e.into()),
+        };
+        if sealed.len() < HEADER_LEN + AEAD_TAG_LEN {
+            return Err(KeystoreError::Malformed);
+        }
+        let (header, ciphertext) = sealed.split_at(HEADER_LEN);
+        if header[..4] != KEYSTORE_MAGIC {
+            return Err(KeystoreError::BadMagic);
+        }
+        if header[4] != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(header[4]));
+        }
+        let params = KdfParams {
+            memory_kib: u32::from_be_bytes(header[5..9].try_into().expect("4-byte slice")),
+            iterations: u32::from_be_bytes(header[9..13].try_into().expect("4-byte slice")),
+            parallelism: u32::from_be_bytes(header[13..17].try_into().expect("4-byte slice")),
+        };
+        let salt = &header[17..17 + SALT_LEN];
+        let nonce = &header[17 + SALT_LEN..];
+
+        let cipher = file_cipher(password, salt, &params)?;
+        let body = Zeroizing::new(cipher.decrypt(nonce, ciphertext, header).map_err(|_| {
+            warn!("Wrong password or corrupted keystore file for {}", name);
+            KeystoreError::Decryption
+        })?);
+        let identity = Identity::decode(&body)?;
+        if identity.name != name {
+            return Err(KeystoreError::Malformed);
+        }
+        Ok(identity)
+    }
+
+    /// Load an identity, creating it on first use
+    ///
+    /// Gives servers a stable identity across restarts.
+    pub fn load_or_create(&self, name: &str, password: &[u8]) -> Result<Identity, KeystoreError> {
+        if self.contains(name) {
+            self.load(name, password)
+        } else {
+            self.create(name, password)
+        }
+    }
+
+    /// Replace an identity's keys with a new generation, keeping the old ones as history
+    pub fn rotate(&self, name: &str, password: &[u8]) -> Result<Identity, KeystoreError> {
+        let mut identity = self.load(name, password)?;
+        let current = identity.generations.last_mut().expect("identity has at least one generation");
+        let generation = current.generation.checked_add(1).ok_or(KeystoreError::Malformed)?;
+        current.retired_at = Some(unix_now());
+        identity.generations.push(IdentityKeys::generate(generation)?);
+        self.save(&identity, password)?;
+        info!("🔑 Rotated identity {} to generation {}", name, generation);
+        Ok(identity)
+    }
+
+    /// Re-encrypt an identity under a new password
+    pub fn change_password(&self, name: &str, old_password: &[u8], new_password: &[u8]) -> Result<(), KeystoreError> {
+        let identity = self.load(name, old_password)?;
+        self.save(&identity, new_password)
+    }
+
+    fn store_new(&self, name: &str, password: &[u8], keys: IdentityKeys) -> Result<Identity, KeystoreError> {
+        if self.path(name).exists() {
+            return Err(KeystoreError::AlreadyExists(name.to_string()));
+        }
+        let identity = Identity {
+            name: name.to_string(),
+            generations: vec![keys],
+        };
+        self.save(&identity, password)?;
+        Ok(identity)
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", name, KEYSTORE_EXTENSION))
+    }
+
+    fn save(&self, identity: &Identity, password: &[u8]) -> Result<(), KeystoreError> {
+        let mut header = [0u8; HEADER_LEN];
+        header[..4].copy_from_slice(&KEYSTORE_MAGIC);
+        header[4] = KEYSTORE_VERSION;
+        header[5..9].copy_from_slice(&self.params.memory_kib.to_be_bytes());
+        header[9..13].copy_from_slice(&self.params.iterations.to_be_bytes());
+        header[13..17].copy_from_slice(&self.params.parallelism.to_be_bytes());
+        getrandom::getrandom(&mut header[17..]).map_err(|_| KeystoreError::Rng)?;
+
+        let cipher = file_cipher(password, &header[17..17 + SALT_LEN], &self.params)?;
+        let body = identity.encode();
+        let ciphertext = cipher
+            .encrypt(&header[17 + SALT_LEN..], &body, &header)
+            .map_err(|_| KeystoreError::Decryption)?;
+
+        let path = self.path(&identity.name);
+        let mut tmp_path = OsString::from(path.as_os_str());
+        tmp_path.push(".tmp");
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut tmp = options.open(&tmp_path)?;
+        tmp.write_all(&header)?;
+        tmp.write_all(&ciphertext)?;
+        tmp.sync_all()?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+}
+
+/// Derive the file cipher from the password with Argon2id
+fn file_cipher(password: &[u8], salt: &[u8], params: &KdfParams) -> Result<AeadBackend, KeystoreError> {
+    if params.memory_kib > MAX_KDF_MEMORY_KIB
+        || params.iterations > MAX_KDF_ITERATIONS
+        || params.parallelism > MAX_KDF_PARALLELISM
+    {
+        warn!("Refusing Argon2id parameters {:?}", params);
+        return Err(KeystoreError::Kdf(format!("parameters exceed the limits: {:?}", params)));
+    }
+    let params = Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32))
+        .map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password, salt, key.as_mut())
+        .map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+    AeadBackend::new(AeadAlgorithm::XChaCha20Poly1305, &key).map_err(|_| KeystoreError::Decryption)
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_IDENTITY_NAME_LEN
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+fn check_name(name: &str) -> Result<(), KeystoreError> {
+    if valid_name(name) {
+        Ok(())
+    } else {
+        Err(KeystoreError::InvalidName(name.to_string()))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Cursor over a decrypted keystore body
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], KeystoreError> {
+        if self.data.len() < len {
+            return Err(KeystoreError::Malformed);
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, KeystoreError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, KeystoreError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().expect("4-byte slice")))
+    }
+
+    fn u64(&mut self) -> Result<u64, KeystoreError> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().expect("8-byte slice")))
+    }
+
+    /// Length-prefixed field
+    fn field(&mut self) -> Result<&'a [u8], KeystoreError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+/// Errors produced by the keystore
+#[derive(Debug)]
+pub enum KeystoreError {
+    /// Reading or writing a keystore file failed
+    Io(std::io::Error),
+    /// Identity names are 1-64 ASCII letters, digits, `-` or `_`
+    InvalidName(String),
+    /// No identity with this name
+    NotFound(String),
+    /// An identity with this name already exists
+    AlreadyExists(String),
+    /// Not a keystore file
+    BadMagic,
+    /// File written by an unknown format version
+    UnsupportedVersion(u8),
+    /// File is truncated or its contents are inconsistent
+    Malformed,
+    /// Wrong password, or the file was modified
+    Decryption,
+    /// Argon2id rejected its parameters
+    Kdf(String),
+    /// ML-KEM or ML-DSA key generation failed
+    KeyGeneration(String),
+    /// RNG unavailable for salt or nonce
+    Rng,
+}
+
+impl std::fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeystoreError::Io(e) => write!(f, "Keystore I/O error: {}", e),
+            KeystoreError::InvalidName(name) => write!(f, "Invalid identity name {:?}", name),
+            KeystoreError::NotFound(name) => write!(f, "No identity named {}", name),
+            KeystoreError::AlreadyExists(name) => write!(f, "Identity {} already exists", name),
+            KeystoreError::BadMagic => write!(f, "Not a keystore file"),
+            KeystoreError::UnsupportedVersion(version) => write!(f, "Unsupported keystore version {}", version),
+            KeystoreError::Malformed => write!(f, "Malformed keystore file"),
+            KeystoreError::Decryption => write!(f, "Wrong password or corrupted keystore file"),
+            KeystoreError::Kdf(e) => write!(f, "Argon2id failed: {}", e),
+            KeystoreError::KeyGeneration(e) => write!(f, "Key generation failed: {}", e),
+            KeystoreError::Rng => write!(f, "RNG unavailable"),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(e: std::io::Error) -> Self {
+        KeystoreError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keystore in a fresh temporary directory, removed on drop
+    struct TempKeystore {
+        keystore: Keystore,
+        dir: PathBuf,
+    }
+
+    impl Drop for TempKeystore {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn temp_keystore() -> TempKeystore {
+        let mut id = [0u8; 8];
+        getrandom::getrandom(&mut id).unwrap();
+        let dir = std::env::temp_dir().join(format!("zks-keystore-{}", hex::encode(id)));
+        // Cheap parameters keep the tests fast
+        let keystore = Keystore::open(&dir).unwrap().with_kdf_params(KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        });
+        TempKeystore { keystore, dir }
+    }
+
+    #[test]
+    fn test_identity_survives_reload() {
+        let store = temp_keystore();
+        let keystore = &store.keystore;
+        let created = keystore.load_or_create("server", b"s3cret").unwrap();
+        let loaded = keystore.load_or_create("server", b"s3cret").unwrap();
+
+        assert_eq!(loaded.current().kem().public_key(), created.current().kem().public_key());
+        assert_eq!(loaded.current().signing().signing_key(), created.current().signing().signing_key());
+        assert_eq!(keystore.list().unwrap(), vec!["server".to_string()]);
+
+        // No secret key bytes reach the disk in the clear
+        let file = std::fs::read(store.dir.join("server.zkskey")).unwrap();
+        let secret = created.current().kem().secret_key();
+        assert!(!file.windows(32).any(|w| w == &secret[..32]));
+
+        assert!(matches!(keystore.load("server", b"wrong"), Err(KeystoreError::Decryption)));
+        assert!(matches!(keystore.load("other", b"s3cret"), Err(KeystoreError::NotFound(_))));
+        assert!(matches!(keystore.create("server", b"s3cret"), Err(KeystoreError::AlreadyExists(_))));
+        assert!(matches!(keystore.create("../escape", b"x"), Err(KeystoreError::InvalidName(_))));
+    }
+
+    #[test]
+    fn test_rotation_keeps_history() {
+        let store = temp_keystore();
+        let keystore = &store.keystore;
+        let first = keystore.create("node", b"pw").unwrap();
+        keystore.create("backup", b"pw").unwrap();
+        let rotated = keystore.rotate("node", b"pw").unwrap();
+
+        assert_eq!(rotated.current().generation(), 1);
+        assert_ne!(rotated.current().kem().public_key(), first.current().kem().public_key());
+        assert_eq!(rotated.history().len(), 1);
+        let old = rotated.generation(0).unwrap();
+        assert!(old.retired_at().is_some());
+        assert_eq!(old.kem().public_key(), first.current().kem().public_key());
+
+        keystore.change_password("node", b"pw", b"new pw").unwrap();
+        let reloaded = keystore.load("node", b"new pw").unwrap();
+        assert_eq!(reloaded.history().len(), 1);
+        assert!(reloaded.current().retired_at().is_none());
+        assert_eq!(keystore.list().unwrap(), vec!["backup".to_string(), "node".to_string()]);
+    }
+
+    #[test]
+    fn test_tampered_header_is_rejected() {
+        let store = temp_keystore();
+        store.keystore.create("id", b"pw").unwrap();
+        let path = store.dir.join("id.zkskey");
+        let original = std::fs::read(&path).unwrap();
+
+        // KDF parameters are authenticated
+        let mut tampered = original.clone();
+        tampered[12] ^= 1;
+        std::fs::write(&path, &tampered).unwrap();
+        assert!(store.keystore.load("id", b"pw").is_err());
+
+        // Costs beyond the limits are refused before any work is done
+        for (offset, value) in [(5, MAX_KDF_MEMORY_KIB + 1), (9, u32::MAX), (13, MAX_KDF_PARALLELISM + 1)] {
+            tampered = original.clone();
+            tampered[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+            std::fs::write(&path, &tampered).unwrap();
+            assert!(matches!(store.keystore.load("id", b"pw"), Err(KeystoreError::Kdf(_))));
+        }
+
+        tampered = original;
+        tampered[4] = 0x07;
+        std::fs::write(&path, &tampered).unwrap();
+        assert!(matches!(store.keystore.load("id", b"pw"), Err(KeystoreError::UnsupportedVersion(0x07))));
+    }
+}
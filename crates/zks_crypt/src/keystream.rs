@@ -0,0 +1,245 @@
+//! Seekable XOR Keystream
+//!
+//! The computational XOR layer of [`WasifVernam`](crate::wasif_vernam::WasifVernam)
+//! draws from one ChaCha20 keystream per swarm seed. The ChaCha20 key is derived
+//! from the seed once with HKDF; a message at keystream offset `o` uses the bytes
+//! starting at `o`, so any position is reached by seeking instead of re-deriving.
+//!
+//! ChaCha20's block counter is 32 bits and the last counter value cannot be
+//! used, so each nonce covers a segment of 2^32 − 1 blocks (just under 256 GiB).
+//! The 64-bit offset is split into a segment, which selects the nonce, and a
+//! position within it; messages crossing a segment boundary continue in the next one.
+//!
+//! This replaces the per-message HKDF expansion, which was slow and capped at
+//! 8160 bytes of output; [`hkdf_keystream`] is kept for envelopes sealed that way.
+//!
+//! One-time pads and the TRUE OTP buffers record which keystream ranges they have
+//! used in a [`KeystreamTracker`].
+
+use alloc::collections::BTreeMap;
+use alloc::{format, vec, vec::Vec};
+use core::fmt;
+
+use chacha20::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+use chacha20::ChaCha20;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+const BLOCK_LEN: u64 = 64;
+
+/// Keystream bytes covered by one nonce: every block the 32-bit counter can address
+const SEGMENT_LEN: u64 = BLOCK_LEN * u32::MAX as u64;
+
+/// ChaCha20 keystream keyed from a swarm seed
+#[derive(Clone)]
+pub struct VernamKeystream {
+    key: Zeroizing<[u8; 32]>,
+}
+
+impl VernamKeystream {
+    /// Derive the keystream key from a swarm seed
+    pub fn new(seed: &[u8; 32]) -> Self {
+        let hk = Hkdf::<Sha256>::new(Some(b"zks-vernam-keystream"), seed);
+        let mut key = Zeroizing::new([0u8; 32]);
+        hk.expand(b"chacha20-keystream-v1", key.as_mut())
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        Self { key }
+    }
+
+    /// XOR `data` with the keystream starting at byte `offset`
+    ///
+    /// Works for any length; applying it twice restores the input.
+    pub fn apply(&self, offset: u64, data: &mut [u8]) {
+        let mut offset = offset;
+        let mut rest = data;
+        while !rest.is_empty() {
+            let segment = offset / SEGMENT_LEN;
+            let within = offset % SEGMENT_LEN;
+            let take = ((SEGMENT_LEN - within).min(rest.len() as u64)) as usize;
+            let (chunk, tail) = rest.split_at_mut(take);
+
+            let mut nonce = [0u8; 12];
+            nonce[4..].copy_from_slice(&segment.to_be_bytes());
+            let mut cipher = ChaCha20::new((&*self.key).into(), &nonce.into());
+            cipher.seek(within);
+            cipher.apply_keystream(chunk);
+
+            offset = offset.wrapping_add(take as u64);
+            rest = tail;
+        }
+    }
+
+    /// `length` keystream bytes starting at `offset`
+    pub fn generate(&self, offset: u64, length: usize) -> Zeroizing<Vec<u8>> {
+        let mut keystream = Zeroizing::new(vec![0u8; length]);
+        self.apply(offset, &mut keystream);
+        keystream
+    }
+}
+
+impl fmt::Debug for VernamKeystream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VernamKeystream").finish_non_exhaustive()
+    }
+}
+
+/// Keystream of the legacy HKDF XOR mode: one HKDF expansion per message
+///
+/// Returns `None` beyond HKDF-SHA256's 8160-byte output limit.
+pub fn hkdf_keystream(seed: &[u8; 32], offset: u64, length: usize) -> Option<Zeroizing<Vec<u8>>> {
+    let hk = Hkdf::<Sha256>::new(Some(b"zks-vernam-keystream"), seed);
+    let info = format!("offset-{}", offset);
+    let mut keystream = Zeroizing::new(vec![0u8; length]);
+    hk.expand(info.as_bytes(), &mut keystream).ok()?;
+    Some(keystream)
+}
+
+/// Keystream position where Bob's send lane starts; Alice sends below it
+pub const BOB_LANE_START: u64 = 1 << 63;
+
+/// Disjoint used ranges tracked before the oldest are folded into the floor
+pub const MAX_TRACKED_RANGES: usize = 1024;
+
+/// Record of keystream ranges already used
+///
+/// Ranges are kept merged; once more than [`MAX_TRACKED_RANGES`] gaps remain, the
+/// oldest range is dropped and everything below its end is refused from then on.
+pub struct KeystreamTracker {
+    /// start -> end (exclusive) of used ranges
+    ranges: BTreeMap<u64, u64>,
+    /// Positions below this are refused
+    floor: u64,
+}
+
+impl KeystreamTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self {
+            ranges: BTreeMap::new(),
+            floor: 0,
+        }
+    }
+
+    /// Mark `[position, position + length)` as used
+    ///
+    /// Returns `false` without recording anything if the range overlaps one already used.
+    pub fn claim(&mut self, position: u64, length: usize) -> bool {
+        let Some(mut end) = position.checked_add(length as u64) else {
+            return false;
+        };
+        if length == 0 {
+            return true;
+        }
+        if position < self.floor {
+            return false;
+        }
+        if let Some((_, &prev_end)) = self.ranges.range(..=position).next_back() {
+            if prev_end > position {
+                return false;
+            }
+        }
+        if let Some((&next_start, _)) = self.ranges.range(position..).next() {
+            if next_start < end {
+                return false;
+            }
+        }
+
+        // Merge with touching neighbours
+        let mut start = position;
+        if let Some((&prev_start, &prev_end)) = self.ranges.range(..position).next_back() {
+            if prev_end == position {
+                self.ranges.remove(&prev_start);
+                start = prev_start;
+            }
+        }
+        if let Some(next_end) = self.ranges.remove(&end) {
+            end = next_end;
+        }
+        self.ranges.insert(start, end);
+
+        while self.ranges.len() > MAX_TRACKED_RANGES {
+            if let Some((_, oldest_end)) = self.ranges.pop_first() {
+                self.floor = oldest_end;
+            }
+        }
+        true
+    }
+
+    /// Floor and used ranges (for persistence)
+    #[cfg(feature = "std")]
+    pub(crate) fn parts(&self) -> (u64, impl Iterator<Item = (u64, u64)> + '_) {
+        (self.floor, self.ranges.iter().map(|(&start, &end)| (start, end)))
+    }
+
+    /// Rebuild a tracker from persisted parts
+    #[cfg(feature = "std")]
+    pub(crate) fn from_parts(floor: u64, ranges: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        Self {
+            ranges: ranges.into_iter().collect(),
+            floor,
+        }
+    }
+}
+
+impl Default for KeystreamTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keystream_is_seekable() {
+        let keystream = VernamKeystream::new(&[7u8; 32]);
+        let whole = keystream.generate(100, 20_000);
+
+        // Any split of the range yields the same bytes, well past HKDF's limit
+        let mut parts = keystream.generate(100, 37).to_vec();
+        parts.extend_from_slice(&keystream.generate(137, 19_963));
+        assert_eq!(&whole[..], &parts[..]);
+
+        let mut data = b"round trip".to_vec();
+        keystream.apply(5, &mut data);
+        assert_ne!(&data[..], b"round trip");
+        keystream.apply(5, &mut data);
+        assert_eq!(&data[..], b"round trip");
+
+        assert_ne!(&VernamKeystream::new(&[8u8; 32]).generate(100, 64)[..], &whole[..64]);
+        assert!(hkdf_keystream(&[7u8; 32], 0, 8161).is_none());
+    }
+
+    #[test]
+    fn test_keystream_crosses_segments() {
+        let keystream = VernamKeystream::new(&[9u8; 32]);
+        let start = SEGMENT_LEN - 10;
+        let across = keystream.generate(start, 20);
+        assert_eq!(&across[..10], &keystream.generate(start, 10)[..]);
+        assert_eq!(&across[10..], &keystream.generate(SEGMENT_LEN, 10)[..]);
+
+        // Ranges running through the segment's last block
+        for before in [64, 100, 1000] {
+            let start = SEGMENT_LEN - before;
+            let across = keystream.generate(start, before as usize + 200);
+            assert_eq!(&across[..before as usize], &keystream.generate(start, before as usize)[..]);
+            assert_eq!(&across[before as usize..], &keystream.generate(SEGMENT_LEN, 200)[..]);
+        }
+
+        // The next segment is not the first one again
+        assert_ne!(&keystream.generate(SEGMENT_LEN, 64)[..], &keystream.generate(0, 64)[..]);
+    }
+
+    #[test]
+    fn test_tracker_floor_bounds_memory() {
+        let mut tracker = KeystreamTracker::new();
+        for i in 0..(MAX_TRACKED_RANGES as u64 + 1) {
+            assert!(tracker.claim(i * 10, 5));
+        }
+        assert_eq!(tracker.ranges.len(), MAX_TRACKED_RANGES);
+        assert!(!tracker.claim(0, 5), "Range folded into the floor is refused");
+        assert!(tracker.claim(5, 5));
+    }
+}
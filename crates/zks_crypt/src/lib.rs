@@ -1,17 +1,63 @@
 //! ZKS Protocol Cryptographic Primitives
 //! 
 //! This crate provides the core cryptographic primitives used by the ZKS Protocol,
-//! including the Wasif Vernam cipher, anti-replay protection, ciphertext scrambling,
-//! key rotation, and TRUE Vernam mode for information-theoretic security.
+//! including the Wasif Vernam cipher, group sender keys, anti-replay protection, ciphertext scrambling,
+//! key rotation, single-shot sealing to ML-KEM keys, an encrypted identity keystore with
+//! threshold backups, and TRUE Vernam mode for information-theoretic security.
+//!
+//! # Features
+//!
+//! - `std` (default): OS RNG, pad files, keystore and Shamir backups, group sessions,
+//!   HPKE sealing and async STREAM I/O
+//! - `drand` (default): drand beacons, entropy combining and the TRUE OTP buffers
+//! - `fetcher` (default): [`TrueVernamFetcher`](crate::true_vernam::TrueVernamFetcher)
+//!   and remote key fetching
+//! - `parallel`: multi-core STREAM encryption
+//!
+//! With default features off the crate is `no_std` + `alloc`: [`WasifVernam`](crate::wasif_vernam::WasifVernam),
+//! [`AntiReplayContainer`](crate::anti_replay::AntiReplayContainer),
+//! [`RecursiveChain`](crate::recursive_chain::RecursiveChain), the scramblers and
+//! [`constant_time`] remain, with randomness installed through [`rng::set_rng`].
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![deny(unsafe_code)]
 #![warn(missing_docs)]
 
+extern crate alloc;
+
+pub mod aead_backend;
 pub mod anti_replay;
+pub mod commitment;
 pub mod constant_time;
+#[cfg(feature = "drand")]
 pub mod drand;
+#[cfg(feature = "drand")]
+pub mod entropy;
+pub mod envelope;
+#[cfg(feature = "std")]
+pub mod group;
+#[cfg(feature = "std")]
+pub mod health;
+#[cfg(feature = "std")]
+pub mod hpke;
+#[cfg(feature = "std")]
+pub mod keystore;
+pub mod keystream;
+#[cfg(feature = "std")]
+pub mod pad;
+pub mod padding;
+pub mod pq_ratchet;
 pub mod recursive_chain;
+pub mod rekey;
+pub mod rng;
 pub mod scramble;
+pub mod session;
+#[cfg(feature = "std")]
+pub mod shamir;
+#[cfg(feature = "std")]
+pub mod stream_aead;
+mod sync;
+#[cfg(feature = "drand")]
 pub mod true_vernam;
 pub mod wasif_vernam;
 
@@ -0,0 +1,489 @@
+//! Pre-shared One-Time Pad Files
+//!
+//! The TRUE Vernam modes expand 32-byte seeds, so anything beyond 32 bytes is only
+//! computationally secure. A [`PadFile`] is a genuine one-time pad instead: both
+//! parties hold a copy of the same file of random bytes, exchanged out of band,
+//! and every keystream byte comes straight from it.
+//!
+//! # Lanes
+//! Alice sends with the first half of the pad and Bob with the second half, so the
+//! two directions never touch the same bytes. Envelopes record the absolute pad
+//! position, so lost or reordered messages still decrypt.
+//!
+//! # Never Reuse
+//! Like [`TrueVernamBuffer::consume`](crate::true_vernam::TrueVernamBuffer::consume),
+//! bytes are handed out once and then gone:
+//! - Consumption offsets live in a state file next to the pad (`<pad>.state`) and are
+//!   written and synced *before* any pad byte is used, so a crash can waste pad but
+//!   never reuse it
+//! - Used segments are overwritten with zeros in the pad file
+//! - Received ranges are tracked with a [`KeystreamTracker`], so a replayed
+//!   position is refused
+//!
+//! Encryption is refused once the send lane is exhausted, and a warning is logged
+//! each time the remaining pad crosses one of [`PAD_LOW_WATER_PERCENT`].
+//!
+//! # State File
+//! ```text
+//! magic "ZKSP" (4) | version (1) | pad_len (8, BE) | role (1) | next_send (8, BE)
+//! | floor (8, BE) | ranges (4, BE) | ranges × (start (8, BE) | end (8, BE))
+//! ```
+
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tracing::{debug, info, warn};
+use zeroize::Zeroizing;
+
+use crate::keystream::KeystreamTracker;
+
+/// Magic bytes identifying a pad state file
+pub const PAD_STATE_MAGIC: [u8; 4] = *b"ZKSP";
+
+/// Current pad state file version
+pub const PAD_STATE_VERSION: u8 = 1;
+
+/// Remaining send lane (in percent) at which a low-water warning is logged
+pub const PAD_LOW_WATER_PERCENT: [u64; 3] = [25, 10, 1];
+
+/// Smallest pad that still leaves each side one byte
+const MIN_PAD_LEN: u64 = 2;
+
+/// Chunk size used when generating or overwriting pad material
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Pre-shared one-time pad backed by a file
+pub struct PadFile {
+    path: PathBuf,
+    state_path: PathBuf,
+    pad_len: u64,
+    is_alice: bool,
+    /// Our send lane, `[start, end)`
+    send_lane: (u64, u64),
+    /// The peer's send lane, `[start, end)`
+    peer_lane: (u64, u64),
+    inner: Mutex<PadInner>,
+}
+
+/// Mutable pad state, guarded together with the file handle
+struct PadInner {
+    file: File,
+    next_send: u64,
+    received: KeystreamTracker,
+    /// Low-water marks already warned about
+    marks_passed: usize,
+}
+
+impl PadFile {
+    /// Write `len` bytes of OS randomness to a new pad file
+    ///
+    /// Copy the file to the peer out of band before opening it on either side.
+    pub fn generate(path: impl AsRef<Path>, len: u64) -> Result<(), PadError> {
+        if len < MIN_PAD_LEN {
+            return Err(PadError::InvalidPad(format!("pad must hold at least {} bytes", MIN_PAD_LEN)));
+        }
+        let mut file = OpenOptions::new().write(true).create_new(true).open(path.as_ref())?;
+        let mut chunk = Zeroizing::new(vec![0u8; CHUNK_SIZE]);
+        let mut remaining = len;
+        while remaining > 0 {
+            let n = remaining.min(CHUNK_SIZE as u64) as usize;
+            getrandom::getrandom(&mut chunk[..n])
+                .map_err(|e| PadError::InvalidPad(format!("RNG unavailable: {}", e)))?;
+            file.write_all(&chunk[..n])?;
+            remaining -= n as u64;
+        }
+        file.sync_all()?;
+        info!("🔐 Generated {} byte one-time pad at {}", len, path.as_ref().display());
+        Ok(())
+    }
+
+    /// Open a pad file, resuming from its state file if one exists
+    ///
+    /// Both parties open their copy of the same pad with opposite roles.
+    pub fn open(path: impl AsRef<Path>, is_alice: bool) -> Result<Self, PadError> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let pad_len = file.metadata()?.len();
+        if pad_len < MIN_PAD_LEN {
+            return Err(PadError::InvalidPad(format!("pad holds {} bytes, need at least {}", pad_len, MIN_PAD_LEN)));
+        }
+
+        let half = pad_len / 2;
+        let (send_lane, peer_lane) = if is_alice {
+            ((0, half), (half, pad_len))
+        } else {
+            ((half, pad_len), (0, half))
+        };
+
+        let mut state_path = OsString::from(path.as_os_str());
+        state_path.push(".state");
+        let state_path = PathBuf::from(state_path);
+
+        let (next_send, received) = match std::fs::read(&state_path) {
+            Ok(bytes) => decode_state(&bytes, pad_len, is_alice)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (send_lane.0, KeystreamTracker::new()),
+            Err(e) => return Err(e.into()),
+        };
+        if next_send < send_lane.0 || next_send > send_lane.1 {
+            return Err(PadError::InvalidPad(format!("send offset {} outside our lane", next_send)));
+        }
+
+        let pad = Self {
+            path,
+            state_path,
+            pad_len,
+            is_alice,
+            send_lane,
+            peer_lane,
+            inner: Mutex::new(PadInner {
+                file,
+                next_send,
+                received,
+                marks_passed: 0,
+            }),
+        };
+        pad.persist(&pad.lock())?;
+        pad.check_low_water(&mut pad.lock());
+        info!(
+            "🔐 Opened one-time pad {} ({} bytes left to send, is_alice: {})",
+            pad.path.display(),
+            pad.available(),
+            is_alice
+        );
+        Ok(pad)
+    }
+
+    /// Consume `count` fresh pad bytes for an outgoing message
+    ///
+    /// Returns the pad position to record in the envelope and the pad bytes, which
+    /// are overwritten on disk before this returns.
+    pub fn consume(&self, count: usize) -> Result<(u64, Zeroizing<Vec<u8>>), PadError> {
+        let mut inner = self.lock();
+        let available = self.send_lane.1 - inner.next_send;
+        if count as u64 > available {
+            warn!("🚨 One-time pad exhausted: requested {} bytes, {} left", count, available);
+            return Err(PadError::Exhausted { requested: count, available });
+        }
+
+        // Write ahead: the new offset is on disk before the bytes are used
+        let position = inner.next_send;
+        inner.next_send += count as u64;
+        self.persist(&inner)?;
+        let bytes = take(&mut inner.file, position, count)?;
+        self.check_low_water(&mut inner);
+
+        debug!("🔑 Consumed {} one-time pad bytes at position {}", count, position);
+        Ok((position, bytes))
+    }
+
+    /// Consume the pad bytes of an incoming message at `position`
+    ///
+    /// Fails if the range lies outside the peer's lane or overlaps a range already
+    /// received.
+    pub fn consume_received(&self, position: u64, count: usize) -> Result<Zeroizing<Vec<u8>>, PadError> {
+        let in_peer_lane = match position.checked_add(count as u64) {
+            Some(end) => position >= self.peer_lane.0 && end <= self.peer_lane.1,
+            None => false,
+        };
+        if !in_peer_lane {
+            warn!("🚨 Received pad range at position {} lies outside the peer's lane", position);
+            return Err(PadError::OutOfLane { position });
+        }
+
+        let mut inner = self.lock();
+        if !inner.received.claim(position, count) {
+            warn!("🚨 Received pad range at position {} was already used", position);
+            return Err(PadError::AlreadyUsed { position });
+        }
+        self.persist(&inner)?;
+        take(&mut inner.file, position, count)
+    }
+
+    /// Pad bytes left in our send lane
+    pub fn available(&self) -> u64 {
+        self.send_lane.1 - self.lock().next_send
+    }
+
+    /// Whether the send lane is below the first low-water mark
+    pub fn is_low(&self) -> bool {
+        self.available() * 100 <= self.lane_len() * PAD_LOW_WATER_PERCENT[0]
+    }
+
+    /// Total pad length in bytes
+    pub fn len(&self) -> u64 {
+        self.pad_len
+    }
+
+    /// Whether the pad holds no bytes (never true for an opened pad)
+    pub fn is_empty(&self) -> bool {
+        self.pad_len == 0
+    }
+
+    /// Whether we hold the Alice (first half) lane
+    pub fn is_alice(&self) -> bool {
+        self.is_alice
+    }
+
+    fn lane_len(&self) -> u64 {
+        self.send_lane.1 - self.send_lane.0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PadInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Log each low-water mark the send lane has newly fallen below
+    fn check_low_water(&self, inner: &mut PadInner) {
+        let remaining = self.send_lane.1 - inner.next_send;
+        while let Some(&mark) = PAD_LOW_WATER_PERCENT.get(inner.marks_passed) {
+            if remaining * 100 > self.lane_len() * mark {
+                break;
+            }
+            warn!(
+                "⚠️ One-time pad {} is below {}% ({} bytes left) - exchange a new pad",
+                self.path.display(),
+                mark,
+                remaining
+            );
+            inner.marks_passed += 1;
+        }
+    }
+
+    /// Atomically replace the state file with the current offsets
+    fn persist(&self, inner: &PadInner) -> Result<(), PadError> {
+        let mut out = Vec::with_capacity(34);
+        out.extend_from_slice(&PAD_STATE_MAGIC);
+        out.push(PAD_STATE_VERSION);
+        out.extend_from_slice(&self.pad_len.to_be_bytes());
+        out.push(if self.is_alice { 1 } else { 2 });
+        out.extend_from_slice(&inner.next_send.to_be_bytes());
+        let (floor, ranges) = inner.received.parts();
+        out.extend_from_slice(&floor.to_be_bytes());
+        let ranges: Vec<(u64, u64)> = ranges.collect();
+        out.extend_from_slice(&(ranges.len() as u32).to_be_bytes());
+        for (start, end) in ranges {
+            out.extend_from_slice(&start.to_be_bytes());
+            out.extend_from_slice(&end.to_be_bytes());
+        }
+
+        let mut tmp_path = OsString::from(self.state_path.as_os_str());
+        tmp_path.push(".tmp");
+        let mut tmp = File::create(&tmp_path)?;
+        tmp.write_all(&out)?;
+        tmp.sync_all()?;
+        std::fs::rename(&tmp_path, &self.state_path)?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for PadFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PadFile")
+            .field("path", &self.path)
+            .field("pad_len", &self.pad_len)
+            .field("is_alice", &self.is_alice)
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+/// Read `count` pad bytes at `position` and overwrite them with zeros
+fn take(file: &mut File, position: u64, count: usize) -> Result<Zeroizing<Vec<u8>>, PadError> {
+    let mut bytes = Zeroizing::new(vec![0u8; count]);
+    file.seek(SeekFrom::Start(position))?;
+    file.read_exact(&mut bytes)?;
+
+    file.seek(SeekFrom::Start(position))?;
+    let zeros = [0u8; CHUNK_SIZE];
+    let mut remaining = count;
+    while remaining > 0 {
+        let n = remaining.min(CHUNK_SIZE);
+        file.write_all(&zeros[..n])?;
+        remaining -= n;
+    }
+    file.sync_data()?;
+    Ok(bytes)
+}
+
+/// Parse a state file, checking it belongs to this pad and role
+fn decode_state(data: &[u8], pad_len: u64, is_alice: bool) -> Result<(u64, KeystreamTracker), PadError> {
+    let malformed = || PadError::InvalidPad("malformed pad state file".to_string());
+    let u64_at = |at: usize| -> Result<u64, PadError> {
+        let bytes = data.get(at..at + 8).ok_or_else(malformed)?;
+        Ok(u64::from_be_bytes(bytes.try_into().map_err(|_| malformed())?))
+    };
+
+    if data.len() < 34 || data[..4] != PAD_STATE_MAGIC || data[4] != PAD_STATE_VERSION {
+        return Err(malformed());
+    }
+    if u64_at(5)? != pad_len {
+        return Err(PadError::InvalidPad("state file belongs to a pad of another length".to_string()));
+    }
+    if data[13] != if is_alice { 1 } else { 2 } {
+        return Err(PadError::InvalidPad("state file was written for the other role".to_string()));
+    }
+    let next_send = u64_at(14)?;
+    let floor = u64_at(22)?;
+    let count = u32::from_be_bytes(data[30..34].try_into().map_err(|_| malformed())?) as usize;
+    if data.len() != 34 + count * 16 {
+        return Err(malformed());
+    }
+    let ranges = (0..count)
+        .map(|i| Ok((u64_at(34 + i * 16)?, u64_at(42 + i * 16)?)))
+        .collect::<Result<Vec<_>, PadError>>()?;
+    Ok((next_send, KeystreamTracker::from_parts(floor, ranges)))
+}
+
+/// Errors produced by a one-time pad
+#[derive(Debug)]
+pub enum PadError {
+    /// Reading or writing the pad or its state failed
+    Io(std::io::Error),
+    /// Pad or state file is unusable
+    InvalidPad(String),
+    /// Not enough pad left in our send lane
+    Exhausted {
+        /// Bytes requested
+        requested: usize,
+        /// Bytes left
+        available: u64,
+    },
+    /// Received range lies outside the peer's lane
+    OutOfLane {
+        /// Start of the rejected range
+        position: u64,
+    },
+    /// Received range overlaps one already used
+    AlreadyUsed {
+        /// Start of the rejected range
+        position: u64,
+    },
+}
+
+impl std::fmt::Display for PadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PadError::Io(e) => write!(f, "Pad I/O error: {}", e),
+            PadError::InvalidPad(e) => write!(f, "Invalid pad: {}", e),
+            PadError::Exhausted { requested, available } => {
+                write!(f, "Pad exhausted: requested {} bytes but only {} left", requested, available)
+            }
+            PadError::OutOfLane { position } => write!(f, "Pad position {} is outside the peer's lane", position),
+            PadError::AlreadyUsed { position } => write!(f, "Pad at position {} was already used", position),
+        }
+    }
+}
+
+impl std::error::Error for PadError {}
+
+impl From<std::io::Error> for PadError {
+    fn from(e: std::io::Error) -> Self {
+        PadError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Alice's and Bob's copy of one pad, removed on drop
+    struct PadPair {
+        alice: PathBuf,
+        bob: PathBuf,
+    }
+
+    impl Drop for PadPair {
+        fn drop(&mut self) {
+            for path in [&self.alice, &self.bob] {
+                let _ = std::fs::remove_file(path);
+                let mut state = OsString::from(path.as_os_str());
+                state.push(".state");
+                let _ = std::fs::remove_file(state);
+            }
+        }
+    }
+
+    fn pad_pair(len: u64) -> PadPair {
+        let mut id = [0u8; 8];
+        getrandom::getrandom(&mut id).unwrap();
+        let dir = std::env::temp_dir();
+        let alice = dir.join(format!("zks-pad-{}-alice", hex::encode(id)));
+        let bob = dir.join(format!("zks-pad-{}-bob", hex::encode(id)));
+        PadFile::generate(&alice, len).unwrap();
+        std::fs::copy(&alice, &bob).unwrap();
+        PadPair { alice, bob }
+    }
+
+    #[test]
+    fn test_pad_bytes_match_and_are_overwritten() {
+        let pads = pad_pair(4096);
+        let original = std::fs::read(&pads.alice).unwrap();
+        let alice = PadFile::open(&pads.alice, true).unwrap();
+        let bob = PadFile::open(&pads.bob, false).unwrap();
+
+        let (first, first_bytes) = alice.consume(100).unwrap();
+        let (second, second_bytes) = alice.consume(50).unwrap();
+        let (reply, reply_bytes) = bob.consume(10).unwrap();
+        assert_eq!((first, second, reply), (0, 100, 2048));
+        assert_eq!(&first_bytes[..], &original[..100]);
+
+        // Out of order on the receiving side
+        assert_eq!(bob.consume_received(second, 50).unwrap(), second_bytes);
+        assert_eq!(bob.consume_received(first, 100).unwrap(), first_bytes);
+        assert_eq!(alice.consume_received(reply, 10).unwrap(), reply_bytes);
+
+        for path in [&pads.alice, &pads.bob] {
+            let on_disk = std::fs::read(path).unwrap();
+            assert!(on_disk[..150].iter().all(|&b| b == 0));
+            assert!(on_disk[2048..2058].iter().all(|&b| b == 0));
+            assert_eq!(on_disk[150..2048], original[150..2048]);
+        }
+    }
+
+    #[test]
+    fn test_pad_refuses_reuse_and_exhaustion() {
+        let pads = pad_pair(200);
+        let alice = PadFile::open(&pads.alice, true).unwrap();
+        let bob = PadFile::open(&pads.bob, false).unwrap();
+
+        let (position, _) = alice.consume(60).unwrap();
+        bob.consume_received(position, 60).unwrap();
+        assert!(matches!(bob.consume_received(position, 60), Err(PadError::AlreadyUsed { .. })));
+        assert!(matches!(bob.consume_received(150, 10), Err(PadError::OutOfLane { .. })));
+        assert!(matches!(bob.consume_received(90, 20), Err(PadError::OutOfLane { .. })));
+
+        assert!(!alice.is_low());
+        assert!(matches!(
+            alice.consume(41),
+            Err(PadError::Exhausted { requested: 41, available: 40 })
+        ));
+        alice.consume(40).unwrap();
+        assert_eq!(alice.available(), 0);
+        assert!(alice.is_low());
+        assert!(matches!(alice.consume(1), Err(PadError::Exhausted { .. })));
+    }
+
+    #[test]
+    fn test_pad_offsets_survive_reopen() {
+        let pads = pad_pair(1000);
+        let (position, _) = {
+            let alice = PadFile::open(&pads.alice, true).unwrap();
+            alice.consume(30).unwrap()
+        };
+        {
+            let bob = PadFile::open(&pads.bob, false).unwrap();
+            bob.consume_received(position, 30).unwrap();
+        }
+
+        let alice = PadFile::open(&pads.alice, true).unwrap();
+        assert_eq!(alice.consume(10).unwrap().0, 30);
+        let bob = PadFile::open(&pads.bob, false).unwrap();
+        assert!(matches!(bob.consume_received(position, 30), Err(PadError::AlreadyUsed { .. })));
+
+        // A copy opened with the wrong role is refused
+        drop(alice);
+        assert!(matches!(PadFile::open(&pads.alice, false), Err(PadError::InvalidPad(_))));
+    }
+}
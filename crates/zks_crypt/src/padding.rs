@@ -0,0 +1,164 @@
+//! Length-Hiding Padding Policies
+//!
+//! Without padding an envelope is exactly plaintext + overhead bytes long, which
+//! reveals message sizes to anyone watching the wire. A [`PaddingPolicy`] rounds
+//! the *envelope* length up, and the padding lives inside the AEAD:
+//!
+//! ```text
+//! length (4, BE) | plaintext | zeros
+//! ```
+//!
+//! Padded envelopes set [`FLAG_PADDED`](crate::envelope::FLAG_PADDED), and the header
+//! and length prefix are both authenticated, so the receiver strips exactly what the
+//! sender added. Non-zero padding is rejected.
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::constant_time::ct_is_zero;
+
+/// Length of the authenticated plaintext length prefix
+pub const PADDING_PREFIX_LEN: usize = 4;
+
+/// Bucket sizes used by [`PaddingPolicy::default_buckets`]
+pub const DEFAULT_PADDING_BUCKETS: [usize; 4] = [256, 1024, 4096, 16384];
+
+/// How far envelopes are padded
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PaddingPolicy {
+    /// No padding; envelopes reveal the exact plaintext length
+    #[default]
+    None,
+    /// Round up to the smallest listed size, then to multiples of the largest
+    Buckets(Vec<usize>),
+    /// Padmé: at most ~12% overhead, leaking O(log log n) bits of the length
+    Padme,
+    /// Round up to a multiple of the path MTU
+    Mtu(usize),
+}
+
+impl PaddingPolicy {
+    /// Bucketed padding with [`DEFAULT_PADDING_BUCKETS`]
+    pub fn default_buckets() -> Self {
+        PaddingPolicy::Buckets(DEFAULT_PADDING_BUCKETS.to_vec())
+    }
+
+    /// Whether this policy pads at all
+    pub fn is_none(&self) -> bool {
+        *self == PaddingPolicy::None
+    }
+
+    /// Envelope length after padding an envelope of `len` bytes
+    ///
+    /// Never shorter than `len`.
+    pub fn padded_len(&self, len: usize) -> usize {
+        match self {
+            PaddingPolicy::None => len,
+            PaddingPolicy::Buckets(buckets) => {
+                match buckets.iter().copied().filter(|&b| b >= len).min() {
+                    Some(bucket) => bucket,
+                    None => match buckets.iter().copied().max() {
+                        Some(largest) if largest > 0 => round_up(len, largest),
+                        _ => len,
+                    },
+                }
+            }
+            PaddingPolicy::Padme => padme(len),
+            PaddingPolicy::Mtu(mtu) if *mtu > 0 => round_up(len, *mtu),
+            PaddingPolicy::Mtu(_) => len,
+        }
+    }
+}
+
+fn round_up(len: usize, multiple: usize) -> usize {
+    len.div_ceil(multiple).saturating_mul(multiple)
+}
+
+/// Padmé (Nikitin et al., PETS 2019): keep the top ⌊log2 E⌋ + 1 bits of the length
+fn padme(len: usize) -> usize {
+    if len < 2 {
+        return len;
+    }
+    let e = usize::BITS - 1 - len.leading_zeros();
+    let s = u32::BITS - e.leading_zeros();
+    let mask = (1usize << (e - s)) - 1;
+    len.saturating_add(mask) & !mask
+}
+
+/// Length prefix for `len` bytes of plaintext
+///
+/// Returns `None` for payloads of 4 GiB or more, which the 32-bit prefix cannot hold.
+pub(crate) fn length_prefix(len: usize) -> Option<[u8; PADDING_PREFIX_LEN]> {
+    u32::try_from(len).ok().map(u32::to_be_bytes)
+}
+
+/// Write `data` framed for padding into `out`, which must hold at least
+/// [`PADDING_PREFIX_LEN`] + `data.len()` bytes; the rest of `out` is zeroed
+///
+/// `prefix` is the [`length_prefix`] of `data`.
+pub(crate) fn write_padded(out: &mut [u8], prefix: [u8; PADDING_PREFIX_LEN], data: &[u8]) {
+    out[..PADDING_PREFIX_LEN].copy_from_slice(&prefix);
+    out[PADDING_PREFIX_LEN..PADDING_PREFIX_LEN + data.len()].copy_from_slice(data);
+    out[PADDING_PREFIX_LEN + data.len()..].fill(0);
+}
+
+/// Locate the plaintext inside a padded body
+///
+/// Returns `None` if the length prefix is out of range or the padding is not zero.
+pub(crate) fn unpad(body: &[u8]) -> Option<core::ops::Range<usize>> {
+    let prefix: [u8; PADDING_PREFIX_LEN] = body.get(..PADDING_PREFIX_LEN)?.try_into().ok()?;
+    let len = u32::from_be_bytes(prefix) as usize;
+    let end = PADDING_PREFIX_LEN.checked_add(len).filter(|&end| end <= body.len())?;
+    ct_is_zero(&body[end..]).then_some(PADDING_PREFIX_LEN..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies_round_up() {
+        let buckets = PaddingPolicy::Buckets(vec![128, 512]);
+        assert_eq!(buckets.padded_len(40), 128);
+        assert_eq!(buckets.padded_len(128), 128);
+        assert_eq!(buckets.padded_len(129), 512);
+        assert_eq!(buckets.padded_len(1100), 1536);
+
+        assert_eq!(PaddingPolicy::Mtu(1400).padded_len(36), 1400);
+        assert_eq!(PaddingPolicy::Mtu(1400).padded_len(1401), 2800);
+        assert_eq!(PaddingPolicy::None.padded_len(77), 77);
+
+        // Padmé reference values
+        assert_eq!(PaddingPolicy::Padme.padded_len(9), 10);
+        assert_eq!(PaddingPolicy::Padme.padded_len(100), 104);
+        assert_eq!(PaddingPolicy::Padme.padded_len(1000), 1024);
+        for len in 1..5000 {
+            let padded = padme(len);
+            assert!(padded >= len && padded - len <= len / 8 + 1);
+        }
+    }
+
+    #[test]
+    fn test_length_prefix_rejects_4_gib() {
+        assert_eq!(length_prefix(5), Some([0, 0, 0, 5]));
+        assert_eq!(length_prefix(u32::MAX as usize), Some([0xFF; 4]));
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(length_prefix(u32::MAX as usize + 1), None);
+    }
+
+    #[test]
+    fn test_unpad_rejects_malformed_bodies() {
+        let mut body = [0u8; 32];
+        write_padded(&mut body, length_prefix(5).unwrap(), b"hello");
+        assert_eq!(unpad(&body), Some(4..9));
+
+        let mut dirty = body;
+        dirty[20] = 1;
+        assert_eq!(unpad(&dirty), None);
+
+        let mut too_long = body;
+        too_long[..4].copy_from_slice(&29u32.to_be_bytes());
+        assert_eq!(unpad(&too_long), None);
+        assert_eq!(unpad(&[0u8; 3]), None);
+    }
+}
@@ -0,0 +1,141 @@
+//! Post-Quantum Ratchet
+//!
+//! The handshake's ML-KEM shared secret is the only post-quantum key material a
+//! session starts with; [`RecursiveChain`](crate::recursive_chain::RecursiveChain)
+//! only mixes symmetric entropy after that. With a PQ ratchet enabled, a key update
+//! that falls due under the [`PqRatchetPolicy`] also runs a fresh ML-KEM-768
+//! encapsulation in-band, see [`crate::rekey`].
+//!
+//! An attacker who recorded the session and later compromises the chain state loses
+//! access again after the next PQ step, even with a quantum computer.
+//!
+//! Without the `std` feature there is no clock, and only the message limit applies.
+
+use core::sync::atomic::Ordering;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::sync::AtomicU64;
+
+/// Default messages sent between PQ ratchet steps
+pub const PQ_RATCHET_MESSAGES: u64 = 100_000;
+
+/// Default time between PQ ratchet steps
+pub const PQ_RATCHET_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// When a key update should also carry an ML-KEM exchange
+///
+/// A step is due once either limit is reached; `None` disables that limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PqRatchetPolicy {
+    /// Messages sent since the last PQ step
+    pub every_messages: Option<u64>,
+    /// Time elapsed since the last PQ step (needs `std`)
+    pub every: Option<Duration>,
+}
+
+impl PqRatchetPolicy {
+    /// Step after `messages` messages
+    pub fn by_messages(messages: u64) -> Self {
+        Self {
+            every_messages: Some(messages),
+            every: None,
+        }
+    }
+
+    /// Step after `interval` has elapsed
+    pub fn by_interval(interval: Duration) -> Self {
+        Self {
+            every_messages: None,
+            every: Some(interval),
+        }
+    }
+}
+
+impl Default for PqRatchetPolicy {
+    fn default() -> Self {
+        Self {
+            every_messages: Some(PQ_RATCHET_MESSAGES),
+            every: Some(PQ_RATCHET_INTERVAL),
+        }
+    }
+}
+
+/// Progress toward the next PQ ratchet step
+pub(crate) struct PqRatchet {
+    policy: PqRatchetPolicy,
+    messages: AtomicU64,
+    #[cfg(feature = "std")]
+    last_step: Instant,
+}
+
+impl PqRatchet {
+    pub(crate) fn new(policy: PqRatchetPolicy) -> Self {
+        Self {
+            policy,
+            messages: AtomicU64::new(0),
+            #[cfg(feature = "std")]
+            last_step: Instant::now(),
+        }
+    }
+
+    /// Count an outgoing message
+    pub(crate) fn record_message(&self) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Check if the next key update should carry an ML-KEM exchange
+    pub(crate) fn is_due(&self) -> bool {
+        let by_count = self
+            .policy
+            .every_messages
+            .is_some_and(|limit| self.messages.load(Ordering::Relaxed) >= limit);
+        by_count || self.interval_elapsed()
+    }
+
+    #[cfg(feature = "std")]
+    fn interval_elapsed(&self) -> bool {
+        self.policy
+            .every
+            .is_some_and(|interval| self.last_step.elapsed() >= interval)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn interval_elapsed(&self) -> bool {
+        false
+    }
+
+    /// Restart the count after a completed PQ step
+    pub(crate) fn completed(&mut self) {
+        self.messages.store(0, Ordering::Relaxed);
+        #[cfg(feature = "std")]
+        {
+            self.last_step = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_limits() {
+        let ratchet = PqRatchet::new(PqRatchetPolicy::by_messages(3));
+        ratchet.record_message();
+        ratchet.record_message();
+        assert!(!ratchet.is_due());
+        ratchet.record_message();
+        assert!(ratchet.is_due());
+
+        #[cfg(feature = "std")]
+        {
+            let mut ratchet = PqRatchet::new(PqRatchetPolicy::by_interval(Duration::ZERO));
+            assert!(ratchet.is_due());
+            ratchet.policy.every = Some(Duration::from_secs(60));
+            ratchet.completed();
+            assert!(!ratchet.is_due());
+        }
+    }
+}
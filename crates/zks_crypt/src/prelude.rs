@@ -4,13 +4,47 @@
 //! from the zks_crypt crate.
 
 // Core cryptographic modules
-pub use crate::anti_replay::AntiReplayContainer;
+pub use crate::aead_backend::{AeadAlgorithm, AeadBackend};
+pub use crate::anti_replay::{AntiReplayContainer, BitmapWindow, ReplayWindow};
+#[cfg(feature = "std")]
+pub use crate::commitment::derive_password_key;
 pub use crate::constant_time::{ct_eq, ct_eq_fixed, ct_compare, ct_copy, ct_swap, ct_is_zero, ct_assign, ct_select_bytes, ct_xor};
-pub use crate::drand::{DrandEntropy, DrandConfig, DrandError, get_drand_entropy, get_unique_entropy};
+#[cfg(feature = "drand")]
+pub use crate::entropy::{EntropySource, EntropyCombiner, EntropyProvenance, CombinedEntropy, CombineError};
+pub use crate::envelope::{EnvelopeHeader, EnvelopeError, CipherMode};
+#[cfg(feature = "std")]
+pub use crate::group::{GroupSession, SenderKeyDistribution, GroupError};
+#[cfg(feature = "std")]
+pub use crate::health::{HealthConfig, HealthMonitor, HealthMetrics, HealthStatus, HealthFailure};
+#[cfg(feature = "std")]
+pub use crate::hpke::{SealMode, SealError, Opened, Exporter};
+#[cfg(feature = "std")]
+pub use crate::keystore::{Keystore, KdfParams, Identity, IdentityKeys, KeystoreError};
+pub use crate::keystream::VernamKeystream;
+#[cfg(feature = "drand")]
+pub use crate::drand::{DrandEntropy, DrandConfig, DrandChainInfo, DrandError, get_drand_entropy, get_unique_entropy};
+#[cfg(feature = "std")]
+pub use crate::pad::{PadFile, PadError};
+pub use crate::padding::PaddingPolicy;
+pub use crate::pq_ratchet::PqRatchetPolicy;
 pub use crate::recursive_chain::RecursiveChain;
-pub use crate::scramble::CiphertextScrambler;
-pub use crate::true_vernam::{TrueVernamBuffer, TrueVernamFetcher};
-pub use crate::wasif_vernam::{WasifVernam, ContinuousEntropyRefresher};
+pub use crate::rekey::{KeyUpdate, RekeyError};
+pub use crate::scramble::{CiphertextScrambler, PacketScrambler};
+pub use crate::session::{SessionState, SessionError};
+#[cfg(feature = "std")]
+pub use crate::shamir::{Share, Recovered, RecoveredKeys, ShamirError, split_keys, recover_keys};
+#[cfg(feature = "std")]
+pub use crate::stream_aead::{StreamEncryptor, StreamDecryptor, EncryptingWriter, DecryptingReader, StreamError};
+#[cfg(feature = "parallel")]
+pub use crate::stream_aead::{encrypt_stream_parallel, decrypt_stream_parallel};
+pub use crate::rng::{set_rng, RngError};
+#[cfg(feature = "drand")]
+pub use crate::true_vernam::TrueVernamBuffer;
+#[cfg(feature = "fetcher")]
+pub use crate::true_vernam::TrueVernamFetcher;
+pub use crate::wasif_vernam::WasifVernam;
+#[cfg(feature = "std")]
+pub use crate::wasif_vernam::ContinuousEntropyRefresher;
 
 // Re-export common dependencies for convenience
 pub use chacha20poly1305;
@@ -24,12 +58,15 @@ pub type VernamCipher = WasifVernam;
 /// Anti-replay attack protection container
 pub type AntiReplay = AntiReplayContainer;
 /// Ciphertext scrambler for traffic analysis resistance
-pub type Scrambler = CiphertextScrambler;
+pub type Scrambler = PacketScrambler;
 /// Recursive key chain for forward secrecy
 pub type KeyChain = RecursiveChain;
 /// True Vernam buffer for information-theoretic security
+#[cfg(feature = "drand")]
 pub type VernamBuffer = TrueVernamBuffer;
 /// Entropy fetcher for random data generation
+#[cfg(feature = "fetcher")]
 pub type EntropyFetcher = TrueVernamFetcher;
 /// Continuous entropy refresher for background security updates
+#[cfg(feature = "std")]
 pub type EntropyRefresher = ContinuousEntropyRefresher;
\ No newline at end of file
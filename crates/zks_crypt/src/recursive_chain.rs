@@ -14,6 +14,7 @@
 //! S_(n+1) = KDF(C_n || S_n || E)   -- Next session key (E = fresh entropy)
 //! ```
 
+use alloc::vec::Vec;
 use hkdf::Hkdf;
 use sha2::Sha256;
 use zeroize::{Zeroize, Zeroizing};
@@ -156,7 +157,54 @@ impl RecursiveChain {
     pub fn generation(&self) -> u64 {
         self.generation
     }
+
+    /// Whether we hold the Alice (initiator) role
+    pub fn is_alice(&self) -> bool {
+        self.is_alice
+    }
+
+    /// Derive the AEAD key for a key epoch from the current chain key
+    ///
+    /// Both parties get the same key once they have exchanged contributions
+    /// and sit at the same generation.
+    pub fn derive_epoch_key(&self, epoch: u32) -> [u8; 32] {
+        let hk = Hkdf::<Sha256>::new(Some(b"zks-epoch-key"), &*self.chain);
+        let mut key = [0u8; 32];
+        hk.expand(&epoch.to_be_bytes(), &mut key)
+            .expect("HKDF expansion should not fail");
+        key
+    }
     
+    /// Mix a post-quantum shared secret into both contribution keys
+    ///
+    /// Both parties must call this at the same generation with the same secret.
+    /// Because the contributions change, the secret carries into every later chain key.
+    pub fn mix_pq_secret(&mut self, shared_secret: &[u8]) {
+        let mut input = Vec::with_capacity(32 + 32 + shared_secret.len());
+        input.extend_from_slice(&*self.alice_key);
+        input.extend_from_slice(&*self.bob_key);
+        input.extend_from_slice(shared_secret);
+
+        let hk = Hkdf::<Sha256>::new(Some(b"zks-pq-ratchet"), &input);
+        hk.expand(b"alice-key", self.alice_key.as_mut())
+            .expect("HKDF expansion should not fail");
+        hk.expand(b"bob-key", self.bob_key.as_mut())
+            .expect("HKDF expansion should not fail");
+        input.zeroize();
+
+        // Recalculate chain key
+        let mut xor_result = [0u8; 32];
+        for (out, (a, b)) in xor_result.iter_mut().zip(self.alice_key.iter().zip(self.bob_key.iter())) {
+            *out = a ^ b;
+        }
+
+        let chain_hk = Hkdf::<Sha256>::new(Some(b"zks-chain-key"), &xor_result);
+        chain_hk.expand(&self.generation.to_le_bytes(), self.chain.as_mut())
+            .expect("HKDF expansion should not fail");
+
+        xor_result.zeroize();
+    }
+
     /// Get our current contribution key (to send to peer)
     pub fn our_contribution(&self) -> [u8; 32] {
         if self.is_alice {
@@ -263,6 +311,23 @@ mod tests {
         assert_eq!(alice.generation(), bob.generation());
     }
     
+    #[test]
+    fn test_contribution_exchange_converges() {
+        let shared_secret = [0x42u8; 32];
+        let mut alice = RecursiveChain::new(&shared_secret, true);
+        let mut bob = RecursiveChain::new(&shared_secret, false);
+        
+        // Alice contributes, Bob follows and contributes back
+        alice.advance(&[0x01u8; 32]);
+        bob.update_peer_key(&alice.our_contribution());
+        bob.advance(&[0x02u8; 32]);
+        alice.update_peer_key(&bob.our_contribution());
+        
+        assert_eq!(alice.generation(), bob.generation());
+        assert_eq!(alice.derive_epoch_key(1), bob.derive_epoch_key(1));
+        assert_ne!(alice.derive_epoch_key(1), alice.derive_epoch_key(2));
+    }
+    
     #[test]
     fn test_export_import_state() {
         let shared_secret = [0x42u8; 32];
@@ -0,0 +1,202 @@
+//! In-band Rekeying
+//!
+//! Both peers run a [`RecursiveChain`](crate::recursive_chain::RecursiveChain) and
+//! move to a new key epoch together by exchanging fresh chain contributions inside
+//! ordinary envelopes flagged with [`FLAG_KEY_UPDATE`](crate::envelope::FLAG_KEY_UPDATE).
+//!
+//! # Protocol
+//! ```text
+//! A: advance chain              --- KeyUpdate::Request(e+1, A') -->   B: update_peer_key(A'), advance chain
+//!                               <-- KeyUpdate::Response(e+1, B') ---  B: switch to epoch e+1
+//! A: update_peer_key(B')
+//! A: switch to epoch e+1
+//! ```
+//!
+//! Both chains end on `KDF(A' XOR B')` at the same generation, so both derive the
+//! same epoch key. The response travels under epoch `e`, and each side keeps the
+//! previous epoch for a grace window so in-flight messages still decrypt.
+//!
+//! If both sides initiate at once, Alice's request wins and Bob rolls back his own.
+//!
+//! # Post-Quantum Ratchet
+//! When a [`PqRatchetPolicy`](crate::pq_ratchet::PqRatchetPolicy) is due, the request
+//! also carries a fresh ML-KEM-768 public key and the response its ciphertext. Both
+//! sides mix the encapsulated secret into the chain before deriving the epoch key.
+
+use alloc::vec::Vec;
+use zks_pqcrypto::ml_kem::{CIPHERTEXT_SIZE as PQ_CIPHERTEXT_SIZE, PUBLIC_KEY_SIZE as PQ_PUBLIC_KEY_SIZE};
+
+/// Messages sent in an epoch before [`needs_rekey`](crate::wasif_vernam::WasifVernam::needs_rekey) fires
+pub const REKEY_INTERVAL: u64 = 1000;
+
+/// Messages authenticated under the new epoch before the previous epoch is dropped
+pub const REKEY_GRACE_MESSAGES: u32 = 256;
+
+/// Encoded length of a [`KeyUpdate`] without a KEM payload
+pub const KEY_UPDATE_LEN: usize = 37;
+
+/// Direction of a key update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum KeyUpdateKind {
+    /// Initiator's contribution
+    Request = 0x01,
+    /// Responder's contribution, completing the exchange
+    Response = 0x02,
+}
+
+/// KeyUpdate control message
+///
+/// Layout: `kind (1) | epoch (4, BE) | contribution (32) | kem (0, 1184 or 1088)`
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeyUpdate {
+    /// Request or response
+    pub kind: KeyUpdateKind,
+    /// Epoch this update establishes
+    pub epoch: u32,
+    /// Sender's new chain contribution
+    pub contribution: [u8; 32],
+    /// ML-KEM public key (request) or ciphertext (response) for a PQ ratchet step
+    pub kem: Option<Vec<u8>>,
+}
+
+impl KeyUpdate {
+    /// Serialize the message
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let kem = self.kem.as_deref().unwrap_or_default();
+        let mut out = Vec::with_capacity(KEY_UPDATE_LEN + kem.len());
+        out.push(self.kind as u8);
+        out.extend_from_slice(&self.epoch.to_be_bytes());
+        out.extend_from_slice(&self.contribution);
+        out.extend_from_slice(kem);
+        out
+    }
+
+    /// Parse a message
+    pub fn from_bytes(data: &[u8]) -> Result<Self, RekeyError> {
+        if data.len() < KEY_UPDATE_LEN {
+            return Err(RekeyError::Malformed);
+        }
+        let kind = match data[0] {
+            0x01 => KeyUpdateKind::Request,
+            0x02 => KeyUpdateKind::Response,
+            _ => return Err(RekeyError::Malformed),
+        };
+        let mut epoch = [0u8; 4];
+        epoch.copy_from_slice(&data[1..5]);
+        let mut contribution = [0u8; 32];
+        contribution.copy_from_slice(&data[5..KEY_UPDATE_LEN]);
+
+        let kem = &data[KEY_UPDATE_LEN..];
+        let kem_len = match kind {
+            KeyUpdateKind::Request => PQ_PUBLIC_KEY_SIZE,
+            KeyUpdateKind::Response => PQ_CIPHERTEXT_SIZE,
+        };
+        let kem = match kem.len() {
+            0 => None,
+            len if len == kem_len => Some(kem.to_vec()),
+            _ => return Err(RekeyError::Malformed),
+        };
+
+        Ok(Self {
+            kind,
+            epoch: u32::from_be_bytes(epoch),
+            contribution,
+            kem,
+        })
+    }
+}
+
+/// Secure debug implementation that doesn't expose the contribution
+impl core::fmt::Debug for KeyUpdate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "KeyUpdate({:?}, epoch {}, pq: {})", self.kind, self.epoch, self.kem.is_some())
+    }
+}
+
+/// Errors produced by the rekey protocol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RekeyError {
+    /// [`enable_key_chain`](crate::wasif_vernam::WasifVernam::enable_key_chain) was not called
+    NoKeyChain,
+    /// A key update we initiated is still awaiting its response
+    InProgress,
+    /// Update targets an epoch other than the next one
+    UnexpectedEpoch {
+        /// Epoch this side would move to
+        expected: u32,
+        /// Epoch carried by the update
+        actual: u32,
+    },
+    /// Response received without a matching request
+    UnexpectedResponse,
+    /// Update could not be parsed
+    Malformed,
+    /// Envelope failed to seal or open
+    Crypto,
+    /// ML-KEM key generation, encapsulation or decapsulation failed
+    Kem,
+    /// RNG unavailable for a fresh contribution
+    Rng,
+}
+
+impl core::fmt::Display for RekeyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RekeyError::NoKeyChain => write!(f, "Key chain not enabled"),
+            RekeyError::InProgress => write!(f, "Key update already in progress"),
+            RekeyError::UnexpectedEpoch { expected, actual } => {
+                write!(f, "Key update for epoch {} (expected {})", actual, expected)
+            }
+            RekeyError::UnexpectedResponse => write!(f, "Key update response without request"),
+            RekeyError::Malformed => write!(f, "Malformed key update"),
+            RekeyError::Crypto => write!(f, "Key update envelope failed"),
+            RekeyError::Kem => write!(f, "ML-KEM ratchet step failed"),
+            RekeyError::Rng => write!(f, "RNG unavailable during key update"),
+        }
+    }
+}
+
+impl core::error::Error for RekeyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_update_roundtrip() {
+        let update = KeyUpdate {
+            kind: KeyUpdateKind::Response,
+            epoch: 0x0102_0304,
+            contribution: [9u8; 32],
+            kem: None,
+        };
+        let bytes = update.to_bytes();
+        assert_eq!(bytes.len(), KEY_UPDATE_LEN);
+        assert_eq!(bytes[0], 0x02);
+        assert_eq!(KeyUpdate::from_bytes(&bytes).unwrap(), update);
+
+        assert_eq!(KeyUpdate::from_bytes(&bytes[..KEY_UPDATE_LEN - 1]), Err(RekeyError::Malformed));
+        let mut bad = bytes;
+        bad[0] = 0x7F;
+        assert_eq!(KeyUpdate::from_bytes(&bad), Err(RekeyError::Malformed));
+    }
+
+    #[test]
+    fn test_kem_payload_length_follows_kind() {
+        let request = KeyUpdate {
+            kind: KeyUpdateKind::Request,
+            epoch: 3,
+            contribution: [1u8; 32],
+            kem: Some(vec![5u8; PQ_PUBLIC_KEY_SIZE]),
+        };
+        let bytes = request.to_bytes();
+        assert_eq!(KeyUpdate::from_bytes(&bytes).unwrap(), request);
+
+        // A public key is not a valid response payload
+        let mut response = bytes.clone();
+        response[0] = KeyUpdateKind::Response as u8;
+        assert_eq!(KeyUpdate::from_bytes(&response), Err(RekeyError::Malformed));
+        assert_eq!(KeyUpdate::from_bytes(&bytes[..bytes.len() - 1]), Err(RekeyError::Malformed));
+    }
+}
@@ -0,0 +1,146 @@
+//! Randomness Source
+//!
+//! Envelope nonces, rekey keypairs, entropy contributions and snapshot nonces are
+//! all drawn through [`fill`]. With the `std` feature it reads the OS RNG unless the
+//! caller has installed one with [`set_rng`]. Without `std` there is no OS RNG: the
+//! caller installs one before the first seal, typically backed by the hardware TRNG
+//! of the device, and every draw fails with [`RngError::Unavailable`] until then.
+
+use alloc::boxed::Box;
+use core::fmt;
+
+use once_cell::race::OnceBox;
+use rand::{CryptoRng, RngCore};
+
+/// Caller-supplied RNG: fills the whole buffer or reports failure
+pub type RngFn = dyn Fn(&mut [u8]) -> Result<(), RngError> + Send + Sync;
+
+static RNG: OnceBox<Box<RngFn>> = OnceBox::new();
+
+/// Randomness could not be drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngError {
+    /// No RNG installed and no OS RNG available
+    Unavailable,
+    /// The RNG failed to produce output
+    Failed,
+    /// An RNG was already installed
+    AlreadySet,
+}
+
+impl fmt::Display for RngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RngError::Unavailable => write!(f, "No RNG installed"),
+            RngError::Failed => write!(f, "RNG failed"),
+            RngError::AlreadySet => write!(f, "RNG already installed"),
+        }
+    }
+}
+
+impl core::error::Error for RngError {}
+
+/// Install the RNG used for all randomness in this crate
+///
+/// It must be cryptographically secure. Only the first call takes effect; later
+/// calls return [`RngError::AlreadySet`].
+pub fn set_rng<F>(rng: F) -> Result<(), RngError>
+where
+    F: Fn(&mut [u8]) -> Result<(), RngError> + Send + Sync + 'static,
+{
+    RNG.set(Box::new(Box::new(rng))).map_err(|_| RngError::AlreadySet)
+}
+
+/// Fill `dest` from the installed RNG, or the OS RNG if none is installed
+pub fn fill(dest: &mut [u8]) -> Result<(), RngError> {
+    match RNG.get() {
+        Some(rng) => rng(dest),
+        None => os_fill(dest),
+    }
+}
+
+// Host tests draw from the OS even without `std`
+#[cfg(any(feature = "std", test))]
+fn os_fill(dest: &mut [u8]) -> Result<(), RngError> {
+    getrandom::getrandom(dest).map_err(|_| RngError::Failed)
+}
+
+#[cfg(not(any(feature = "std", test)))]
+fn os_fill(_dest: &mut [u8]) -> Result<(), RngError> {
+    Err(RngError::Unavailable)
+}
+
+/// [`fill`] as a `rand_core` RNG, for ML-KEM
+///
+/// `rand_core` cannot report failure from `fill_bytes`, so the first error is kept
+/// and the output must be discarded if [`finish`](Self::finish) returns it.
+#[derive(Default)]
+pub(crate) struct FillRng {
+    error: Option<RngError>,
+}
+
+impl FillRng {
+    /// The first error hit while drawing, if any
+    pub(crate) fn finish(self) -> Result<(), RngError> {
+        self.error.map_or(Ok(()), Err)
+    }
+}
+
+impl RngCore for FillRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(e) = fill(dest) {
+            self.error.get_or_insert(e);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for FillRng {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aead_backend::AeadAlgorithm;
+    use crate::wasif_vernam::WasifVernam;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_installed_rng_is_used() {
+        static DRAWS: AtomicUsize = AtomicUsize::new(0);
+
+        // Still the OS RNG underneath, so tests running alongside are unaffected
+        set_rng(|dest| {
+            DRAWS.fetch_add(1, Ordering::SeqCst);
+            getrandom::getrandom(dest).map_err(|_| RngError::Failed)
+        })
+        .unwrap();
+        assert_eq!(set_rng(|_| Err(RngError::Failed)), Err(RngError::AlreadySet));
+
+        let before = DRAWS.load(Ordering::SeqCst);
+        // XChaCha draws a random nonce for every seal
+        let mut cipher = WasifVernam::with_algorithm([0x42; 32], AeadAlgorithm::XChaCha20Poly1305).unwrap();
+        let sealed = cipher.encrypt(b"nonce from the installed RNG").unwrap();
+        assert!(DRAWS.load(Ordering::SeqCst) > before);
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), b"nonce from the installed RNG");
+
+        let mut rng = FillRng::default();
+        let _ = rng.next_u64();
+        assert_eq!(rng.finish(), Ok(()));
+    }
+}
@@ -4,24 +4,140 @@
 //! After encryption, ciphertext bytes are permuted according to a deterministic
 //! mapping derived from shared entropy.
 //! 
+//! [`PacketScrambler`] permutes every packet, whatever its length, with a fresh
+//! permutation derived from the session key and the packet nonce. Positions are
+//! mapped through a keyed small-domain PRP (a Feistel network with cycle walking),
+//! so no per-size table has to be agreed on in advance. Payloads longer than
+//! [`MAX_SCRAMBLE_SIZE`] are permuted chunk by chunk.
+//! 
+//! [`CiphertextScrambler`] keeps the older fixed-size, table-based permutation.
+//! 
 //! # Security Benefits
 //! - Makes traffic pattern analysis harder
 //! - Prevents correlation attacks between packets
 //! - Adds another layer of obfuscation on top of encryption
 //! 
 //! # How It Works
-//! 1. Both peers derive the same permutation table from shared entropy
-//! 2. Sender: scramble(ciphertext) before transmission
-//! 3. Receiver: unscramble(data) after reception
-//! 4. Mapping is deterministic - both sides produce identical tables
+//! 1. Both peers derive the same PRP key from the session key with HKDF-SHA256
+//! 2. For each packet, ChaCha20 under that key and the packet nonce fills the
+//!    round tables of an 8-round balanced Feistel network over the smallest
+//!    `4^k >= len` positions; each 64 KiB chunk uses its own keystream range
+//! 3. Cycle walking re-applies the network to positions past the packet end
+//!    until they land inside it, so every length gets a true permutation
+//! 4. Sender: byte `i` moves to `permute(i)`; receiver: byte `i` comes back from
+//!    `permute(i)`, recomputed from the same key and nonce
 
-use sha2::{Sha256, Digest};
+use alloc::{vec, vec::Vec};
+use chacha20::{ChaCha20, cipher::{KeyIvInit, StreamCipher, StreamCipherSeek}};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::Zeroizing;
 
 /// Maximum data size we support scrambling (64KB)
 /// Larger data should be chunked
 pub const MAX_SCRAMBLE_SIZE: usize = 65536;
 
+/// Feistel rounds of the per-packet position PRP
+const FEISTEL_ROUNDS: usize = 8;
+
+/// Largest Feistel half: 8 bits cover a 2^16 = [`MAX_SCRAMBLE_SIZE`] domain
+const MAX_HALF_BITS: u32 = 8;
+
+/// Keystream bytes reserved for the round tables of one chunk
+const CHUNK_TABLE_LEN: u64 = (FEISTEL_ROUNDS << MAX_HALF_BITS) as u64;
+
+/// Per-packet ciphertext scrambler keyed by the session key
+///
+/// Each packet is permuted under its own nonce, so two packets of the same
+/// length are scrambled differently. Both peers need the same session key.
+#[derive(Clone)]
+pub struct PacketScrambler {
+    key: Zeroizing<[u8; 32]>,
+}
+
+impl PacketScrambler {
+    /// Derive a scrambler from a 32-byte session key
+    pub fn new(session_key: &[u8; 32]) -> Self {
+        let mut key = Zeroizing::new([0u8; 32]);
+        let hk = Hkdf::<Sha256>::new(Some(b"zks-packet-scrambler"), session_key);
+        hk.expand(b"position-prp-v1", key.as_mut())
+            .expect("32 bytes is a valid HKDF output length");
+        Self { key }
+    }
+
+    /// Scramble a packet in place under `nonce`
+    pub fn scramble(&self, nonce: &[u8; 12], data: &mut [u8]) {
+        for (index, chunk) in data.chunks_mut(MAX_SCRAMBLE_SIZE).enumerate() {
+            let prp = self.chunk_prp(nonce, index, chunk.len());
+            let original = chunk.to_vec();
+            for (pos, &byte) in original.iter().enumerate() {
+                chunk[prp.permute(pos)] = byte;
+            }
+        }
+    }
+
+    /// Reverse [`scramble`](Self::scramble) for the same `nonce`
+    pub fn unscramble(&self, nonce: &[u8; 12], data: &mut [u8]) {
+        for (index, chunk) in data.chunks_mut(MAX_SCRAMBLE_SIZE).enumerate() {
+            let prp = self.chunk_prp(nonce, index, chunk.len());
+            let scrambled = chunk.to_vec();
+            for (pos, byte) in chunk.iter_mut().enumerate() {
+                *byte = scrambled[prp.permute(pos)];
+            }
+        }
+    }
+
+    /// Round tables for chunk `index` come from the ChaCha20 keystream under `nonce`
+    fn chunk_prp(&self, nonce: &[u8; 12], index: usize, len: usize) -> PositionPrp {
+        let half_bits = match len {
+            0..=1 => 0,
+            _ => (usize::BITS - (len - 1).leading_zeros()).div_ceil(2),
+        };
+        let mut tables = vec![0u8; FEISTEL_ROUNDS << half_bits];
+        let mut stream = ChaCha20::new((&*self.key).into(), nonce.into());
+        stream.seek(index as u64 * CHUNK_TABLE_LEN);
+        stream.apply_keystream(&mut tables);
+        PositionPrp { tables, half_bits, len }
+    }
+}
+
+/// Keyed permutation of `0..len` built from a balanced Feistel network
+///
+/// The network permutes `0..4^half_bits`; cycle walking re-encrypts values
+/// outside `0..len` until they land inside, which keeps the result a permutation.
+struct PositionPrp {
+    /// One `2^half_bits`-entry round function per round
+    tables: Vec<u8>,
+    half_bits: u32,
+    len: usize,
+}
+
+impl PositionPrp {
+    fn permute(&self, pos: usize) -> usize {
+        if self.half_bits == 0 {
+            return pos;
+        }
+        let mut value = self.feistel(pos);
+        while value >= self.len {
+            value = self.feistel(value);
+        }
+        value
+    }
+
+    fn feistel(&self, value: usize) -> usize {
+        let mask = (1usize << self.half_bits) - 1;
+        let (mut left, mut right) = (value >> self.half_bits, value & mask);
+        for round in self.tables.chunks_exact(1 << self.half_bits) {
+            let f = round[right] as usize & mask;
+            (left, right) = (right, left ^ f);
+        }
+        (left << self.half_bits) | right
+    }
+}
+
 /// Ciphertext scrambler using Fisher-Yates derived permutation
+///
+/// Only scrambles data of exactly one size; prefer [`PacketScrambler`].
 pub struct CiphertextScrambler {
     /// Forward permutation: original_pos -> scrambled_pos
     forward_map: Vec<u16>,
@@ -288,4 +404,48 @@ mod tests {
         scramble_with_entropy(&mut data, &entropy);
         assert!(data.is_empty());
     }
+
+    #[test]
+    fn test_packet_scrambler_any_length() {
+        let scrambler = PacketScrambler::new(&[0x5Au8; 32]);
+        let nonce = [0x01u8; 12];
+
+        for len in [0, 1, 2, 3, 17, 255, 256, 1000, MAX_SCRAMBLE_SIZE, MAX_SCRAMBLE_SIZE + 333] {
+            let original: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let mut data = original.clone();
+            scrambler.scramble(&nonce, &mut data);
+            if len >= 17 {
+                assert_ne!(data, original, "length {} left unscrambled", len);
+            }
+            let mut sorted = data.clone();
+            sorted.sort_unstable();
+            let mut expected = original.clone();
+            expected.sort_unstable();
+            assert_eq!(sorted, expected, "length {} is not a permutation", len);
+
+            scrambler.unscramble(&nonce, &mut data);
+            assert_eq!(data, original, "length {} did not round-trip", len);
+        }
+    }
+
+    #[test]
+    fn test_packet_scrambler_varies_per_nonce_and_key() {
+        let scrambler = PacketScrambler::new(&[0x5Au8; 32]);
+        let original: Vec<u8> = (0..=255).collect();
+
+        let scramble = |scrambler: &PacketScrambler, nonce: [u8; 12]| {
+            let mut data = original.clone();
+            scrambler.scramble(&nonce, &mut data);
+            data
+        };
+        let first = scramble(&scrambler, [0x01; 12]);
+        assert_eq!(first, scramble(&scrambler, [0x01; 12]));
+        assert_ne!(first, scramble(&scrambler, [0x02; 12]));
+        assert_ne!(first, scramble(&PacketScrambler::new(&[0x5Bu8; 32]), [0x01; 12]));
+
+        // Unscrambling under the wrong nonce does not restore the packet
+        let mut data = first;
+        scrambler.unscramble(&[0x02; 12], &mut data);
+        assert_ne!(data, original);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,383 @@
+//! Crash-safe Session Persistence
+//!
+//! A [`SessionState`] captures everything a [`WasifVernam`](crate::wasif_vernam::WasifVernam)
+//! needs to resume after a restart: AEAD key, nonce counter, keystream offset, key
+//! epoch, replay window, recursive chain, synchronized-buffer position and whether
+//! key commitment is required.
+//!
+//! # Sealed Format
+//! ```text
+//! magic "ZKSS" (4) | version (1) | sequence (8, BE) | nonce (24) | ciphertext | tag (16)
+//! ```
+//! The body is sealed with XChaCha20-Poly1305 under a key derived from the caller's
+//! storage key, and the header is bound as associated data.
+//!
+//! # Nonce Safety
+//! A snapshot records the nonce counter and keystream offset pushed ahead by
+//! [`SESSION_NONCE_RESERVE`] and [`SESSION_OFFSET_RESERVE`], so messages sent after
+//! the snapshot is written never reuse a nonce once it is restored. Take a new snapshot
+//! when [`needs_snapshot`](crate::wasif_vernam::WasifVernam::needs_snapshot) fires:
+//! until then the cipher refuses to encrypt, and a restored cipher refuses until its
+//! first new snapshot.
+//!
+//! Every snapshot carries a sequence number. [`SessionState::open`] refuses any
+//! snapshot older than the last one written, which the caller must track.
+
+use alloc::vec::Vec;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::aead_backend::{AeadAlgorithm, AeadBackend, AEAD_TAG_LEN, XNONCE_LEN};
+use crate::anti_replay::ReplayState;
+use crate::recursive_chain::ChainState;
+use crate::rng;
+
+/// Magic bytes identifying a sealed session snapshot
+pub const SESSION_MAGIC: [u8; 4] = *b"ZKSS";
+
+/// Current session snapshot format version
+pub const SESSION_VERSION: u8 = 1;
+
+/// Messages that may be sent after a snapshot before it must be replaced
+pub const SESSION_NONCE_RESERVE: u64 = 1 << 16;
+
+/// Keystream bytes that may be used after a snapshot before it must be replaced
+pub const SESSION_OFFSET_RESERVE: u64 = 1 << 32;
+
+/// Length of the sealed snapshot header
+const SEALED_HEADER_LEN: usize = 4 + 1 + 8 + XNONCE_LEN;
+
+/// Synchronized Vernam buffer position
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct SyncBufferState {
+    pub(crate) shared_seed: [u8; 32],
+    pub(crate) starting_round: u64,
+    pub(crate) is_alice: Option<bool>,
+    pub(crate) position: u64,
+}
+
+/// Snapshot of a cipher session
+///
+/// Produced by [`WasifVernam::snapshot`](crate::wasif_vernam::WasifVernam::snapshot)
+/// and consumed by [`WasifVernam::restore`](crate::wasif_vernam::WasifVernam::restore).
+/// The scrambler, TRUE Vernam fetch buffer, previous-epoch keys and any pending key
+/// update are not captured.
+pub struct SessionState {
+    pub(crate) sequence: u64,
+    pub(crate) algorithm: AeadAlgorithm,
+    pub(crate) aead_key: [u8; 32],
+    pub(crate) key_epoch: u32,
+    pub(crate) nonce_counter: u64,
+    pub(crate) key_offset: u64,
+    pub(crate) swarm_seed: Option<[u8; 32]>,
+    pub(crate) replay: ReplayState,
+    pub(crate) chain: Option<ChainState>,
+    pub(crate) sync_buffer: Option<SyncBufferState>,
+    pub(crate) key_commitment: bool,
+}
+
+impl SessionState {
+    /// Sequence number of this snapshot, increasing with every snapshot taken
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Encrypt and authenticate the snapshot under `storage_key`
+    pub fn seal(&self, storage_key: &[u8; 32]) -> Result<Vec<u8>, SessionError> {
+        let mut header = [0u8; SEALED_HEADER_LEN];
+        header[..4].copy_from_slice(&SESSION_MAGIC);
+        header[4] = SESSION_VERSION;
+        header[5..13].copy_from_slice(&self.sequence.to_be_bytes());
+        rng::fill(&mut header[13..]).map_err(|_| SessionError::Rng)?;
+
+        let body = Zeroizing::new(self.encode());
+        let cipher = storage_cipher(storage_key)?;
+        let ciphertext = cipher
+            .encrypt(&header[13..], &body, &header)
+            .map_err(|_| SessionError::Crypto)?;
+
+        let mut sealed = Vec::with_capacity(SEALED_HEADER_LEN + ciphertext.len());
+        sealed.extend_from_slice(&header);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Read the sequence number of a sealed snapshot without decrypting it
+    ///
+    /// The value is not authenticated until [`open`](Self::open) succeeds.
+    pub fn peek_sequence(sealed: &[u8]) -> Result<u64, SessionError> {
+        let header = parse_header(sealed)?;
+        Ok(u64::from_be_bytes(header[5..13].try_into().expect("8-byte slice")))
+    }
+
+    /// Decrypt a sealed snapshot
+    ///
+    /// `last_sequence` is the sequence of the most recent snapshot the caller wrote;
+    /// anything older is refused as [`SessionError::Stale`].
+    pub fn open(sealed: &[u8], storage_key: &[u8; 32], last_sequence: u64) -> Result<Self, SessionError> {
+        let header = parse_header(sealed)?;
+        let cipher = storage_cipher(storage_key)?;
+        let body = Zeroizing::new(
+            cipher
+                .decrypt(&header[13..], &sealed[SEALED_HEADER_LEN..], header)
+                .map_err(|_| SessionError::Crypto)?,
+        );
+
+        let state = Self::decode(&body)?;
+        let sequence = Self::peek_sequence(sealed)?;
+        if state.sequence != sequence {
+            return Err(SessionError::Malformed);
+        }
+        if sequence < last_sequence {
+            return Err(SessionError::Stale {
+                sequence,
+                last: last_sequence,
+            });
+        }
+        Ok(state)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(160 + self.replay.recorded.len() * 8);
+        out.extend_from_slice(&self.sequence.to_be_bytes());
+        out.push(self.algorithm as u8);
+        out.extend_from_slice(&self.aead_key);
+        out.extend_from_slice(&self.key_epoch.to_be_bytes());
+        out.extend_from_slice(&self.nonce_counter.to_be_bytes());
+        out.extend_from_slice(&self.key_offset.to_be_bytes());
+
+        match self.swarm_seed {
+            Some(ref seed) => {
+                out.push(1);
+                out.extend_from_slice(seed);
+            }
+            None => out.push(0),
+        }
+
+        out.extend_from_slice(&self.replay.window_size.to_be_bytes());
+        out.extend_from_slice(&self.replay.counter_out.to_be_bytes());
+        out.extend_from_slice(&(self.replay.recorded.len() as u32).to_be_bytes());
+        for pid in &self.replay.recorded {
+            out.extend_from_slice(&pid.to_be_bytes());
+        }
+
+        match self.chain {
+            Some(ref chain) => {
+                out.push(1);
+                out.extend_from_slice(&chain.chain);
+                out.extend_from_slice(&chain.alice_key);
+                out.extend_from_slice(&chain.bob_key);
+                out.extend_from_slice(&chain.generation.to_be_bytes());
+                out.push(chain.is_alice as u8);
+            }
+            None => out.push(0),
+        }
+
+        match self.sync_buffer {
+            Some(ref sync) => {
+                out.push(1);
+                out.extend_from_slice(&sync.shared_seed);
+                out.extend_from_slice(&sync.starting_round.to_be_bytes());
+                out.push(match sync.is_alice {
+                    None => 0,
+                    Some(true) => 1,
+                    Some(false) => 2,
+                });
+                out.extend_from_slice(&sync.position.to_be_bytes());
+            }
+            None => out.push(0),
+        }
+        out.push(self.key_commitment as u8);
+        out
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, SessionError> {
+        let mut r = Reader { data };
+        let sequence = r.u64()?;
+        let algorithm = AeadAlgorithm::from_u8(r.u8()?).ok_or(SessionError::Malformed)?;
+        let aead_key = r.array()?;
+        let key_epoch = u32::from_be_bytes(r.array()?);
+        let nonce_counter = r.u64()?;
+        let key_offset = r.u64()?;
+        let swarm_seed = if r.flag()? { Some(r.array()?) } else { None };
+
+        let window_size = r.u64()?;
+        let counter_out = r.u64()?;
+        let count = u32::from_be_bytes(r.array()?) as usize;
+        if count > r.data.len() / 8 {
+            return Err(SessionError::Malformed);
+        }
+        let recorded = (0..count).map(|_| r.u64()).collect::<Result<Vec<_>, _>>()?;
+
+        let chain = if r.flag()? {
+            Some(ChainState {
+                chain: r.array()?,
+                alice_key: r.array()?,
+                bob_key: r.array()?,
+                generation: r.u64()?,
+                is_alice: r.flag()?,
+            })
+        } else {
+            None
+        };
+
+        let sync_buffer = if r.flag()? {
+            Some(SyncBufferState {
+                shared_seed: r.array()?,
+                starting_round: r.u64()?,
+                is_alice: match r.u8()? {
+                    0 => None,
+                    1 => Some(true),
+                    2 => Some(false),
+                    _ => return Err(SessionError::Malformed),
+                },
+                position: r.u64()?,
+            })
+        } else {
+            None
+        };
+
+        let key_commitment = r.flag()?;
+
+        if !r.data.is_empty() {
+            return Err(SessionError::Malformed);
+        }
+
+        Ok(Self {
+            sequence,
+            algorithm,
+            aead_key,
+            key_epoch,
+            nonce_counter,
+            key_offset,
+            swarm_seed,
+            replay: ReplayState {
+                window_size,
+                counter_out,
+                recorded,
+            },
+            chain,
+            sync_buffer,
+            key_commitment,
+        })
+    }
+}
+
+impl Drop for SessionState {
+    fn drop(&mut self) {
+        self.aead_key.zeroize();
+        if let Some(ref mut seed) = self.swarm_seed {
+            seed.zeroize();
+        }
+        if let Some(ref mut sync) = self.sync_buffer {
+            sync.shared_seed.zeroize();
+        }
+    }
+}
+
+/// Secure debug implementation that doesn't expose key material
+impl core::fmt::Debug for SessionState {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SessionState")
+            .field("sequence", &self.sequence)
+            .field("algorithm", &self.algorithm)
+            .field("key_epoch", &self.key_epoch)
+            .field("nonce_counter", &self.nonce_counter)
+            .field("key_offset", &self.key_offset)
+            .field("key_commitment", &self.key_commitment)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Errors produced when sealing or opening a session snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    /// Not a session snapshot
+    BadMagic,
+    /// Snapshot written by an unknown format version
+    UnsupportedVersion(u8),
+    /// Snapshot is truncated or its contents are inconsistent
+    Malformed,
+    /// Wrong storage key or tampered snapshot
+    Crypto,
+    /// Snapshot is older than the last one written
+    Stale {
+        /// Sequence of the snapshot being restored
+        sequence: u64,
+        /// Sequence of the last snapshot written
+        last: u64,
+    },
+    /// RNG unavailable for the snapshot nonce
+    Rng,
+}
+
+impl core::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SessionError::BadMagic => write!(f, "Not a session snapshot"),
+            SessionError::UnsupportedVersion(v) => write!(f, "Unsupported session snapshot version: {}", v),
+            SessionError::Malformed => write!(f, "Malformed session snapshot"),
+            SessionError::Crypto => write!(f, "Session snapshot failed to authenticate"),
+            SessionError::Stale { sequence, last } => {
+                write!(f, "Stale session snapshot {} (last written {})", sequence, last)
+            }
+            SessionError::Rng => write!(f, "RNG unavailable while sealing session snapshot"),
+        }
+    }
+}
+
+impl core::error::Error for SessionError {}
+
+fn parse_header(sealed: &[u8]) -> Result<&[u8], SessionError> {
+    if sealed.len() < SEALED_HEADER_LEN + AEAD_TAG_LEN {
+        return Err(SessionError::Malformed);
+    }
+    if sealed[..4] != SESSION_MAGIC {
+        return Err(SessionError::BadMagic);
+    }
+    if sealed[4] != SESSION_VERSION {
+        return Err(SessionError::UnsupportedVersion(sealed[4]));
+    }
+    Ok(&sealed[..SEALED_HEADER_LEN])
+}
+
+/// Derive the snapshot cipher from the caller's storage key
+fn storage_cipher(storage_key: &[u8; 32]) -> Result<AeadBackend, SessionError> {
+    let hk = Hkdf::<Sha256>::new(Some(b"zks-session-state"), storage_key);
+    let mut key = Zeroizing::new([0u8; 32]);
+    hk.expand(b"snapshot-key", &mut *key).map_err(|_| SessionError::Crypto)?;
+    AeadBackend::new(AeadAlgorithm::XChaCha20Poly1305, &key).map_err(|_| SessionError::Crypto)
+}
+
+/// Cursor over a decrypted snapshot body
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SessionError> {
+        if self.data.len() < N {
+            return Err(SessionError::Malformed);
+        }
+        let (head, rest) = self.data.split_at(N);
+        self.data = rest;
+        Ok(head.try_into().expect("N-byte slice"))
+    }
+
+    fn u8(&mut self) -> Result<u8, SessionError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, SessionError> {
+        Ok(u64::from_be_bytes(self.array()?))
+    }
+
+    fn flag(&mut self) -> Result<bool, SessionError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SessionError::Malformed),
+        }
+    }
+}
@@ -0,0 +1,598 @@
+//! Threshold Backup of Identity Keys
+//!
+//! Shamir secret sharing over GF(2^8) splits an identity's ML-KEM and ML-DSA
+//! keypairs into `n` shares, any `threshold` of which recover them. Fewer shares
+//! reveal nothing about the keys.
+//!
+//! # Share Format
+//! ```text
+//! magic "ZKSS" (4) | version (1) | backup id (16) | threshold (1) | index (1)
+//! | secret digest (32) | payload length (4, BE) | payload | checksum (32)
+//! ```
+//! The checksum (SHA-256 over everything before it) catches a share damaged in
+//! storage. The secret digest commits every share of a backup to the original
+//! keys, so a share that parses but was tampered with is caught at recovery:
+//! with more than `threshold` shares on hand, recovery finds a consistent subset
+//! and reports the shares that disagree with it. The checksum is unkeyed, so a
+//! share's header can be rewritten too; shares are grouped by header and a share
+//! whose header disagrees with the recovering group is reported the same way.
+
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+use zks_pqcrypto::{MlDsaKeypair, MlKemKeypair};
+
+use crate::constant_time::ct_eq;
+use crate::keystore::{Identity, Keystore, KeystoreError};
+
+/// Magic bytes identifying a backup share
+pub const SHARE_MAGIC: [u8; 4] = *b"ZKSH";
+
+/// Current share format version
+pub const SHARE_VERSION: u8 = 0x01;
+
+/// Most share subsets tried when looking for a consistent one
+pub const MAX_RECOVERY_ATTEMPTS: usize = 4096;
+
+const BACKUP_ID_LEN: usize = 16;
+const DIGEST_LEN: usize = 32;
+const SHARE_HEADER_LEN: usize = 4 + 1 + BACKUP_ID_LEN + 1 + 1 + DIGEST_LEN + 4;
+
+/// One share of a key backup
+pub struct Share {
+    backup_id: [u8; BACKUP_ID_LEN],
+    threshold: u8,
+    index: u8,
+    digest: [u8; DIGEST_LEN],
+    payload: Zeroizing<Vec<u8>>,
+}
+
+impl Share {
+    /// Backup this share belongs to
+    pub fn backup_id(&self) -> &[u8; BACKUP_ID_LEN] {
+        &self.backup_id
+    }
+
+    /// Shares needed to recover
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Share index, 1-based
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Serialize the share with its checksum
+    pub fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        let mut out = Zeroizing::new(Vec::with_capacity(SHARE_HEADER_LEN + self.payload.len() + DIGEST_LEN));
+        out.extend_from_slice(&SHARE_MAGIC);
+        out.push(SHARE_VERSION);
+        out.extend_from_slice(&self.backup_id);
+        out.push(self.threshold);
+        out.push(self.index);
+        out.extend_from_slice(&self.digest);
+        out.extend_from_slice(&(self.payload.len() as u32).to_be_bytes());
+        out.extend_from_slice(&self.payload);
+        let checksum = Sha256::digest(&out[..]);
+        out.extend_from_slice(&checksum);
+        out
+    }
+
+    /// Parse a share, verifying its checksum
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ShamirError> {
+        if data.len() < SHARE_HEADER_LEN + DIGEST_LEN {
+            return Err(ShamirError::Malformed);
+        }
+        if data[..4] != SHARE_MAGIC {
+            return Err(ShamirError::BadMagic);
+        }
+        if data[4] != SHARE_VERSION {
+            return Err(ShamirError::UnsupportedVersion(data[4]));
+        }
+        let (body, checksum) = data.split_at(data.len() - DIGEST_LEN);
+        if !ct_eq(&Sha256::digest(body), checksum) {
+            warn!("Backup share failed its checksum");
+            return Err(ShamirError::CorruptShare);
+        }
+
+        let backup_id: [u8; BACKUP_ID_LEN] = body[5..21].try_into().expect("16-byte slice");
+        let threshold = body[21];
+        let index = body[22];
+        let digest: [u8; DIGEST_LEN] = body[23..55].try_into().expect("32-byte slice");
+        let payload_len = u32::from_be_bytes(body[55..59].try_into().expect("4-byte slice")) as usize;
+        if payload_len != body.len() - SHARE_HEADER_LEN || threshold == 0 || index == 0 {
+            return Err(ShamirError::Malformed);
+        }
+        Ok(Self {
+            backup_id,
+            threshold,
+            index,
+            digest,
+            payload: Zeroizing::new(body[SHARE_HEADER_LEN..].to_vec()),
+        })
+    }
+}
+
+impl std::fmt::Debug for Share {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Share")
+            .field("backup_id", &hex::encode(self.backup_id))
+            .field("threshold", &self.threshold)
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Split `secret` into `shares` shares, any `threshold` of which recover it
+pub fn split(secret: &[u8], threshold: u8, shares: u8) -> Result<Vec<Share>, ShamirError> {
+    if threshold == 0 || shares < threshold {
+        return Err(ShamirError::InvalidThreshold { threshold, shares });
+    }
+    let mut backup_id = [0u8; BACKUP_ID_LEN];
+    getrandom::getrandom(&mut backup_id).map_err(|_| ShamirError::Rng)?;
+    let digest = secret_digest(&backup_id, threshold, secret);
+
+    // Row k holds coefficient k + 1 of every byte's polynomial
+    let degree = threshold as usize - 1;
+    let mut coefficients = Zeroizing::new(vec![0u8; degree * secret.len()]);
+    getrandom::getrandom(&mut coefficients).map_err(|_| ShamirError::Rng)?;
+
+    let shares = (1..=shares)
+        .map(|x| {
+            let payload = secret
+                .iter()
+                .enumerate()
+                .map(|(i, &s)| {
+                    // Horner's rule from the highest coefficient down
+                    let mut y = 0u8;
+                    for k in (0..degree).rev() {
+                        y = gf_mul(y, x) ^ coefficients[k * secret.len() + i];
+                    }
+                    gf_mul(y, x) ^ s
+                })
+                .collect();
+            Share {
+                backup_id,
+                threshold,
+                index: x,
+                digest,
+                payload: Zeroizing::new(payload),
+            }
+        })
+        .collect();
+    Ok(shares)
+}
+
+/// Recovered secret and the shares that disagreed with it
+pub struct Recovered {
+    secret: Zeroizing<Vec<u8>>,
+    rejected: Vec<u8>,
+}
+
+impl Recovered {
+    /// Recovered secret
+    pub fn secret(&self) -> &[u8] {
+        &self.secret
+    }
+
+    /// Indices of shares inconsistent with the recovered secret
+    pub fn rejected(&self) -> &[u8] {
+        &self.rejected
+    }
+}
+
+/// Recover a secret from shares of one backup
+///
+/// Shares are grouped by backup ID, threshold, secret digest and length, largest
+/// group first. Within a group, subsets of `threshold` shares are tried until one
+/// matches the group's secret digest, so extra shares let recovery survive tampered
+/// ones. Shares outside the recovering group are reported as rejected.
+pub fn recover(shares: &[Share]) -> Result<Recovered, ShamirError> {
+    if shares.is_empty() {
+        return Err(ShamirError::NotEnoughShares { have: 0, need: 1 });
+    }
+
+    let mut groups: Vec<Vec<&Share>> = Vec::new();
+    for share in shares {
+        match groups.iter_mut().find(|group| same_backup(group[0], share)) {
+            Some(group) => group.push(share),
+            None => groups.push(vec![share]),
+        }
+    }
+    // Stable, so equally large groups are tried in the order they were supplied
+    groups.sort_by_key(|group| core::cmp::Reverse(group.len()));
+
+    let mut attempts = 0;
+    let mut tried_any = false;
+    for group in &groups {
+        let first = group[0];
+        let threshold = first.threshold as usize;
+        if distinct_indices(group) < threshold {
+            continue;
+        }
+        tried_any = true;
+
+        let mut subset: Vec<usize> = (0..threshold).collect();
+        while attempts < MAX_RECOVERY_ATTEMPTS {
+            let chosen: Vec<&Share> = subset.iter().map(|&i| group[i]).collect();
+            // Repeated indices make the interpolation singular
+            let distinct = chosen
+                .iter()
+                .enumerate()
+                .all(|(i, share)| chosen[..i].iter().all(|other| other.index != share.index));
+            if distinct {
+                attempts += 1;
+                let secret = interpolate(&chosen, 0);
+                if ct_eq(&secret_digest(&first.backup_id, first.threshold, &secret), &first.digest) {
+                    let rejected: Vec<u8> = shares
+                        .iter()
+                        .filter(|share| {
+                            !same_backup(first, share) || !ct_eq(&interpolate(&chosen, share.index), &share.payload)
+                        })
+                        .map(|share| share.index)
+                        .collect();
+                    if !rejected.is_empty() {
+                        warn!("Rejected tampered backup shares {:?}", rejected);
+                    }
+                    info!("🔑 Recovered backup from {} shares", threshold);
+                    return Ok(Recovered { secret, rejected });
+                }
+            }
+            if !next_combination(&mut subset, group.len()) {
+                break;
+            }
+        }
+    }
+
+    if tried_any {
+        warn!("No consistent subset of {} backup shares", shares.len());
+        return Err(ShamirError::RecoveryFailed);
+    }
+    if groups.len() > 1 {
+        return Err(ShamirError::MismatchedShares);
+    }
+    Err(ShamirError::NotEnoughShares {
+        have: distinct_indices(&groups[0]),
+        need: groups[0][0].threshold as usize,
+    })
+}
+
+/// Number of different share indices in `shares`
+fn distinct_indices(shares: &[&Share]) -> usize {
+    shares
+        .iter()
+        .enumerate()
+        .filter(|(i, share)| shares[..*i].iter().all(|other| other.index != share.index))
+        .count()
+}
+
+/// Whether two shares claim the same backup
+fn same_backup(a: &Share, b: &Share) -> bool {
+    a.backup_id == b.backup_id
+        && a.threshold == b.threshold
+        && a.digest == b.digest
+        && a.payload.len() == b.payload.len()
+}
+
+/// Identity keypairs recovered from a backup
+pub struct RecoveredKeys {
+    kem: MlKemKeypair,
+    signing: MlDsaKeypair,
+    rejected: Vec<u8>,
+}
+
+impl RecoveredKeys {
+    /// ML-KEM keypair
+    pub fn kem(&self) -> &MlKemKeypair {
+        &self.kem
+    }
+
+    /// ML-DSA keypair
+    pub fn signing(&self) -> &MlDsaKeypair {
+        &self.signing
+    }
+
+    /// Indices of shares inconsistent with the recovered keys
+    pub fn rejected(&self) -> &[u8] {
+        &self.rejected
+    }
+
+    /// Store the recovered keys as a new keystore identity
+    pub fn reseal(self, keystore: &Keystore, name: &str, password: &[u8]) -> Result<Identity, ShamirError> {
+        Ok(keystore.import(name, password, self.kem, self.signing)?)
+    }
+}
+
+/// Split an identity's keypairs into `shares` shares, any `threshold` of which recover them
+pub fn split_keys(
+    kem: &MlKemKeypair,
+    signing: &MlDsaKeypair,
+    threshold: u8,
+    shares: u8,
+) -> Result<Vec<Share>, ShamirError> {
+    let mut secret = Zeroizing::new(Vec::new());
+    for field in [kem.public_key(), kem.secret_key(), signing.verifying_key(), signing.signing_key()] {
+        secret.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        secret.extend_from_slice(field);
+    }
+    split(&secret, threshold, shares)
+}
+
+/// Recover identity keypairs from shares made by [`split_keys`]
+pub fn recover_keys(shares: &[Share]) -> Result<RecoveredKeys, ShamirError> {
+    let recovered = recover(shares)?;
+    let mut rest = recovered.secret();
+    let mut fields = Vec::with_capacity(4);
+    for _ in 0..4 {
+        let len = rest
+            .get(..4)
+            .map(|len| u32::from_be_bytes(len.try_into().expect("4-byte slice")) as usize)
+            .filter(|&len| len <= rest.len() - 4)
+            .ok_or(ShamirError::Malformed)?;
+        fields.push(rest[4..4 + len].to_vec());
+        rest = &rest[4 + len..];
+    }
+    if !rest.is_empty() {
+        return Err(ShamirError::Malformed);
+    }
+    let mut fields = fields.into_iter();
+    let mut next = || fields.next().expect("four fields");
+    let kem = MlKemKeypair::from_bytes(next(), next()).map_err(|e| ShamirError::InvalidKeys(e.to_string()))?;
+    let signing = MlDsaKeypair::from_bytes(next(), next()).map_err(|e| ShamirError::InvalidKeys(e.to_string()))?;
+    Ok(RecoveredKeys {
+        kem,
+        signing,
+        rejected: recovered.rejected,
+    })
+}
+
+fn secret_digest(backup_id: &[u8; BACKUP_ID_LEN], threshold: u8, secret: &[u8]) -> [u8; DIGEST_LEN] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"zks-shamir-secret-v1");
+    hasher.update(backup_id);
+    hasher.update([threshold]);
+    hasher.update(secret);
+    hasher.finalize().into()
+}
+
+/// Evaluate the polynomial through `shares` at `x`
+fn interpolate(shares: &[&Share], x: u8) -> Zeroizing<Vec<u8>> {
+    let mut out = Zeroizing::new(vec![0u8; shares[0].payload.len()]);
+    for (i, share) in shares.iter().enumerate() {
+        // Lagrange basis: Π (x - x_j) / (x_i - x_j), with subtraction being XOR
+        let mut num = 1u8;
+        let mut den = 1u8;
+        for (j, other) in shares.iter().enumerate() {
+            if i != j {
+                num = gf_mul(num, x ^ other.index);
+                den = gf_mul(den, share.index ^ other.index);
+            }
+        }
+        let basis = gf_mul(num, gf_inv(den));
+        for (o, &y) in out.iter_mut().zip(share.payload.iter()) {
+            *o ^= gf_mul(basis, y);
+        }
+    }
+    out
+}
+
+/// Advance to the next `k`-combination of `0..n` in lexicographic order
+fn next_combination(subset: &mut [usize], n: usize) -> bool {
+    let k = subset.len();
+    for i in (0..k).rev() {
+        if subset[i] < n - k + i {
+            subset[i] += 1;
+            for j in i + 1..k {
+                subset[j] = subset[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+/// Constant-time multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse as a^254 (0 maps to 0)
+fn gf_inv(a: u8) -> u8 {
+    let a2 = gf_mul(a, a);
+    let a4 = gf_mul(a2, a2);
+    let a8 = gf_mul(a4, a4);
+    let a16 = gf_mul(a8, a8);
+    let a32 = gf_mul(a16, a16);
+    let a64 = gf_mul(a32, a32);
+    let a128 = gf_mul(a64, a64);
+    // 254 = 128 + 64 + 32 + 16 + 8 + 4 + 2
+    [a64, a32, a16, a8, a4, a2].into_iter().fold(a128, gf_mul)
+}
+
+/// Errors produced by threshold backups
+#[derive(Debug)]
+pub enum ShamirError {
+    /// Threshold must be between 1 and the number of shares
+    InvalidThreshold {
+        /// Requested threshold
+        threshold: u8,
+        /// Requested number of shares
+        shares: u8,
+    },
+    /// Fewer shares than the threshold
+    NotEnoughShares {
+        /// Shares supplied
+        have: usize,
+        /// Shares needed
+        need: usize,
+    },
+    /// Shares come from different backups, none with enough shares to recover
+    MismatchedShares,
+    /// Share failed its checksum
+    CorruptShare,
+    /// Not a backup share
+    BadMagic,
+    /// Share written by an unknown format version
+    UnsupportedVersion(u8),
+    /// Share or recovered secret is truncated or inconsistent
+    Malformed,
+    /// No subset of the shares reproduces the backed-up secret
+    RecoveryFailed,
+    /// Recovered bytes are not valid keypairs
+    InvalidKeys(String),
+    /// Storing recovered keys failed
+    Keystore(KeystoreError),
+    /// RNG unavailable for coefficients
+    Rng,
+}
+
+impl std::fmt::Display for ShamirError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShamirError::InvalidThreshold { threshold, shares } => {
+                write!(f, "Invalid threshold {} of {} shares", threshold, shares)
+            }
+            ShamirError::NotEnoughShares { have, need } => write!(f, "Need {} shares, have {}", need, have),
+            ShamirError::MismatchedShares => write!(f, "Shares belong to different backups"),
+            ShamirError::CorruptShare => write!(f, "Share failed its checksum"),
+            ShamirError::BadMagic => write!(f, "Not a backup share"),
+            ShamirError::UnsupportedVersion(version) => write!(f, "Unsupported share version {}", version),
+            ShamirError::Malformed => write!(f, "Malformed backup share"),
+            ShamirError::RecoveryFailed => write!(f, "Shares do not reproduce the backed-up secret"),
+            ShamirError::InvalidKeys(e) => write!(f, "Recovered keys are invalid: {}", e),
+            ShamirError::Keystore(e) => write!(f, "{}", e),
+            ShamirError::Rng => write!(f, "RNG unavailable"),
+        }
+    }
+}
+
+impl std::error::Error for ShamirError {}
+
+impl From<KeystoreError> for ShamirError {
+    fn from(e: KeystoreError) -> Self {
+        ShamirError::Keystore(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keystore::KdfParams;
+    use zks_pqcrypto::{MlDsa, MlKem};
+
+    #[test]
+    fn test_gf256_inverse() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+    }
+
+    #[test]
+    fn test_any_threshold_subset_recovers() {
+        let secret = b"long-term identity secret".to_vec();
+        let shares = split(&secret, 3, 5).unwrap();
+
+        let mut subset = vec![0, 1, 2];
+        loop {
+            let chosen: Vec<Share> = subset
+                .iter()
+                .map(|&i: &usize| Share::from_bytes(&shares[i].to_bytes()).unwrap())
+                .collect();
+            let recovered = recover(&chosen).unwrap();
+            assert_eq!(recovered.secret(), &secret[..]);
+            assert!(recovered.rejected().is_empty());
+            if !next_combination(&mut subset, 5) {
+                break;
+            }
+        }
+
+        assert!(matches!(
+            recover(&split(&secret, 3, 5).unwrap()[..2]),
+            Err(ShamirError::NotEnoughShares { have: 2, need: 3 })
+        ));
+        assert!(matches!(split(&secret, 4, 3), Err(ShamirError::InvalidThreshold { .. })));
+
+        // A share supplied twice does not count twice
+        let shares = split(&secret, 2, 3).unwrap();
+        let twice = [Share::from_bytes(&shares[0].to_bytes()).unwrap(), Share::from_bytes(&shares[0].to_bytes()).unwrap()];
+        assert!(matches!(recover(&twice), Err(ShamirError::NotEnoughShares { have: 1, need: 2 })));
+    }
+
+    #[test]
+    fn test_corrupted_shares_are_detected() {
+        let shares = split(b"secret", 2, 4).unwrap();
+
+        // Storage damage fails the checksum
+        let mut damaged = shares[0].to_bytes();
+        damaged[SHARE_HEADER_LEN] ^= 1;
+        assert!(matches!(Share::from_bytes(&damaged), Err(ShamirError::CorruptShare)));
+
+        // A tampered share with a fixed-up checksum is outvoted by the others
+        let mut tampered: Vec<Share> = shares.iter().map(|s| Share::from_bytes(&s.to_bytes()).unwrap()).collect();
+        tampered[0].payload[0] ^= 1;
+        let recovered = recover(&tampered).unwrap();
+        assert_eq!(recovered.secret(), b"secret");
+        assert_eq!(recovered.rejected(), &[1]);
+
+        // With only the threshold available there is nothing to outvote it with
+        assert!(matches!(recover(&tampered[..2]), Err(ShamirError::RecoveryFailed)));
+
+        // So is a share whose header was rewritten along with its checksum
+        let mut relabeled: Vec<Share> = shares.iter().map(|s| Share::from_bytes(&s.to_bytes()).unwrap()).collect();
+        relabeled[2].threshold = 3;
+        relabeled[2] = Share::from_bytes(&relabeled[2].to_bytes()).unwrap();
+        let recovered = recover(&relabeled).unwrap();
+        assert_eq!(recovered.secret(), b"secret");
+        assert_eq!(recovered.rejected(), &[3]);
+        relabeled[0].backup_id[0] ^= 1;
+        let recovered = recover(&relabeled).unwrap();
+        assert_eq!(recovered.secret(), b"secret");
+        assert_eq!(recovered.rejected(), &[1, 3]);
+
+        let other = split(b"secret", 2, 4).unwrap();
+        let mixed = [Share::from_bytes(&shares[0].to_bytes()).unwrap(), Share::from_bytes(&other[1].to_bytes()).unwrap()];
+        assert!(matches!(recover(&mixed), Err(ShamirError::MismatchedShares)));
+    }
+
+    #[test]
+    fn test_session_snapshot_is_not_a_share() {
+        use crate::wasif_vernam::WasifVernam;
+
+        let sealed = WasifVernam::new([5u8; 32]).unwrap().snapshot().seal(&[6u8; 32]).unwrap();
+        assert!(matches!(Share::from_bytes(&sealed), Err(ShamirError::BadMagic)));
+    }
+
+    #[test]
+    fn test_recovered_keys_reseal_into_keystore() {
+        let kem = MlKem::generate_keypair().unwrap();
+        let signing = MlDsa::generate_keypair().unwrap();
+        let shares = split_keys(&kem, &signing, 2, 3).unwrap();
+        let recovered = recover_keys(&shares[1..]).unwrap();
+        assert_eq!(recovered.kem().secret_key(), kem.secret_key());
+        assert_eq!(recovered.signing().signing_key(), signing.signing_key());
+
+        let mut id = [0u8; 8];
+        getrandom::getrandom(&mut id).unwrap();
+        let dir = std::env::temp_dir().join(format!("zks-shamir-{}", hex::encode(id)));
+        let keystore = Keystore::open(&dir).unwrap().with_kdf_params(KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        });
+        recovered.reseal(&keystore, "restored", b"pw").unwrap();
+        let loaded = keystore.load("restored", b"pw");
+        let _ = std::fs::remove_dir_all(&dir);
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.current().kem().public_key(), kem.public_key());
+        assert_eq!(loaded.current().signing().verifying_key(), signing.verifying_key());
+    }
+}
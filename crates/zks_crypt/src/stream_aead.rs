@@ -0,0 +1,899 @@
+//! Chunked Streaming AEAD
+//!
+//! Implements the STREAM construction (Hoang, Reyhanitabar, Rogaway, Vizár)
+//! on top of ChaCha20-Poly1305 so that arbitrarily large payloads can be
+//! encrypted and decrypted in constant memory.
+//!
+//! # Format
+//! ```text
+//! header  = version (1) || segment_size (4, BE) || nonce_prefix (7)
+//! segment = ChaCha20-Poly1305(plaintext_segment) || tag (16)
+//! nonce   = nonce_prefix (7) || segment_counter (4, BE) || last_flag (1)
+//! ```
+//! Every segment except the last one carries exactly `segment_size` bytes of
+//! plaintext. The last segment carries the remainder (possibly zero bytes) and
+//! is sealed with the last flag set. The header is bound as associated data
+//! to every segment.
+//!
+//! # Security Properties
+//! - Segments cannot be reordered or dropped (counter is part of the nonce)
+//! - Truncation at a segment boundary is detected (last flag)
+//! - Data appended after the final segment is rejected
+//! - A fresh random nonce prefix per stream keeps nonces unique under one key
+//!
+//! # Parallel Bulk Encryption
+//! Each segment has its own nonce, so with the `parallel` feature
+//! [`encrypt_stream_parallel`] seals the segments of a large buffer on all cores.
+//! The output is the same STREAM format, so [`StreamDecryptor`] and
+//! [`DecryptingReader`] verify it like any other stream.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use chacha20poly1305::{
+    aead::{AeadInPlace, KeyInit},
+    ChaCha20Poly1305, Nonce,
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Current stream format version
+pub const STREAM_VERSION: u8 = 0x01;
+
+/// Length of the stream header in bytes
+pub const STREAM_HEADER_LEN: usize = 12;
+
+/// Length of the Poly1305 tag appended to every segment
+pub const STREAM_TAG_LEN: usize = 16;
+
+/// Length of the random per-stream nonce prefix
+pub const NONCE_PREFIX_LEN: usize = 7;
+
+/// Default plaintext bytes per segment (64KB)
+pub const DEFAULT_SEGMENT_SIZE: usize = 64 * 1024;
+
+/// Largest accepted segment size (16MB)
+/// Bounds the memory a decryptor will commit to a single segment
+pub const MAX_SEGMENT_SIZE: usize = 16 * 1024 * 1024;
+
+/// Read size used by [`DecryptingReader`] when pulling ciphertext
+const READ_CHUNK: usize = 8 * 1024;
+
+/// Errors produced by the streaming AEAD
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamError {
+    /// Header is shorter than [`STREAM_HEADER_LEN`]
+    InvalidHeader,
+    /// Header carries a version this build does not understand
+    UnsupportedVersion(u8),
+    /// Segment size is zero or above [`MAX_SEGMENT_SIZE`]
+    InvalidSegmentSize {
+        /// The rejected segment size
+        size: usize,
+    },
+    /// A segment failed authentication (tampered, reordered or wrong key)
+    AuthenticationFailed,
+    /// The stream ended before its final segment
+    Truncated,
+    /// Segment counter space exhausted
+    CounterOverflow,
+    /// The final segment was already processed
+    AlreadyFinished,
+    /// OS random number generator failed
+    RandomFailure,
+}
+
+impl std::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::InvalidHeader => write!(f, "Stream header is missing or too short"),
+            StreamError::UnsupportedVersion(v) => write!(f, "Unsupported stream version: 0x{:02x}", v),
+            StreamError::InvalidSegmentSize { size } => {
+                write!(f, "Invalid segment size: {} bytes (must be 1..={})", size, MAX_SEGMENT_SIZE)
+            }
+            StreamError::AuthenticationFailed => write!(f, "Stream segment failed authentication"),
+            StreamError::Truncated => write!(f, "Stream truncated before the final segment"),
+            StreamError::CounterOverflow => write!(f, "Stream segment counter overflow"),
+            StreamError::AlreadyFinished => write!(f, "Stream already finished"),
+            StreamError::RandomFailure => write!(f, "Failed to generate stream nonce prefix"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<StreamError> for io::Error {
+    fn from(err: StreamError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Parsed stream header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamHeader {
+    /// Plaintext bytes per non-final segment
+    pub segment_size: u32,
+    /// Random per-stream nonce prefix
+    pub nonce_prefix: [u8; NONCE_PREFIX_LEN],
+}
+
+impl StreamHeader {
+    /// Serialize the header
+    pub fn to_bytes(&self) -> [u8; STREAM_HEADER_LEN] {
+        let mut out = [0u8; STREAM_HEADER_LEN];
+        out[0] = STREAM_VERSION;
+        out[1..5].copy_from_slice(&self.segment_size.to_be_bytes());
+        out[5..].copy_from_slice(&self.nonce_prefix);
+        out
+    }
+
+    /// Parse a header, rejecting unknown versions and invalid segment sizes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, StreamError> {
+        if bytes.len() < STREAM_HEADER_LEN {
+            return Err(StreamError::InvalidHeader);
+        }
+        if bytes[0] != STREAM_VERSION {
+            return Err(StreamError::UnsupportedVersion(bytes[0]));
+        }
+
+        let segment_size = u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+        validate_segment_size(segment_size as usize)?;
+
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        nonce_prefix.copy_from_slice(&bytes[5..STREAM_HEADER_LEN]);
+
+        Ok(Self { segment_size, nonce_prefix })
+    }
+}
+
+fn validate_segment_size(size: usize) -> Result<(), StreamError> {
+    if size == 0 || size > MAX_SEGMENT_SIZE {
+        return Err(StreamError::InvalidSegmentSize { size });
+    }
+    Ok(())
+}
+
+/// Build the STREAM nonce for a segment
+fn segment_nonce(prefix: &[u8; NONCE_PREFIX_LEN], counter: u32, last: bool) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    *Nonce::from_slice(&nonce)
+}
+
+/// Segment-at-a-time STREAM encryptor
+pub struct StreamEncryptor {
+    cipher: ChaCha20Poly1305,
+    header: StreamHeader,
+    header_bytes: [u8; STREAM_HEADER_LEN],
+    counter: u32,
+    finished: bool,
+}
+
+impl StreamEncryptor {
+    /// Create an encryptor with the default segment size and a random nonce prefix
+    pub fn new(key: &[u8; 32]) -> Result<Self, StreamError> {
+        Self::with_segment_size(key, DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// Create an encryptor with a custom segment size
+    pub fn with_segment_size(key: &[u8; 32], segment_size: usize) -> Result<Self, StreamError> {
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        getrandom::getrandom(&mut nonce_prefix).map_err(|_| StreamError::RandomFailure)?;
+        Self::with_header(key, segment_size, nonce_prefix)
+    }
+
+    /// Create an encryptor with an explicit nonce prefix
+    ///
+    /// The prefix MUST never be reused with the same key.
+    pub fn with_header(
+        key: &[u8; 32],
+        segment_size: usize,
+        nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, StreamError> {
+        validate_segment_size(segment_size)?;
+        let header = StreamHeader {
+            segment_size: segment_size as u32,
+            nonce_prefix,
+        };
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(key.into()),
+            header,
+            header_bytes: header.to_bytes(),
+            counter: 0,
+            finished: false,
+        })
+    }
+
+    /// Header that must precede the encrypted segments
+    pub fn header(&self) -> [u8; STREAM_HEADER_LEN] {
+        self.header_bytes
+    }
+
+    /// Plaintext bytes per non-final segment
+    pub fn segment_size(&self) -> usize {
+        self.header.segment_size as usize
+    }
+
+    /// Whether the final segment has been produced
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Encrypt a full, non-final segment
+    ///
+    /// `plaintext` must be exactly [`segment_size`](Self::segment_size) bytes.
+    pub fn encrypt_segment(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, StreamError> {
+        if plaintext.len() != self.segment_size() {
+            return Err(StreamError::InvalidSegmentSize { size: plaintext.len() });
+        }
+        let mut buf = Vec::with_capacity(plaintext.len() + STREAM_TAG_LEN);
+        buf.extend_from_slice(plaintext);
+        self.seal_in_place(&mut buf, false)?;
+        Ok(buf)
+    }
+
+    /// Encrypt the final segment (at most `segment_size` bytes, may be empty)
+    pub fn encrypt_last(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, StreamError> {
+        if plaintext.len() > self.segment_size() {
+            return Err(StreamError::InvalidSegmentSize { size: plaintext.len() });
+        }
+        let mut buf = Vec::with_capacity(plaintext.len() + STREAM_TAG_LEN);
+        buf.extend_from_slice(plaintext);
+        self.seal_in_place(&mut buf, true)?;
+        Ok(buf)
+    }
+
+    /// Seal `buf` in place, appending the tag
+    fn seal_in_place(&mut self, buf: &mut Vec<u8>, last: bool) -> Result<(), StreamError> {
+        if self.finished {
+            return Err(StreamError::AlreadyFinished);
+        }
+        if !last && self.counter == u32::MAX {
+            return Err(StreamError::CounterOverflow);
+        }
+
+        let nonce = segment_nonce(&self.header.nonce_prefix, self.counter, last);
+        self.cipher
+            .encrypt_in_place(&nonce, &self.header_bytes, buf)
+            .map_err(|_| StreamError::AuthenticationFailed)?;
+
+        if last {
+            self.finished = true;
+        } else {
+            self.counter += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Segment-at-a-time STREAM decryptor
+pub struct StreamDecryptor {
+    cipher: ChaCha20Poly1305,
+    header: StreamHeader,
+    header_bytes: [u8; STREAM_HEADER_LEN],
+    counter: u32,
+    finished: bool,
+}
+
+impl StreamDecryptor {
+    /// Create a decryptor from the key and the stream header
+    pub fn new(key: &[u8; 32], header: &[u8]) -> Result<Self, StreamError> {
+        let parsed = StreamHeader::from_bytes(header)?;
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(key.into()),
+            header: parsed,
+            header_bytes: parsed.to_bytes(),
+            counter: 0,
+            finished: false,
+        })
+    }
+
+    /// Plaintext bytes per non-final segment
+    pub fn segment_size(&self) -> usize {
+        self.header.segment_size as usize
+    }
+
+    /// Ciphertext bytes per non-final segment (segment size plus tag)
+    pub fn encrypted_segment_len(&self) -> usize {
+        self.segment_size() + STREAM_TAG_LEN
+    }
+
+    /// Whether the final segment has been verified
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Decrypt a full, non-final segment
+    pub fn decrypt_segment(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, StreamError> {
+        if ciphertext.len() != self.encrypted_segment_len() {
+            return Err(StreamError::Truncated);
+        }
+        let mut buf = ciphertext.to_vec();
+        self.open_in_place(&mut buf, false)?;
+        Ok(buf)
+    }
+
+    /// Decrypt and verify the final segment
+    pub fn decrypt_last(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, StreamError> {
+        if ciphertext.len() < STREAM_TAG_LEN {
+            return Err(StreamError::Truncated);
+        }
+        if ciphertext.len() > self.encrypted_segment_len() {
+            return Err(StreamError::AuthenticationFailed);
+        }
+        let mut buf = ciphertext.to_vec();
+        self.open_in_place(&mut buf, true)?;
+        Ok(buf)
+    }
+
+    /// Open `buf` in place, stripping the tag
+    fn open_in_place(&mut self, buf: &mut Vec<u8>, last: bool) -> Result<(), StreamError> {
+        if self.finished {
+            return Err(StreamError::AlreadyFinished);
+        }
+        if !last && self.counter == u32::MAX {
+            return Err(StreamError::CounterOverflow);
+        }
+
+        let nonce = segment_nonce(&self.header.nonce_prefix, self.counter, last);
+        self.cipher
+            .decrypt_in_place(&nonce, &self.header_bytes, buf)
+            .map_err(|_| StreamError::AuthenticationFailed)?;
+
+        if last {
+            self.finished = true;
+        } else {
+            self.counter += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Encrypt a complete buffer into the STREAM format (header followed by segments)
+pub fn encrypt_stream(key: &[u8; 32], plaintext: &[u8], segment_size: usize) -> Result<Vec<u8>, StreamError> {
+    let mut encryptor = StreamEncryptor::with_segment_size(key, segment_size)?;
+    let segments = plaintext.len() / segment_size + 1;
+    let mut out = Vec::with_capacity(STREAM_HEADER_LEN + plaintext.len() + segments * STREAM_TAG_LEN);
+    out.extend_from_slice(&encryptor.header());
+
+    let mut chunks = plaintext.chunks_exact(segment_size);
+    for chunk in &mut chunks {
+        out.extend_from_slice(&encryptor.encrypt_segment(chunk)?);
+    }
+    out.extend_from_slice(&encryptor.encrypt_last(chunks.remainder())?);
+    Ok(out)
+}
+
+/// Decrypt a complete STREAM buffer produced by [`encrypt_stream`] or [`EncryptingWriter`]
+pub fn decrypt_stream(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, StreamError> {
+    let mut decryptor = StreamDecryptor::new(key, data)?;
+    let body = &data[STREAM_HEADER_LEN..];
+    let segment_len = decryptor.encrypted_segment_len();
+
+    let mut out = Vec::with_capacity(body.len());
+    let mut pos = 0;
+    // A full segment is only non-final if more data follows it
+    while body.len() - pos > segment_len {
+        out.extend_from_slice(&decryptor.decrypt_segment(&body[pos..pos + segment_len])?);
+        pos += segment_len;
+    }
+    out.extend_from_slice(&decryptor.decrypt_last(&body[pos..])?);
+    Ok(out)
+}
+
+/// Encrypt a complete buffer into the STREAM format, sealing segments in parallel
+///
+/// Produces exactly what [`encrypt_stream`] would for the same nonce prefix.
+#[cfg(feature = "parallel")]
+pub fn encrypt_stream_parallel(key: &[u8; 32], plaintext: &[u8], segment_size: usize) -> Result<Vec<u8>, StreamError> {
+    let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+    getrandom::getrandom(&mut nonce_prefix).map_err(|_| StreamError::RandomFailure)?;
+    encrypt_parallel_with_header(key, plaintext, segment_size, nonce_prefix)
+}
+
+#[cfg(feature = "parallel")]
+fn encrypt_parallel_with_header(
+    key: &[u8; 32],
+    plaintext: &[u8],
+    segment_size: usize,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+) -> Result<Vec<u8>, StreamError> {
+    use rayon::prelude::*;
+
+    validate_segment_size(segment_size)?;
+    let header = StreamHeader {
+        segment_size: segment_size as u32,
+        nonce_prefix,
+    };
+    let header_bytes = header.to_bytes();
+    // The last segment holds the remainder and may be empty
+    let segments = plaintext.len() / segment_size + 1;
+    if segments - 1 > u32::MAX as usize {
+        return Err(StreamError::CounterOverflow);
+    }
+
+    let mut out = vec![0u8; STREAM_HEADER_LEN + plaintext.len() + segments * STREAM_TAG_LEN];
+    out[..STREAM_HEADER_LEN].copy_from_slice(&header_bytes);
+    let cipher = ChaCha20Poly1305::new(key.into());
+    out[STREAM_HEADER_LEN..]
+        .par_chunks_mut(segment_size + STREAM_TAG_LEN)
+        .enumerate()
+        .try_for_each(|(index, segment)| {
+            let start = index * segment_size;
+            let (buf, tag) = segment.split_at_mut(segment.len() - STREAM_TAG_LEN);
+            buf.copy_from_slice(&plaintext[start..start + buf.len()]);
+            let nonce = segment_nonce(&nonce_prefix, index as u32, index == segments - 1);
+            let sealed = cipher
+                .encrypt_in_place_detached(&nonce, &header_bytes, buf)
+                .map_err(|_| StreamError::AuthenticationFailed)?;
+            tag.copy_from_slice(&sealed);
+            Ok(())
+        })?;
+    Ok(out)
+}
+
+/// Decrypt a complete STREAM buffer, verifying segments in parallel
+///
+/// Accepts anything [`decrypt_stream`] accepts.
+#[cfg(feature = "parallel")]
+pub fn decrypt_stream_parallel(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, StreamError> {
+    use rayon::prelude::*;
+
+    let header = StreamHeader::from_bytes(data)?;
+    let header_bytes = header.to_bytes();
+    let segment_len = header.segment_size as usize + STREAM_TAG_LEN;
+    let body = &data[STREAM_HEADER_LEN..];
+    if body.len() < STREAM_TAG_LEN {
+        return Err(StreamError::Truncated);
+    }
+    // A full segment is only non-final if more data follows it
+    let segments = (body.len() - 1) / segment_len + 1;
+    if segments - 1 > u32::MAX as usize {
+        return Err(StreamError::CounterOverflow);
+    }
+    if body.len() - (segments - 1) * segment_len < STREAM_TAG_LEN {
+        return Err(StreamError::Truncated);
+    }
+
+    let cipher = ChaCha20Poly1305::new(key.into());
+    let mut buf = Zeroizing::new(body.to_vec());
+    buf.par_chunks_mut(segment_len).enumerate().try_for_each(|(index, segment)| {
+        let (ct, tag) = segment.split_at_mut(segment.len() - STREAM_TAG_LEN);
+        let nonce = segment_nonce(&header.nonce_prefix, index as u32, index == segments - 1);
+        cipher
+            .decrypt_in_place_detached(&nonce, &header_bytes, ct, (&*tag).into())
+            .map_err(|_| StreamError::AuthenticationFailed)
+    })?;
+
+    let mut out = Vec::with_capacity(body.len() - segments * STREAM_TAG_LEN);
+    for segment in buf.chunks(segment_len) {
+        out.extend_from_slice(&segment[..segment.len() - STREAM_TAG_LEN]);
+    }
+    Ok(out)
+}
+
+/// `AsyncWrite` adapter that encrypts everything written into STREAM segments
+///
+/// Call `shutdown()` to emit the final segment; a stream that is dropped
+/// without shutdown will be rejected as truncated by the reader.
+pub struct EncryptingWriter<W> {
+    inner: W,
+    encryptor: StreamEncryptor,
+    /// Plaintext waiting for a full segment
+    pending: Vec<u8>,
+    /// Ciphertext waiting to be written to `inner`
+    out: Vec<u8>,
+    out_pos: usize,
+}
+
+impl<W: AsyncWrite + Unpin> EncryptingWriter<W> {
+    /// Wrap `inner` using the default segment size
+    pub fn new(inner: W, key: &[u8; 32]) -> Result<Self, StreamError> {
+        Self::with_segment_size(inner, key, DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// Wrap `inner` using a custom segment size
+    pub fn with_segment_size(inner: W, key: &[u8; 32], segment_size: usize) -> Result<Self, StreamError> {
+        let encryptor = StreamEncryptor::with_segment_size(key, segment_size)?;
+        let out = encryptor.header().to_vec();
+        Ok(Self {
+            inner,
+            pending: Vec::with_capacity(segment_size + STREAM_TAG_LEN),
+            encryptor,
+            out,
+            out_pos: 0,
+        })
+    }
+
+    /// Get a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Consume the adapter and return the underlying writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Write buffered ciphertext to the inner writer
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.out_pos < self.out.len() {
+            let n = match Pin::new(&mut self.inner).poll_write(cx, &self.out[self.out_pos..]) {
+                Poll::Ready(Ok(n)) => n,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            };
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.out_pos += n;
+        }
+        self.out.clear();
+        self.out_pos = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Move the pending plaintext into the output buffer and seal it
+    fn seal_pending(&mut self, last: bool) -> Result<(), StreamError> {
+        std::mem::swap(&mut self.pending, &mut self.out);
+        self.out_pos = 0;
+        self.encryptor.seal_in_place(&mut self.out, last)
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for EncryptingWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        match this.poll_drain(cx) {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
+        }
+        if this.encryptor.is_finished() {
+            return Poll::Ready(Err(StreamError::AlreadyFinished.into()));
+        }
+
+        let room = this.encryptor.segment_size() - this.pending.len();
+        let n = room.min(buf.len());
+        this.pending.extend_from_slice(&buf[..n]);
+
+        if this.pending.len() == this.encryptor.segment_size() {
+            this.seal_pending(false)?;
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_drain(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_flush(cx),
+            other => other,
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_drain(cx) {
+            Poll::Ready(Ok(())) => {}
+            other => return other,
+        }
+        if !this.encryptor.is_finished() {
+            this.seal_pending(true)?;
+            match this.poll_drain(cx) {
+                Poll::Ready(Ok(())) => {}
+                other => return other,
+            }
+        }
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+/// `AsyncRead` adapter that decrypts and verifies a STREAM produced by [`EncryptingWriter`]
+///
+/// Plaintext is only released after its segment authenticates. Reaching EOF
+/// before the final segment yields an `InvalidData` error.
+pub struct DecryptingReader<R> {
+    inner: R,
+    key: Zeroizing<[u8; 32]>,
+    decryptor: Option<StreamDecryptor>,
+    /// Ciphertext read from `inner` but not yet decrypted
+    raw: Vec<u8>,
+    /// Verified plaintext not yet handed to the caller
+    plain: Zeroizing<Vec<u8>>,
+    plain_pos: usize,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> DecryptingReader<R> {
+    /// Wrap `inner`; the header is read lazily on first poll
+    pub fn new(inner: R, key: &[u8; 32]) -> Self {
+        Self {
+            inner,
+            key: Zeroizing::new(*key),
+            decryptor: None,
+            raw: Vec::new(),
+            plain: Zeroizing::new(Vec::new()),
+            plain_pos: 0,
+            eof: false,
+        }
+    }
+
+    /// Get a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Consume the adapter and return the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Try to turn buffered ciphertext into plaintext
+    ///
+    /// Returns `Ok(true)` if progress was made without needing more input.
+    fn advance(&mut self) -> Result<bool, StreamError> {
+        let decryptor = match self.decryptor.as_mut() {
+            Some(decryptor) => decryptor,
+            None => {
+                if self.raw.len() < STREAM_HEADER_LEN {
+                    return if self.eof { Err(StreamError::Truncated) } else { Ok(false) };
+                }
+                self.decryptor = Some(StreamDecryptor::new(&self.key, &self.raw[..STREAM_HEADER_LEN])?);
+                self.raw.drain(..STREAM_HEADER_LEN);
+                return Ok(true);
+            }
+        };
+
+        if decryptor.is_finished() {
+            return Ok(false);
+        }
+
+        let segment_len = decryptor.encrypted_segment_len();
+        self.plain.zeroize();
+        self.plain_pos = 0;
+
+        if self.raw.len() > segment_len {
+            let mut segment: Vec<u8> = self.raw.drain(..segment_len).collect();
+            decryptor.open_in_place(&mut segment, false)?;
+            self.plain = Zeroizing::new(segment);
+            Ok(true)
+        } else if self.eof {
+            let mut segment = std::mem::take(&mut self.raw);
+            if segment.len() < STREAM_TAG_LEN {
+                return Err(StreamError::Truncated);
+            }
+            decryptor.open_in_place(&mut segment, true)?;
+            self.plain = Zeroizing::new(segment);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for DecryptingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.plain_pos < this.plain.len() {
+                let n = (this.plain.len() - this.plain_pos).min(buf.remaining());
+                buf.put_slice(&this.plain[this.plain_pos..this.plain_pos + n]);
+                this.plain_pos += n;
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.decryptor.as_ref().is_some_and(|d| d.is_finished()) {
+                // Verified final segment already delivered
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.advance()? {
+                continue;
+            }
+
+            let mut chunk = [0u8; READ_CHUNK];
+            let mut read_buf = ReadBuf::new(&mut chunk);
+            match Pin::new(&mut this.inner).poll_read(cx, &mut read_buf) {
+                Poll::Ready(Ok(())) => {
+                    let filled = read_buf.filled();
+                    if filled.is_empty() {
+                        this.eof = true;
+                    } else {
+                        this.raw.extend_from_slice(filled);
+                    }
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const KEY: [u8; 32] = [0x42; 32];
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 + 7) as u8).collect()
+    }
+
+    #[test]
+    fn test_roundtrip_various_lengths() {
+        for len in [0, 1, 63, 64, 65, 128, 1000] {
+            let data = sample(len);
+            let sealed = encrypt_stream(&KEY, &data, 64).unwrap();
+            assert_eq!(decrypt_stream(&KEY, &sealed).unwrap(), data, "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_truncation_at_segment_boundary_detected() {
+        let data = sample(256);
+        let sealed = encrypt_stream(&KEY, &data, 64).unwrap();
+
+        // Drop the final (empty) segment: remaining stream ends on a full segment
+        let truncated = &sealed[..sealed.len() - STREAM_TAG_LEN];
+        assert_eq!(decrypt_stream(&KEY, truncated), Err(StreamError::AuthenticationFailed));
+
+        // Drop a whole data segment as well
+        let truncated = &sealed[..STREAM_HEADER_LEN + 2 * (64 + STREAM_TAG_LEN)];
+        assert!(decrypt_stream(&KEY, truncated).is_err());
+    }
+
+    #[test]
+    fn test_reordered_segments_rejected() {
+        let data = sample(200);
+        let mut sealed = encrypt_stream(&KEY, &data, 64).unwrap();
+        let seg = 64 + STREAM_TAG_LEN;
+        let (a, b) = (STREAM_HEADER_LEN, STREAM_HEADER_LEN + seg);
+        let first: Vec<u8> = sealed[a..a + seg].to_vec();
+        let second: Vec<u8> = sealed[b..b + seg].to_vec();
+        sealed[a..a + seg].copy_from_slice(&second);
+        sealed[b..b + seg].copy_from_slice(&first);
+
+        assert_eq!(decrypt_stream(&KEY, &sealed), Err(StreamError::AuthenticationFailed));
+    }
+
+    #[test]
+    fn test_header_is_authenticated() {
+        let sealed = encrypt_stream(&KEY, &sample(100), 64).unwrap();
+
+        let mut tampered = sealed.clone();
+        tampered[STREAM_HEADER_LEN - 1] ^= 0x01;
+        assert_eq!(decrypt_stream(&KEY, &tampered), Err(StreamError::AuthenticationFailed));
+
+        let mut tampered = sealed;
+        tampered[0] = 0x7F;
+        assert_eq!(decrypt_stream(&KEY, &tampered), Err(StreamError::UnsupportedVersion(0x7F)));
+    }
+
+    #[test]
+    fn test_trailing_data_rejected() {
+        let mut sealed = encrypt_stream(&KEY, &sample(100), 64).unwrap();
+        sealed.extend_from_slice(b"extra");
+        assert!(decrypt_stream(&KEY, &sealed).is_err());
+    }
+
+    #[test]
+    fn test_encryptor_refuses_after_last() {
+        let mut enc = StreamEncryptor::with_segment_size(&KEY, 16).unwrap();
+        enc.encrypt_last(b"done").unwrap();
+        assert_eq!(enc.encrypt_last(b""), Err(StreamError::AlreadyFinished));
+        assert_eq!(enc.encrypt_segment(&[0u8; 16]), Err(StreamError::AlreadyFinished));
+    }
+
+    #[test]
+    fn test_invalid_segment_size() {
+        assert!(matches!(
+            StreamEncryptor::with_segment_size(&KEY, 0),
+            Err(StreamError::InvalidSegmentSize { size: 0 })
+        ));
+        assert!(StreamEncryptor::with_segment_size(&KEY, MAX_SEGMENT_SIZE + 1).is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[tokio::test]
+    async fn test_parallel_output_matches_streaming_format() {
+        let prefix = [9u8; NONCE_PREFIX_LEN];
+        for len in [0, 1, 64, 65, 128, 10_000] {
+            let data = sample(len);
+            let parallel = encrypt_parallel_with_header(&KEY, &data, 64, prefix).unwrap();
+
+            let mut encryptor = StreamEncryptor::with_header(&KEY, 64, prefix).unwrap();
+            let mut serial = encryptor.header().to_vec();
+            let mut chunks = data.chunks_exact(64);
+            for chunk in &mut chunks {
+                serial.extend_from_slice(&encryptor.encrypt_segment(chunk).unwrap());
+            }
+            serial.extend_from_slice(&encryptor.encrypt_last(chunks.remainder()).unwrap());
+            assert_eq!(parallel, serial, "len {}", len);
+        }
+
+        // The streaming reader verifies parallel output
+        let data = sample(300_000);
+        let sealed = encrypt_stream_parallel(&KEY, &data, 4096).unwrap();
+        let mut reader = DecryptingReader::new(&sealed[..], &KEY);
+        let mut plaintext = Vec::new();
+        reader.read_to_end(&mut plaintext).await.unwrap();
+        assert_eq!(plaintext, data);
+        assert_eq!(decrypt_stream_parallel(&KEY, &sealed).unwrap(), data);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_decryption_rejects_tampering() {
+        for len in [0, 63, 64, 256] {
+            let data = sample(len);
+            let sealed = encrypt_stream(&KEY, &data, 64).unwrap();
+            assert_eq!(decrypt_stream_parallel(&KEY, &sealed).unwrap(), data, "len {}", len);
+        }
+
+        let sealed = encrypt_stream_parallel(&KEY, &sample(256), 64).unwrap();
+        let truncated = &sealed[..sealed.len() - STREAM_TAG_LEN];
+        assert_eq!(decrypt_stream_parallel(&KEY, truncated), Err(StreamError::AuthenticationFailed));
+        assert_eq!(decrypt_stream_parallel(&KEY, &sealed[..STREAM_HEADER_LEN + 5]), Err(StreamError::Truncated));
+
+        // Swapping two segments breaks the authenticated segment index
+        let segment = 64 + STREAM_TAG_LEN;
+        let mut swapped = sealed.clone();
+        let (first, second) = swapped[STREAM_HEADER_LEN..].split_at_mut(segment);
+        first.swap_with_slice(&mut second[..segment]);
+        assert_eq!(decrypt_stream_parallel(&KEY, &swapped), Err(StreamError::AuthenticationFailed));
+    }
+
+    #[tokio::test]
+    async fn test_async_adapters_roundtrip() {
+        let data = sample(300_000);
+
+        let mut writer = EncryptingWriter::with_segment_size(Vec::new(), &KEY, 4096).unwrap();
+        for chunk in data.chunks(1000) {
+            writer.write_all(chunk).await.unwrap();
+        }
+        writer.shutdown().await.unwrap();
+        let sealed = writer.into_inner();
+
+        // The writer and the one-shot decryptor agree on the format
+        assert_eq!(decrypt_stream(&KEY, &sealed).unwrap(), data);
+
+        let mut reader = DecryptingReader::new(&sealed[..], &KEY);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).await.unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[tokio::test]
+    async fn test_async_reader_detects_truncation() {
+        let data = sample(10_000);
+        let sealed = encrypt_stream(&KEY, &data, 1024).unwrap();
+        let truncated = &sealed[..sealed.len() - 100];
+
+        let mut reader = DecryptingReader::new(truncated, &KEY);
+        let mut out = Vec::new();
+        let err = reader.read_to_end(&mut out).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_writer_without_shutdown_is_truncated() {
+        let mut writer = EncryptingWriter::with_segment_size(Vec::new(), &KEY, 16).unwrap();
+        writer.write_all(&sample(64)).await.unwrap();
+        writer.flush().await.unwrap();
+        let sealed = writer.into_inner();
+
+        assert!(decrypt_stream(&KEY, &sealed).is_err());
+    }
+}
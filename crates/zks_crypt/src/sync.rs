@@ -0,0 +1,6 @@
+//! Atomics shared by std and `no_std` builds
+//!
+//! `AtomicU64` comes from `portable-atomic`, which is the native type wherever the
+//! target has one.
+
+pub(crate) use portable_atomic::AtomicU64;
@@ -21,14 +21,20 @@
 //! provides computational security (256-bit security level) but is no longer
 //! information-theoretically secure.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+#[cfg(feature = "fetcher")]
 use tokio::sync::Mutex;
+#[cfg(feature = "fetcher")]
 use tokio::time::{interval, Duration};
-use tracing::{debug, info, warn};
+#[cfg(feature = "fetcher")]
+use tracing::info;
+use tracing::{debug, warn};
 use sha2::{Digest, Sha256};
 use zeroize::Zeroize;
-use crate::constant_time::ct_eq;
+use crate::health::{HealthConfig, HealthFailure, HealthMetrics, HealthMonitor, HealthStatus};
+#[cfg(feature = "fetcher")]
+use crate::entropy::{EntropyCombiner, EntropySource, EntropySourceError, FixedEntropy, OsEntropy};
 use chacha20::{ChaCha20, cipher::{KeyIvInit, StreamCipher}};
 
 /// Minimum buffer size before we start warning
@@ -41,108 +47,6 @@ const TARGET_BUFFER_SIZE: usize = 1024 * 1024; // 1MB
 #[allow(dead_code)]
 const FETCH_CHUNK_SIZE: usize = 1024 * 32; // 32KB per request
 
-/// Minimum entropy quality threshold (0.0 to 1.0)
-const MIN_ENTROPY_QUALITY: f64 = 0.95;
-
-/// Validates that the provided bytes have sufficient entropy quality
-/// Returns true if the entropy appears to be truly random
-fn validate_entropy_quality(bytes: &[u8]) -> bool {
-    if bytes.is_empty() {
-        debug!("Entropy validation failed: empty bytes");
-        return false;
-    }
-    
-    // For very small data sizes, skip statistical tests
-    if bytes.len() < 32 {
-        // Just check for all zeros or all ones
-        if ct_is_zero(bytes) {
-            warn!("Entropy is all zeros");
-            return false;
-        }
-        let all_ones = bytes.iter().all(|&b| b == 0xFF);
-        if all_ones {
-            warn!("Entropy is all ones");
-            return false;
-        }
-        debug!("Small sample passed basic checks");
-        return true;
-    }
-    
-    // Basic statistical tests for randomness
-    // 1. Check byte distribution (should be roughly uniform)
-    let mut byte_counts = [0u32; 256];
-    for &byte in bytes {
-        byte_counts[byte as usize] += 1;
-    }
-    
-    // Chi-square test for uniformity (only for larger samples)
-    if bytes.len() >= 256 {
-        let expected_count = bytes.len() as f64 / 256.0;
-        let mut chi_square = 0.0;
-        for &count in &byte_counts {
-            if count > 0 {
-                let diff = count as f64 - expected_count;
-                chi_square += (diff * diff) / expected_count;
-            }
-        }
-        
-        // Chi-square should be reasonable for uniform distribution
-        // For 255 degrees of freedom, values between 200-300 are typical for random data
-        // Using wider bounds (100-500) to reduce false positives while maintaining security
-        debug!("Chi-square test result: {}", chi_square);
-        if chi_square < 100.0 || chi_square > 500.0 {
-            warn!("Entropy failed chi-square test: {}", chi_square);
-            return false;
-        }
-    }
-    
-    // 2. Check for obvious patterns (repeated bytes, sequences) - use constant-time comparison
-    let mut repeated_bytes = 0;
-    for i in 1..bytes.len() {
-        // Use constant-time comparison to avoid timing leaks
-        if ct_eq(&[bytes[i]], &[bytes[i-1]]) {
-            repeated_bytes += 1;
-        }
-    }
-    
-    // Too many repeated bytes suggests poor entropy
-    let repeat_ratio = repeated_bytes as f64 / bytes.len() as f64;
-    debug!("Repeat ratio: {:.2}%", repeat_ratio * 100.0);
-    if repeat_ratio > 0.05 {
-        warn!("Entropy has too many repeated bytes: {:.2}%", repeat_ratio * 100.0);
-        return false;
-    }
-    
-    // 3. Calculate Shannon entropy (only for larger samples)
-    if bytes.len() >= 64 {
-        let mut shannon_entropy = 0.0;
-        for &count in &byte_counts {
-            if count > 0 {
-                let probability = count as f64 / bytes.len() as f64;
-                shannon_entropy -= probability * probability.log2();
-            }
-        }
-        
-        // Shannon entropy should be close to maximum (8 bits per byte)
-        debug!("Shannon entropy: {:.2} bits", shannon_entropy);
-        if shannon_entropy < 7.5 {
-            warn!("Entropy has insufficient Shannon entropy: {:.2} bits", shannon_entropy);
-            return false;
-        }
-    }
-    
-    true
-}
-
-/// Constant-time check if all bytes are zero
-fn ct_is_zero(bytes: &[u8]) -> bool {
-    let mut acc = 0u8;
-    for &b in bytes {
-        acc |= b;
-    }
-    acc == 0
-}
-
 /// True Vernam Buffer: Stores TRUE random bytes for one-time use
 #[derive(Debug)]
 pub struct TrueVernamBuffer {
@@ -152,32 +56,65 @@ pub struct TrueVernamBuffer {
     bytes_consumed: u64,
     /// Total bytes fetched (for statistics)
     bytes_fetched: u64,
+    /// Health test parameters for new sources
+    health_config: HealthConfig,
+    /// Continuous health test state per source
+    health: HashMap<String, HealthMonitor>,
 }
 
 impl TrueVernamBuffer {
     /// Create a new empty buffer
     pub fn new() -> Self {
+        Self::with_health_config(HealthConfig::default())
+    }
+
+    /// Create a new empty buffer with custom health test parameters
+    pub fn with_health_config(health_config: HealthConfig) -> Self {
         Self {
             buffer: VecDeque::with_capacity(TARGET_BUFFER_SIZE),
             bytes_consumed: 0,
             bytes_fetched: 0,
+            health_config,
+            health: HashMap::new(),
         }
     }
 
     /// Add TRUE random bytes to the buffer
     /// 
-    /// # Security
-    /// This function validates that the provided bytes have sufficient entropy quality
-    /// to ensure information-theoretic security guarantees.
+    /// Equivalent to [`push_entropy_from`](Self::push_entropy_from) with a
+    /// source named `"default"`.
     pub fn push_entropy(&mut self, bytes: &[u8]) -> Result<(), EntropyError> {
-        // Validate buffer size (minimum 32 bytes for quality checks)
+        self.push_entropy_from("default", bytes)
+    }
+
+    /// Add TRUE random bytes delivered by `source` to the buffer
+    /// 
+    /// # Security
+    /// The bytes must pass the source's continuous health tests (NIST SP 800-90B);
+    /// a failing source is quarantined and its data refused until
+    /// [`release_source`](Self::release_source) is called.
+    pub fn push_entropy_from(&mut self, source: &str, bytes: &[u8]) -> Result<(), EntropyError> {
+        // Validate buffer size (minimum 32 bytes for health tests)
         if bytes.len() < 32 {
             return Err(EntropyError::InvalidBufferSize { size: bytes.len() });
         }
         
-        // Validate entropy quality before accepting
-        if !validate_entropy_quality(bytes) {
-            return Err(EntropyError::InvalidQuality);
+        // Run the source's health tests before accepting
+        let config = self.health_config;
+        let monitor = self
+            .health
+            .entry(source.to_string())
+            .or_insert_with(|| HealthMonitor::new(config));
+        match monitor.check(bytes) {
+            Ok(HealthStatus::Healthy) => {}
+            Ok(_) => {
+                debug!("Entropy source {} still in startup, discarding {} bytes", source, bytes.len());
+                return Ok(());
+            }
+            Err(failure) => {
+                warn!("Entropy from {} rejected: {}", source, failure);
+                return Err(EntropyError::HealthTest(failure));
+            }
         }
         
         // Additional integrity check: compute and verify hash
@@ -249,6 +186,21 @@ impl TrueVernamBuffer {
     pub fn stats(&self) -> (u64, u64) {
         (self.bytes_consumed, self.bytes_fetched)
     }
+
+    /// Health test metrics per source
+    pub fn health_metrics(&self) -> Vec<(String, HealthMetrics)> {
+        self.health
+            .iter()
+            .map(|(source, monitor)| (source.clone(), monitor.metrics()))
+            .collect()
+    }
+
+    /// Lift a source's quarantine; it has to pass startup again
+    pub fn release_source(&mut self, source: &str) {
+        if let Some(monitor) = self.health.get_mut(source) {
+            monitor.release();
+        }
+    }
 }
 
 impl Drop for TrueVernamBuffer {
@@ -277,19 +229,25 @@ impl Default for TrueVernamBuffer {
 ///
 /// Information-Theoretic Formula: combined_entropy = local_random XOR worker_entropy XOR peer1 XOR peer2 XOR ...
 /// This provides TRUE unbreakable security as long as at least ONE entropy source remains uncompromised.
+#[cfg(feature = "fetcher")]
 pub struct TrueVernamFetcher {
     vernam_url: String,
     buffer: Arc<Mutex<TrueVernamBuffer>>,
     /// Swarm seed from peer entropy collection (if available)
     swarm_seed: Option<[u8; 32]>,
+    /// Sources and their health state, kept across fetches
+    combiner: EntropyCombiner,
 }
 
+#[cfg(feature = "fetcher")]
 impl TrueVernamFetcher {
     pub fn new(vernam_url: String, buffer: Arc<Mutex<TrueVernamBuffer>>) -> Self {
+        let combiner = Self::build_combiner(&vernam_url, None);
         Self {
             vernam_url,
             buffer,
             swarm_seed: None,
+            combiner,
         }
     }
 
@@ -297,9 +255,28 @@ impl TrueVernamFetcher {
     /// This makes the entropy generation trustless!
     pub fn set_swarm_seed(&mut self, seed: [u8; 32]) {
         self.swarm_seed = Some(seed);
+        self.combiner = Self::build_combiner(&self.vernam_url, self.swarm_seed);
         info!("🔗 True Vernam: Swarm seed set - TRUSTLESS mode activated!");
     }
 
+    /// Health test metrics of each entropy source
+    pub fn health_metrics(&self) -> Vec<(String, HealthMetrics)> {
+        self.combiner.health_metrics()
+    }
+
+    /// OPTIMIZATION: When a swarm seed is set (trustless mode), we skip Worker calls
+    /// entirely to save API costs. Local CSPRNG + swarm is already cryptographically
+    /// secure and completely trustless.
+    fn build_combiner(vernam_url: &str, swarm_seed: Option<[u8; 32]>) -> EntropyCombiner {
+        let combiner = EntropyCombiner::new().with_source(Arc::new(OsEntropy));
+        match swarm_seed {
+            // SWARM MODE: no Worker call needed (cost optimization)
+            Some(seed) => combiner.with_source(Arc::new(FixedEntropy::new("swarm", seed))),
+            // NON-SWARM MODE: Cloudflare Worker (needs external trust)
+            None => combiner.with_source(Arc::new(WorkerEntropy::new(vernam_url.to_string()))),
+        }
+    }
+
     /// Start the background fetching task
     pub fn start_background_task(self) {
         tokio::spawn(async move {
@@ -336,72 +313,20 @@ impl TrueVernamFetcher {
     ///
     /// Security: Even if worker is compromised, local + swarm entropy protects you.
     /// Even if your device is compromised, worker + swarm entropy protects you.
-    /// 
-    /// INFORMATION-THEORETIC SECURITY: For messages ≤32 bytes, uses pure XOR combination
-    /// to achieve TRUE unbreakable encryption (not just computationally secure).
-    /// For larger messages, falls back to SHA256-based mixing for practical key expansion.
-    /// 
-    /// OPTIMIZATION: When swarm_seed is set (trustless mode), we skip Worker calls
-    /// entirely to save API costs. Local CSPRNG + swarm is already cryptographically
-    /// secure and completely trustless.
+    ///
+    /// The sources go through an [`EntropyCombiner`], so a failed or unhealthy
+    /// source is recorded and the remaining sources still produce output.
     async fn fetch_hybrid_entropy(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // 1. Local CSPRNG entropy (always available, you trust your device)
-        let mut local_entropy = [0u8; 32];
-        getrandom::getrandom(&mut local_entropy)
-            .map_err(|e| format!("CSPRNG unavailable: {}", e))?;
-
-        // 2. Worker entropy - SKIP if swarm_seed is set (already trustless!)
-        // This saves Cloudflare Worker API costs when running in swarm mode.
-        let worker_entropy = if self.swarm_seed.is_some() {
-            // SWARM MODE: Use additional local entropy instead of Worker
-            // Security is maintained: local CSPRNG + swarm_seed = fully trustless
-            debug!("🚀 Swarm mode: skipping Worker entropy (cost optimization)");
-            let mut extra_local = [0u8; 32];
-            getrandom::getrandom(&mut extra_local)
-                .map_err(|e| format!("CSPRNG unavailable: {}", e))?;
-            extra_local.to_vec()
-        } else {
-            // NON-SWARM MODE: Fetch from Cloudflare Worker (needs external trust)
-            self.fetch_worker_entropy().await.unwrap_or_else(|e| {
-                warn!(
-                    "Worker entropy fetch failed: {}, using additional local randomness",
-                    e
-                );
-                // Fallback: generate MORE local entropy (not zeros!)
-                let mut fallback = [0u8; 32];
-                match getrandom::getrandom(&mut fallback) {
-                    Ok(_) => fallback.to_vec(),
-                    Err(e) => {
-                        tracing::error!("🚨 CRITICAL: Both CSPRNG and Worker entropy failed. Cannot proceed securely.");
-                        panic!("Entropy generation failed: {}. System cannot operate without secure randomness.", e);
-                    }
-                }
-            })
-        };
-
-        // 3. INFORMATION-THEORETIC SECURITY: Pure XOR combination for TRUE unbreakable entropy
-        // This achieves information-theoretic security for messages ≤32 bytes
-        let mut combined = [0u8; 32];
-        
-        // XOR all entropy sources together (no hashing for information-theoretic security)
-        for i in 0..32 {
-            combined[i] = local_entropy[i] ^ worker_entropy[i];
-        }
-        
-        // Add swarm seed if available (TRUSTLESS - even if worker is evil)
-        if let Some(swarm_seed) = &self.swarm_seed {
-            for i in 0..32 {
-                combined[i] ^= swarm_seed[i];
-            }
-            debug!("🔗 Hybrid entropy: local XOR local2 XOR swarm (INFORMATION-THEORETIC)");
-        } else {
-            debug!("⚠️ Hybrid entropy: local XOR worker (information-theoretic)");
+        let combined = self.combiner.combine().await?;
+        for (name, e) in combined.provenance().failed() {
+            warn!("{} entropy fetch failed: {}, continuing with remaining sources", name, e);
         }
+        debug!("🔗 Hybrid entropy from {:?}", combined.provenance().contributed());
 
         // Add to buffer
         {
             let mut buffer = self.buffer.lock().await;
-            if let Err(e) = buffer.push_entropy(&combined) {
+            if let Err(e) = buffer.push_entropy_from("hybrid", combined.entropy()) {
                 warn!("Failed to add entropy to buffer: {}", e);
                 return Err(e.into());
             }
@@ -409,8 +334,22 @@ impl TrueVernamFetcher {
 
         Ok(())
     }
+}
+
+/// Cloudflare Worker entropy endpoint (Cloudflare's hardware RNG)
+#[cfg(feature = "fetcher")]
+pub struct WorkerEntropy {
+    vernam_url: String,
+}
+
+#[cfg(feature = "fetcher")]
+impl WorkerEntropy {
+    /// Create a source for the worker at `vernam_url`
+    pub fn new(vernam_url: String) -> Self {
+        Self { vernam_url }
+    }
 
-    /// Fetch entropy from worker (Cloudflare's hardware RNG)
+    /// Fetch entropy from worker
     async fn fetch_worker_entropy(
         &self,
     ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
@@ -449,23 +388,48 @@ impl TrueVernamFetcher {
     }
 }
 
+#[cfg(feature = "fetcher")]
+#[async_trait::async_trait]
+impl EntropySource for WorkerEntropy {
+    fn name(&self) -> &str {
+        "worker"
+    }
+
+    async fn fetch(&self) -> Result<[u8; 32], EntropySourceError> {
+        let entropy = self
+            .fetch_worker_entropy()
+            .await
+            .map_err(|e| EntropySourceError::Unavailable(e.to_string()))?;
+        let sample: [u8; 32] = entropy
+            .get(..32)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| EntropySourceError::Invalid(format!("Expected 32 bytes, got {}", entropy.len())))?;
+        Ok(sample)
+    }
+}
+
 /// Errors that can occur during entropy operations
 #[derive(Debug, Clone)]
 pub enum EntropyError {
-    /// Entropy quality validation failed
-    InvalidQuality,
+    /// Source failed its continuous health tests
+    HealthTest(HealthFailure),
     /// Buffer is empty (no entropy available)
     BufferEmpty,
     /// Requested more bytes than available in buffer
     InsufficientEntropy { requested: usize, available: usize },
     /// Invalid buffer size (must be at least 32 bytes)
     InvalidBufferSize { size: usize },
+    /// Keystream range overlaps one already used, or lies in the wrong lane
+    KeystreamReuse {
+        /// Start of the rejected range
+        position: u64,
+    },
 }
 
 impl std::fmt::Display for EntropyError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            EntropyError::InvalidQuality => write!(f, "Entropy quality validation failed - data does not appear to be truly random"),
+            EntropyError::HealthTest(failure) => write!(f, "Entropy health test failed: {}", failure),
             EntropyError::BufferEmpty => write!(f, "Buffer is empty - no entropy available for consumption"),
             EntropyError::InsufficientEntropy { requested, available } => {
                 write!(f, "Insufficient entropy: requested {} bytes but only {} available", requested, available)
@@ -473,6 +437,9 @@ impl std::fmt::Display for EntropyError {
             EntropyError::InvalidBufferSize { size } => {
                 write!(f, "Invalid buffer size: {} bytes (minimum 32 bytes required)", size)
             }
+            EntropyError::KeystreamReuse { position } => {
+                write!(f, "Keystream at position {} was already used", position)
+            }
         }
     }
 }
@@ -483,9 +450,19 @@ impl std::error::Error for EntropyError {}
 mod tests {
     use super::*;
 
+    /// Buffer whose default source has passed startup
+    fn started_buffer() -> TrueVernamBuffer {
+        let mut buffer = TrueVernamBuffer::new();
+        let mut block = [0u8; 32];
+        getrandom::getrandom(&mut block).unwrap();
+        buffer.push_entropy(&block).unwrap();
+        assert_eq!(buffer.available(), 0, "Startup block must be dropped");
+        buffer
+    }
+
     #[test]
     fn test_buffer_consume_removes_bytes() {
-        let mut buffer = TrueVernamBuffer::new();
+        let mut buffer = started_buffer();
 
         // Add some entropy (use high-quality random data)
         let entropy: Vec<u8> = vec![
@@ -519,7 +496,7 @@ mod tests {
 
     #[test]
     fn test_bytes_never_reused() {
-        let mut buffer = TrueVernamBuffer::new();
+        let mut buffer = started_buffer();
 
         // Add entropy (use varied data to pass quality checks)
         // Use a pattern that should definitely pass quality checks
@@ -565,6 +542,39 @@ mod tests {
         let repeated_pattern = vec![0xAB; 50]; // All the same byte - 50 bytes total
         assert!(buffer.push_entropy(&repeated_pattern).is_err());
     }
+
+    #[test]
+    fn test_failing_source_is_quarantined_alone() {
+        let mut buffer = TrueVernamBuffer::new();
+        let mut good = [0u8; 64];
+        getrandom::getrandom(&mut good).unwrap();
+
+        assert!(matches!(
+            buffer.push_entropy_from("stuck", &[0x42; 32]),
+            Err(EntropyError::HealthTest(HealthFailure::RepetitionCount { .. }))
+        ));
+        assert!(matches!(
+            buffer.push_entropy_from("stuck", &good),
+            Err(EntropyError::HealthTest(HealthFailure::Quarantined))
+        ));
+        // The first block only completes startup
+        buffer.push_entropy_from("os", &good).unwrap();
+        assert_eq!(buffer.available(), 0);
+        getrandom::getrandom(&mut good).unwrap();
+        buffer.push_entropy_from("os", &good).unwrap();
+        assert_eq!(buffer.available(), 64);
+
+        let metrics: HashMap<_, _> = buffer.health_metrics().into_iter().collect();
+        assert_eq!(metrics["stuck"].status, HealthStatus::Quarantined);
+        assert_eq!(metrics["os"].status, HealthStatus::Healthy);
+
+        buffer.release_source("stuck");
+        for _ in 0..2 {
+            getrandom::getrandom(&mut good).unwrap();
+            buffer.push_entropy_from("stuck", &good).unwrap();
+        }
+        assert_eq!(buffer.available(), 128);
+    }
 }
 
 /// Synchronized Vernam Buffer: TRUE Information-Theoretic OTP Implementation
@@ -580,19 +590,23 @@ mod tests {
 /// - No key transmission required - both parties generate identical keystreams
 use std::sync::atomic::{AtomicU64, Ordering};
 
+pub use crate::keystream::{KeystreamTracker, BOB_LANE_START, MAX_TRACKED_RANGES};
+
 /// Synchronized deterministic keystream generator for TRUE OTP
 /// 
 /// ⚠️ CRITICAL SECURITY REQUIREMENTS:// 
-/// 1. SYNCHRONIZATION RISK: Both parties MUST consume keystream in EXACT same order.
-///    - Lost messages → Desynchronization → Decryption failure
-///    - Reordered messages → Desynchronization → Decryption failure  
-///    - Out-of-order processing → Desynchronization → Decryption failure
+/// 1. POSITIONS: The keystream is random-access by absolute position.
+///    - [`reserve`](Self::reserve) hands out fresh positions to the sender, which
+///      records them in the authenticated envelope
+///    - The receiver calls [`claim_received`](Self::claim_received) and then
+///      [`keystream_at`](Self::keystream_at), so lost or reordered messages still decrypt
+///    - A [`KeystreamTracker`] refuses any range that overlaps one already used
 /// 
-/// 2. RECOVERY MECHANISMS:
-///    - Option A: Include position in message header (e.g., "position: 1234")
-///    - Option B: Use sequence numbers with acknowledgments
-///    - Option C: Implement re-synchronization protocol on failure
-///    - Option D: Use reliable transport (TCP, QUIC) instead of UDP
+/// 2. DIRECTIONS: Buffers built with [`with_role`](Self::with_role) give each side its
+///    own lane, so both can send at once. Buffers from [`new`](Self::new) share one
+///    space: concurrent sends collide and are refused rather than reusing keystream.
+///    - [`consume_sync`](Self::consume_sync) keeps the old counter-driven behaviour,
+///      where both parties MUST consume in the EXACT same order
 /// 
 /// 3. OPERATIONAL CONSIDERATIONS:
 ///    - Monitor for repeated decryption failures (indicates desync)
@@ -614,6 +628,10 @@ pub struct SynchronizedVernamBuffer {
     starting_round: u64,
     /// Drand client for fetching entropy rounds
     drand_client: Arc<crate::drand::DrandEntropy>,
+    /// Our role when each side sends in its own lane
+    is_alice: Option<bool>,
+    /// Ranges already used, sent or received
+    tracker: std::sync::Mutex<KeystreamTracker>,
 }
 
 impl SynchronizedVernamBuffer {
@@ -628,9 +646,25 @@ impl SynchronizedVernamBuffer {
             position_counter: AtomicU64::new(0),
             starting_round,
             drand_client,
+            is_alice: None,
+            tracker: std::sync::Mutex::new(KeystreamTracker::new()),
         }
     }
 
+    /// Create a synchronized buffer whose drand rounds start after an agreed time
+    ///
+    /// The starting round is the first one produced after `agreed_at` on the client's
+    /// chain, so both parties derive it independently from the same time (e.g. the
+    /// handshake timestamp) and neither could know its randomness in advance.
+    pub fn new_with_drand_at(
+        shared_seed: [u8; 32],
+        agreed_at: std::time::SystemTime,
+        drand_client: Arc<crate::drand::DrandEntropy>,
+    ) -> Self {
+        let starting_round = drand_client.chain_info().round_at(agreed_at) + 1;
+        Self::new_with_drand(shared_seed, starting_round, drand_client)
+    }
+
     /// Create a new synchronized buffer with just a shared seed (legacy compatibility)
     /// 
     /// This creates a buffer without drand client, falling back to ChaCha20 for all messages.
@@ -642,55 +676,89 @@ impl SynchronizedVernamBuffer {
             position_counter: AtomicU64::new(0),
             starting_round: 0, // No drand rounds available
             drand_client,
+            is_alice: None,
+            tracker: std::sync::Mutex::new(KeystreamTracker::new()),
+        }
+    }
+
+    /// Create a buffer where each party sends in its own keystream lane
+    ///
+    /// Alice sends below [`BOB_LANE_START`] and Bob from it upwards, so the two
+    /// directions can never use the same keystream.
+    pub fn with_role(shared_seed: [u8; 32], is_alice: bool) -> Self {
+        let mut buffer = Self::new(shared_seed);
+        buffer.is_alice = Some(is_alice);
+        if !is_alice {
+            buffer.position_counter.store(BOB_LANE_START, Ordering::SeqCst);
         }
+        buffer
     }
     
     /// Create shared seed from multiple entropy sources (information-theoretic)
     /// 
-    /// This combines entropy sources using XOR for information-theoretic security.
-    /// The result is unbreakable if ANY source is truly random.
+    /// Same XOR combination as
+    /// [`WasifVernam::create_shared_seed`](crate::wasif_vernam::WasifVernam::create_shared_seed),
+    /// which explains why it does not go through the [`EntropyCombiner`].
     pub fn create_shared_seed(
         mlkem_secret: [u8; 32],
         drand_entropy: [u8; 32],
         peer_contributions: [u8; 32],
     ) -> [u8; 32] {
-        let mut shared_seed = [0u8; 32];
-        
-        // Information-theoretic XOR combination: secure if any source is random
-        for i in 0..32 {
-            shared_seed[i] = mlkem_secret[i] ^ drand_entropy[i] ^ peer_contributions[i];
-        }
-        
-        debug!("🔑 Created information-theoretic shared seed (secure if any source is random)");
-        shared_seed
+        crate::wasif_vernam::WasifVernam::create_shared_seed(mlkem_secret, drand_entropy, peer_contributions)
     }
     
     /// Generate TRUE information-theoretic keystream for small messages (≤32 bytes)
     /// 
     /// This fetches drand rounds directly for true OTP security.
     /// Both parties must fetch the same rounds to generate identical keystreams.
+    /// Rounds not yet published fail immediately instead of being waited for.
     async fn generate_true_otp_keystream(&self, position: u64, length: usize) -> Result<Vec<u8>, crate::drand::DrandError> {
         if length > 32 {
             return Err(crate::drand::DrandError::NetworkError("True OTP limited to 32 bytes".to_string()));
         }
         
-        // Calculate which drand round to fetch based on position
-        let round_number = self.starting_round + position / 32;
-        
-        // Fetch the drand entropy for this round
-        let drand_entropy = self.drand_client.fetch_round(round_number).await?;
-        
-        // Extract the specific bytes needed from this round
-        let start_byte = (position % 32) as usize;
-        let end_byte = std::cmp::min(start_byte + length, 32);
-        
-        let mut keystream = vec![0u8; length];
-        keystream.copy_from_slice(&drand_entropy[start_byte..end_byte]);
-        
-        debug!("🔑 Generated TRUE OTP keystream: {} bytes from drand round {} (info-theoretic)", length, round_number);
+        let mut keystream = Vec::with_capacity(length);
+        for (round_number, bytes) in self.round_slices(position, length) {
+            // Never wait for a future round: this runs on the encrypt/decrypt path
+            let latest_round = self.drand_client.chain_info().round_at(std::time::SystemTime::now());
+            if round_number > latest_round {
+                return Err(crate::drand::DrandError::ApiError(format!(
+                    "drand round {} not published yet (latest {})",
+                    round_number, latest_round
+                )));
+            }
+            let drand_entropy = self.drand_client.fetch_round(round_number).await?;
+            keystream.extend_from_slice(&drand_entropy[bytes]);
+        }
+
+        debug!("🔑 Generated TRUE OTP keystream: {} bytes from drand (info-theoretic)", length);
         Ok(keystream)
     }
 
+    /// drand rounds and byte ranges covering `length` bytes of keystream at `position`
+    ///
+    /// Each round supplies 32 bytes, so a message may span two rounds. With lanes,
+    /// Alice takes the even rounds and Bob the odd ones, each counted from the start
+    /// of its own lane, so both directions stay near `starting_round` without sharing.
+    fn round_slices(&self, position: u64, length: usize) -> Vec<(u64, core::ops::Range<usize>)> {
+        let mut slices = Vec::new();
+        let mut position = position;
+        let mut remaining = length;
+        while remaining > 0 {
+            let round_index = match self.is_alice {
+                Some(_) if position >= BOB_LANE_START => (position - BOB_LANE_START) / 32 * 2 + 1,
+                Some(_) => position / 32 * 2,
+                None => position / 32,
+            };
+            let start = (position % 32) as usize;
+            let take = remaining.min(32 - start);
+            slices.push((self.starting_round.saturating_add(round_index), start..start + take));
+            position = position.saturating_add(take as u64);
+            remaining -= take;
+        }
+        slices
+    }
+
     /// Generate keystream for a specific position (deterministic PRG)
     /// 
     /// For ≤32 bytes: Uses TRUE drand entropy (information-theoretic)
@@ -717,7 +785,7 @@ impl SynchronizedVernamBuffer {
         nonce_bytes[4..12].copy_from_slice(&position.to_be_bytes());
         
         // Create ChaCha20 cipher with shared seed and position nonce
-        let mut cipher = ChaCha20::new(self.shared_seed.as_ref().into(), nonce_bytes.as_ref().into());
+        let mut cipher = ChaCha20::new(&self.shared_seed.into(), &nonce_bytes.into());
         cipher.apply_keystream(&mut keystream);
         
         debug!("🔑 Generated {} bytes at position {} (computational ChaCha20)", length, position);
@@ -752,7 +820,57 @@ impl SynchronizedVernamBuffer {
     /// Note: This blocks the current thread. Use `consume()` for async version.
     pub fn consume_sync(&self, length: usize) -> Vec<u8> {
         let position = self.position_counter.fetch_add(length as u64, Ordering::SeqCst);
-        
+        self.keystream_at(position, length)
+    }
+
+    /// Reserve `length` fresh bytes of keystream for an outgoing message
+    ///
+    /// Returns the position to record in the envelope.
+    pub fn reserve(&self, length: usize) -> Result<u64, EntropyError> {
+        let position = self.position_counter.fetch_add(length as u64, Ordering::SeqCst);
+        if !self.lock_tracker().claim(position, length) {
+            warn!("🚨 Keystream at position {} already used - refusing to reuse it", position);
+            return Err(EntropyError::KeystreamReuse { position });
+        }
+        Ok(position)
+    }
+
+    /// Check and record the keystream range of an incoming message
+    ///
+    /// Fails if the range lies outside the peer's lane or overlaps any range
+    /// already sent or received.
+    pub fn claim_received(&self, position: u64, length: usize) -> Result<(), EntropyError> {
+        let end = position.saturating_add(length as u64);
+        let in_peer_lane = match self.is_alice {
+            Some(true) => position >= BOB_LANE_START,
+            Some(false) => end <= BOB_LANE_START,
+            None => true,
+        };
+        if !in_peer_lane || !self.lock_tracker().claim(position, length) {
+            warn!("🚨 Received keystream range at position {} overlaps one already used", position);
+            return Err(EntropyError::KeystreamReuse { position });
+        }
+        if self.is_alice.is_none() {
+            // Shared space: our next send starts past everything the peer used
+            self.position_counter.fetch_max(end, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    fn lock_tracker(&self) -> std::sync::MutexGuard<'_, KeystreamTracker> {
+        self.tracker.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Generate keystream at an absolute position without moving the counter
+    /// 
+    /// For ≤32 bytes: TRUE information-theoretic security (drand entropy)
+    /// For >32 bytes: Computational security (ChaCha20 expansion)
+    ///
+    /// This never sleeps for a drand round: if a round is not published yet or
+    /// cannot be fetched, the ChaCha20 keystream is used instead. Both parties then
+    /// only agree if they see the same rounds, so start sending once
+    /// `starting_round` has been produced.
+    pub fn keystream_at(&self, position: u64, length: usize) -> Vec<u8> {
         // For small messages (≤32 bytes), use TRUE information-theoretic security
         if length <= 32 && self.starting_round > 0 {
             // Try to get the current runtime handle
@@ -781,7 +899,7 @@ impl SynchronizedVernamBuffer {
         let mut keystream = vec![0u8; length];
         let mut nonce_bytes = [0u8; 12];
         nonce_bytes[4..12].copy_from_slice(&position.to_be_bytes());
-        let mut cipher = ChaCha20::new(self.shared_seed.as_ref().into(), nonce_bytes.as_ref().into());
+        let mut cipher = ChaCha20::new(&self.shared_seed.into(), &nonce_bytes.into());
         cipher.apply_keystream(&mut keystream);
         keystream
     }
@@ -791,6 +909,37 @@ impl SynchronizedVernamBuffer {
         self.position_counter.load(Ordering::SeqCst)
     }
     
+    /// drand client the TRUE OTP rounds are fetched from
+    pub fn drand_client(&self) -> &Arc<crate::drand::DrandEntropy> {
+        &self.drand_client
+    }
+
+    /// Shared seed, starting drand round and lane role (for persistence)
+    pub(crate) fn seed_round_and_role(&self) -> ([u8; 32], u64, Option<bool>) {
+        (self.shared_seed, self.starting_round, self.is_alice)
+    }
+
+    /// Recreate a buffer at a persisted position
+    ///
+    /// Used ranges are not persisted; replayed envelopes are caught by the
+    /// restored anti-replay window instead.
+    pub(crate) fn resume(
+        shared_seed: [u8; 32],
+        starting_round: u64,
+        is_alice: Option<bool>,
+        position: u64,
+        drand_client: Arc<crate::drand::DrandEntropy>,
+    ) -> Self {
+        Self {
+            shared_seed,
+            position_counter: AtomicU64::new(position),
+            starting_round,
+            drand_client,
+            is_alice,
+            tracker: std::sync::Mutex::new(KeystreamTracker::new()),
+        }
+    }
+
     /// Reset position counter (use with extreme caution - breaks synchronization!)
     pub fn reset_position(&self, new_position: u64) {
         self.position_counter.store(new_position, Ordering::SeqCst);
@@ -852,6 +1001,64 @@ mod synchronized_tests {
         assert_eq!(alice_keystream, bob_keystream); // Identical keystreams
     }
     
+    #[test]
+    fn test_positions_survive_loss_and_reordering() {
+        let seed = [0x5A; 32];
+        let alice = SynchronizedVernamBuffer::with_role(seed, true);
+        let bob = SynchronizedVernamBuffer::with_role(seed, false);
+
+        let first = alice.reserve(40).unwrap();
+        let second = alice.reserve(40).unwrap();
+        let third = alice.reserve(40).unwrap();
+        let expected = alice.keystream_at(third, 40);
+
+        // The first datagram is lost and the other two arrive swapped
+        bob.claim_received(third, 40).unwrap();
+        assert_eq!(bob.keystream_at(third, 40), expected);
+        bob.claim_received(second, 40).unwrap();
+        assert!(bob.claim_received(second + 8, 40).is_err(), "Overlap must be refused");
+        assert!(bob.claim_received(first, 40).is_ok());
+
+        // Bob sends in his own lane; Alice refuses ranges from her own lane
+        let reply = bob.reserve(16).unwrap();
+        assert!(reply >= BOB_LANE_START);
+        assert!(alice.claim_received(reply, 16).is_ok());
+        assert!(alice.claim_received(first, 40).is_err());
+    }
+
+    #[test]
+    fn test_shared_space_refuses_colliding_sends() {
+        let seed = [0x3C; 32];
+        let alice = SynchronizedVernamBuffer::new(seed);
+        let bob = SynchronizedVernamBuffer::new(seed);
+
+        // Both send at position 0: the second to arrive is refused
+        let from_alice = alice.reserve(10).unwrap();
+        let from_bob = bob.reserve(10).unwrap();
+        assert_eq!(from_alice, from_bob);
+        assert!(bob.claim_received(from_alice, 10).is_err());
+
+        // After receiving, the next send starts past the peer's range
+        let later = alice.reserve(10).unwrap();
+        bob.claim_received(later, 10).unwrap();
+        assert!(bob.reserve(10).unwrap() >= later + 10);
+    }
+
+    #[test]
+    fn test_starting_round_from_agreed_time() {
+        use crate::drand::{DrandConfig, DrandEntropy};
+
+        let client = Arc::new(DrandEntropy::with_config(DrandConfig::quicknet()));
+        let chain = client.chain_info().clone();
+        let agreed_at = chain.time_of_round(5000) + std::time::Duration::from_secs(1);
+
+        let alice = SynchronizedVernamBuffer::new_with_drand_at([0x42; 32], agreed_at, client.clone());
+        let bob = SynchronizedVernamBuffer::new_with_drand_at([0x42; 32], agreed_at, client);
+        assert_eq!(alice.starting_round, 5001);
+        assert_eq!(alice.starting_round, bob.starting_round);
+        assert!(chain.time_of_round(alice.starting_round) > agreed_at);
+    }
+
     #[tokio::test]
     async fn test_true_otp_generation() {
         // Mock drand client for testing
@@ -970,4 +1177,44 @@ mod synchronized_tests {
         // Should be different due to position mismatch
         assert_ne!(alice_keystream2, bob_keystream2);
     }
+
+    #[test]
+    fn test_round_slices_span_round_boundary() {
+        use crate::drand::DrandEntropy;
+
+        let buffer = SynchronizedVernamBuffer::new_with_drand([0x42; 32], 1000, Arc::new(DrandEntropy::new()));
+
+        // 20 bytes at position 20 take the last 12 bytes of one round and 8 of the next
+        assert_eq!(buffer.round_slices(20, 20), vec![(1000, 20..32), (1001, 0..8)]);
+        assert_eq!(buffer.round_slices(64, 32), vec![(1002, 0..32)]);
+    }
+
+    #[test]
+    fn test_round_slices_per_lane() {
+        use crate::drand::DrandEntropy;
+
+        let drand_client = Arc::new(DrandEntropy::new());
+        let alice = SynchronizedVernamBuffer::resume([0x42; 32], 1000, Some(true), 0, drand_client.clone());
+        let bob = SynchronizedVernamBuffer::resume([0x42; 32], 1000, Some(false), BOB_LANE_START, drand_client);
+
+        // Bob's lane maps next to the starting round, interleaved with Alice's
+        assert_eq!(alice.round_slices(0, 32), vec![(1000, 0..32)]);
+        assert_eq!(alice.round_slices(32, 32), vec![(1002, 0..32)]);
+        assert_eq!(bob.round_slices(BOB_LANE_START, 32), vec![(1001, 0..32)]);
+        assert_eq!(bob.round_slices(BOB_LANE_START + 20, 20), vec![(1001, 20..32), (1003, 0..8)]);
+    }
+
+    #[tokio::test]
+    async fn test_future_round_fails_without_waiting() {
+        use crate::drand::DrandEntropy;
+
+        let drand_client = Arc::new(DrandEntropy::new());
+        let future_round = drand_client.chain_info().round_at(std::time::SystemTime::now()) + 100;
+        let buffer = SynchronizedVernamBuffer::new_with_drand([0x42; 32], future_round, drand_client);
+
+        let started = std::time::Instant::now();
+        let err = buffer.generate_true_otp_keystream(0, 16).await.unwrap_err();
+        assert!(err.to_string().contains("not published yet"), "unexpected error: {}", err);
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
 }
\ No newline at end of file
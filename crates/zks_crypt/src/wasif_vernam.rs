@@ -3,28 +3,66 @@
 //! This module implements the Wasif Vernam cipher, a quantum-resistant encryption scheme
 //! that combines multiple layers of security:
 //! 
-//! 1. Base Layer: ChaCha20-Poly1305 for authenticated encryption
-//! 2. XOR Layer: HKDF-derived keystream or TRUE Vernam random data
+//! 1. Base Layer: AEAD (ChaCha20-Poly1305 by default, see [`crate::aead_backend`])
+//! 2. XOR Layer: seekable ChaCha20 keystream (see [`crate::keystream`]), TRUE Vernam
+//!    random data or a pre-shared one-time pad (see [`crate::pad`])
 //! 3. Optional: Ciphertext scrambling for traffic analysis resistance
 //! 4. Optional: Recursive key chain for forward secrecy
-
-use chacha20poly1305::{
-    aead::{Aead, Error as AeadError},
-    ChaCha20Poly1305, KeyInit, Nonce,
-};
+//! 5. Optional: Key commitment (see [`crate::commitment`]), always on for password-derived
+//!    and multi-recipient keys
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::{vec, vec::Vec};
+use bytes::{Buf, BytesMut};
+use chacha20poly1305::aead::Error as AeadError;
+use core::sync::atomic::{AtomicU32, Ordering};
 use hkdf::Hkdf;
 use sha2::{Digest, Sha256};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+#[cfg(feature = "std")]
 use tokio::sync::Mutex;
 use zeroize::{Zeroize, Zeroizing};
-use crate::anti_replay::AntiReplayContainer;
-use crate::recursive_chain::RecursiveChain;
-use crate::scramble::CiphertextScrambler;
+use crate::aead_backend::{AeadAlgorithm, AeadBackend, XNONCE_LEN};
+use crate::anti_replay::{AntiReplayContainer, BitmapWindow, ReplayWindow, MAX_WINDOW_SIZE};
+#[cfg(feature = "std")]
+use crate::commitment;
+use crate::commitment::{CommitmentKey, COMMITMENT_LEN};
+use crate::keystream::{hkdf_keystream, VernamKeystream};
+use crate::envelope::{CipherMode, EnvelopeHeader, FLAG_COMMITTED, FLAG_KEY_UPDATE, FLAG_PADDED, FLAG_SCRAMBLED, LEGACY_ENVELOPE_VERSION};
+#[cfg(feature = "std")]
+use crate::pad::PadFile;
+use crate::padding::{self, PaddingPolicy, PADDING_PREFIX_LEN};
+use crate::pq_ratchet::{PqRatchet, PqRatchetPolicy};
+use crate::recursive_chain::{ChainState, RecursiveChain};
+use crate::rekey::{KeyUpdate, KeyUpdateKind, RekeyError, REKEY_GRACE_MESSAGES, REKEY_INTERVAL};
+use crate::rng::{self, FillRng};
+use crate::scramble::PacketScrambler;
+use crate::session::{SessionState, SESSION_NONCE_RESERVE, SESSION_OFFSET_RESERVE};
+#[cfg(feature = "drand")]
+use crate::session::SyncBufferState;
+use crate::sync::AtomicU64;
+#[cfg(feature = "drand")]
+use crate::drand::DrandEntropy;
+#[cfg(feature = "drand")]
 use crate::true_vernam::{TrueVernamBuffer, SynchronizedVernamBuffer};
+#[cfg(feature = "std")]
 use std::time::Duration;
+#[cfg(feature = "std")]
 use tokio::time::interval;
 use tracing::{debug, error, info, warn};
+use zks_pqcrypto::{MlKem, MlKemEncapsulation, MlKemKeypair};
+use zks_types::crypto::CryptoParameters;
+
+pub use crate::envelope::ENVELOPE_HEADER_LEN;
+
+/// Length of the Poly1305 authentication tag
+pub const TAG_LEN: usize = 16;
+
+/// Total bytes [`WasifVernam::encrypt`] adds to a plaintext with a 96-bit nonce AEAD
+///
+/// XChaCha20-Poly1305 envelopes carry an extra [`XNONCE_LEN`] bytes; see
+/// [`WasifVernam::envelope_overhead`].
+pub const ENVELOPE_OVERHEAD: usize = ENVELOPE_HEADER_LEN + TAG_LEN;
 
 /// The main Wasif Vernam cipher implementation
 /// 
@@ -33,46 +71,223 @@ use tracing::{debug, error, info, warn};
 /// 
 /// Security Modes:
 /// - Mode 0x01: TRUE OTP via SynchronizedVernamBuffer (information-theoretic, unbreakable)
-/// - Mode 0x02: HKDF-based XOR (computational, 256-bit security)
+/// - Mode 0x02: HKDF-based XOR (legacy, decryption only)
+/// - Mode 0x03: Pre-shared one-time pad file (information-theoretic for any length)
+/// - Mode 0x04: ChaCha20 keystream XOR (computational, 256-bit security)
+/// 
+/// The mode, key epoch and flags travel in a versioned [`EnvelopeHeader`], so the
+/// receiver never infers the XOR layer from the keystream offset.
+/// 
+/// With [`enable_key_chain`](Self::enable_key_chain) both peers can move to a new key
+/// epoch in-band, see [`crate::rekey`].
 /// 
 /// For TRUE OTP: Both parties must derive the same shared seed during handshake (e.g., from
 /// ML-KEM shared secret + drand entropy + peer contributions). The keystream is generated
 /// deterministically from seed + position - NO key transmission required!
 pub struct WasifVernam {
-    cipher: ChaCha20Poly1305,
+    cipher: AeadBackend,
+    /// Key behind `cipher`, kept for session snapshots
+    aead_key: Zeroizing<[u8; 32]>,
+    /// Commitment key derived from `aead_key`
+    commit_key: CommitmentKey,
+    /// Seal committed envelopes and refuse uncommitted ones
+    key_commitment: bool,
     nonce_counter: AtomicU64,
     anti_replay: Arc<AntiReplayContainer>,
     swarm_seed: Zeroizing<[u8; 32]>,
+    /// XOR keystream keyed from `swarm_seed`
+    keystream: VernamKeystream,
     key_offset: AtomicU64,
+    /// Key epoch, bumped whenever the AEAD key or swarm seed changes
+    key_epoch: AtomicU32,
+    /// Keys of the previous epoch, kept for a grace window
+    previous_epoch: Option<PreviousEpoch>,
+    /// Key update we initiated that awaits the peer's response
+    pending_rekey: Option<PendingRekey>,
     has_swarm_entropy: bool,
+    #[cfg(feature = "drand")]
     true_vernam_buffer: Option<Arc<Mutex<TrueVernamBuffer>>>,
     /// TRUE OTP: Synchronized keystream generator (no key transmission!)
+    #[cfg(feature = "drand")]
     synchronized_buffer: Option<Arc<SynchronizedVernamBuffer>>,
-    scrambler: Option<CiphertextScrambler>,
+    /// Pre-shared one-time pad, used for every message while set
+    #[cfg(feature = "std")]
+    pad: Option<Arc<PadFile>>,
+    /// Length-hiding padding applied by `encrypt` and `encrypt_in_place`
+    padding: PaddingPolicy,
+    /// Per-packet ciphertext scrambling, keyed by the AEAD key
+    scrambler: Option<PacketScrambler>,
     key_chain: Option<RecursiveChain>,
+    /// Schedule for ML-KEM steps within key updates
+    pq_ratchet: Option<PqRatchet>,
+    /// Sequence of the last session snapshot taken
+    snapshot_sequence: u64,
+    /// Counters covered by the last session snapshot
+    snapshot_reserve: Option<SnapshotReserve>,
+}
+
+/// Keys of the epoch before the current one
+struct PreviousEpoch {
+    epoch: u32,
+    cipher: AeadBackend,
+    commit_key: CommitmentKey,
+    swarm_seed: Zeroizing<[u8; 32]>,
+    keystream: VernamKeystream,
+    scrambler: Option<PacketScrambler>,
+    anti_replay: Arc<AntiReplayContainer>,
+    /// Current-epoch messages still allowed before these keys are dropped
+    grace_remaining: AtomicU32,
+}
+
+/// Nonce and keystream ranges reserved by the last session snapshot
+struct SnapshotReserve {
+    key_epoch: u32,
+    nonce_counter: u64,
+    key_offset: u64,
+    sync_position: Option<u64>,
+}
+
+/// Key update awaiting the peer's response
+struct PendingRekey {
+    epoch: u32,
+    /// Chain state before our contribution, restored if the peer's request wins
+    snapshot: ChainState,
+    /// ML-KEM secret key when the request carries a PQ ratchet step
+    kem_secret: Option<Zeroizing<Vec<u8>>>,
 }
 
 impl WasifVernam {
     /// Create a new Wasif Vernam cipher with the given key
     pub fn new(key: [u8; 32]) -> Result<Self, AeadError> {
-        let cipher = ChaCha20Poly1305::new_from_slice(&key)
-            .map_err(|_| chacha20poly1305::aead::Error)?;
+        Self::with_algorithm(key, AeadAlgorithm::default())
+    }
+
+    /// Create a cipher whose AEAD is selected from `params`
+    pub fn with_parameters(key: [u8; 32], params: &CryptoParameters) -> Result<Self, AeadError> {
+        Self::with_algorithm(key, params.into())
+    }
+
+    /// Create a cipher running the given AEAD backend
+    pub fn with_algorithm(key: [u8; 32], algorithm: AeadAlgorithm) -> Result<Self, AeadError> {
+        let cipher = AeadBackend::new(algorithm, &key)?;
         
         Ok(Self {
             cipher,
+            aead_key: Zeroizing::new(key),
+            commit_key: CommitmentKey::derive(&key),
+            key_commitment: false,
             nonce_counter: AtomicU64::new(0),
             anti_replay: Arc::new(AntiReplayContainer::new()),
             swarm_seed: Zeroizing::new([0u8; 32]),
+            keystream: VernamKeystream::new(&[0u8; 32]),
             key_offset: AtomicU64::new(0),
+            key_epoch: AtomicU32::new(0),
+            previous_epoch: None,
+            pending_rekey: None,
             has_swarm_entropy: false,
+            #[cfg(feature = "drand")]
             true_vernam_buffer: None,
+            #[cfg(feature = "drand")]
             synchronized_buffer: None,
+            #[cfg(feature = "std")]
+            pad: None,
+            padding: PaddingPolicy::None,
             scrambler: None,
             key_chain: None,
+            pq_ratchet: None,
+            snapshot_sequence: 0,
+            snapshot_reserve: None,
         })
     }
 
+    /// Create a committing cipher from a password
+    ///
+    /// The key comes from [`commitment::derive_password_key`]; `salt` must be at least
+    /// [`MIN_PASSWORD_SALT_LEN`](commitment::MIN_PASSWORD_SALT_LEN) bytes. Key commitment
+    /// is always on, so a receiver trying candidate passwords learns nothing from
+    /// envelopes crafted to open under several of them.
+    #[cfg(feature = "std")]
+    pub fn from_password(password: &[u8], salt: &[u8]) -> Result<Self, AeadError> {
+        let key = commitment::derive_password_key(password, salt).ok_or_else(|| {
+            warn!("Password salt must be at least {} bytes", commitment::MIN_PASSWORD_SALT_LEN);
+            AeadError
+        })?;
+        let mut cipher = Self::new(*key)?;
+        cipher.enable_key_commitment();
+        Ok(cipher)
+    }
+
+    /// Create a committing cipher for a key shared by more than two parties
+    ///
+    /// With a group key any member can seal, so key commitment is always on: no
+    /// member can craft an envelope that opens differently for different recipients.
+    pub fn new_multi_recipient(key: [u8; 32]) -> Result<Self, AeadError> {
+        let mut cipher = Self::new(key)?;
+        cipher.enable_key_commitment();
+        Ok(cipher)
+    }
+
+    /// Seal every envelope with a key commitment and refuse envelopes without one
+    ///
+    /// Adds [`COMMITMENT_LEN`] bytes per envelope. Both peers must enable it.
+    pub fn enable_key_commitment(&mut self) {
+        self.key_commitment = true;
+    }
+
+    /// Whether envelopes are sealed with, and required to carry, a key commitment
+    pub fn key_commitment(&self) -> bool {
+        self.key_commitment
+    }
+
+    /// Bytes following the ciphertext: the tag and, if enabled, the key commitment
+    fn trailer_len(&self) -> usize {
+        if self.key_commitment {
+            TAG_LEN + COMMITMENT_LEN
+        } else {
+            TAG_LEN
+        }
+    }
+
+    /// AEAD backend sealing the base layer
+    pub fn aead_algorithm(&self) -> AeadAlgorithm {
+        self.cipher.algorithm()
+    }
+
+    /// Envelope header length produced by this cipher
+    pub fn envelope_header_len(&self) -> usize {
+        EnvelopeHeader::encoded_len(self.cipher.algorithm())
+    }
+
+    /// Total bytes [`encrypt`](Self::encrypt) adds to a plaintext before padding
+    pub fn envelope_overhead(&self) -> usize {
+        self.envelope_header_len() + self.trailer_len()
+    }
+
+    /// Pad envelopes according to `policy` to hide message lengths
+    ///
+    /// Applies to [`encrypt`](Self::encrypt), [`encrypt_with_aad`](Self::encrypt_with_aad)
+    /// and [`encrypt_in_place`](Self::encrypt_in_place). The receiver needs no
+    /// configuration: padded envelopes are flagged and stripped automatically.
+    pub fn set_padding_policy(&mut self, policy: PaddingPolicy) {
+        self.padding = policy;
+    }
+
+    /// Current padding policy
+    pub fn padding_policy(&self) -> &PaddingPolicy {
+        &self.padding
+    }
+
+    /// Length of the envelope [`encrypt`](Self::encrypt) produces for `data_len` bytes
+    pub fn sealed_len(&self, data_len: usize) -> usize {
+        if self.padding.is_none() {
+            data_len + self.envelope_overhead()
+        } else {
+            self.padding.padded_len(data_len + PADDING_PREFIX_LEN + self.envelope_overhead())
+        }
+    }
+
     /// Enable TRUE Vernam mode with a buffer for random data
+    #[cfg(feature = "drand")]
     pub fn enable_true_vernam(&mut self, _buffer_size: usize) {
         let buffer = TrueVernamBuffer::new();
         self.true_vernam_buffer = Some(Arc::new(Mutex::new(buffer)));
@@ -86,12 +301,38 @@ impl WasifVernam {
     /// 
     /// # Arguments
     /// * `shared_seed` - 32-byte shared seed from create_shared_seed()
+    #[cfg(feature = "drand")]
     pub fn enable_synchronized_vernam(&mut self, shared_seed: [u8; 32]) {
         let sync_buffer = SynchronizedVernamBuffer::new(shared_seed);
         self.synchronized_buffer = Some(Arc::new(sync_buffer));
         info!("✅ Enabled TRUE synchronized Vernam mode (information-theoretic security)");
     }
 
+    /// Enable TRUE Vernam mode with a separate keystream lane per direction
+    ///
+    /// Unlike [`enable_synchronized_vernam`](Self::enable_synchronized_vernam), both
+    /// parties may send at the same time without their envelopes colliding.
+    /// `is_alice` must be true on exactly one side.
+    #[cfg(feature = "drand")]
+    pub fn enable_synchronized_vernam_with_role(&mut self, shared_seed: [u8; 32], is_alice: bool) {
+        let sync_buffer = SynchronizedVernamBuffer::with_role(shared_seed, is_alice);
+        self.synchronized_buffer = Some(Arc::new(sync_buffer));
+        info!("✅ Enabled TRUE synchronized Vernam mode (is_alice: {})", is_alice);
+    }
+
+    /// Enable genuine one-time pad mode with a pre-shared pad file
+    ///
+    /// Every message is XORed with fresh pad bytes, taking precedence over the
+    /// other XOR layers. Encryption fails once our half of the pad is used up;
+    /// watch [`PadFile::is_low`] and exchange a new pad in time. The pad keeps its
+    /// own persistent offsets, so it is not part of session snapshots and must be
+    /// enabled again after [`restore`](Self::restore).
+    #[cfg(feature = "std")]
+    pub fn enable_one_time_pad(&mut self, pad: Arc<PadFile>) {
+        info!("✅ Enabled one-time pad mode ({} bytes left, is_alice: {})", pad.available(), pad.is_alice());
+        self.pad = Some(pad);
+    }
+
     /// Create a shared seed from multiple entropy sources for TRUE OTP
     /// 
     /// This combines multiple entropy sources using XOR for information-theoretic security:
@@ -100,6 +341,11 @@ impl WasifVernam {
     /// - Peer contributions XOR'd during handshake
     /// 
     /// The result is information-theoretically secure if at least one source is random.
+    ///
+    /// Unlike [`EntropyCombiner`](crate::entropy::EntropyCombiner), whose hash extractor
+    /// is only computationally secure, the seed stays a plain XOR: that is what keeps it
+    /// uniform whenever one input is, which the one-time pad modes rely on. It also
+    /// needs no async runtime or `drand` feature, and never drops an input.
     /// 
     /// # Arguments
     /// * `mlkem_secret` - 32-byte shared secret from ML-KEM handshake
@@ -124,10 +370,19 @@ impl WasifVernam {
         shared_seed
     }
 
-    /// Enable ciphertext scrambling with a specific permutation size
-    pub fn enable_scrambling(&mut self, size: usize) {
-        // Use the swarm seed as entropy for scrambling
-        self.scrambler = Some(CiphertextScrambler::from_entropy(&self.swarm_seed, size));
+    /// Enable ciphertext scrambling for every packet
+    ///
+    /// Each envelope body is permuted under a key derived from the AEAD key and
+    /// the envelope nonce, so packets of any length are scrambled differently.
+    pub fn enable_scrambling(&mut self) {
+        self.scrambler = Some(PacketScrambler::new(&self.aead_key));
+    }
+
+    /// Replace the replay window used for received envelopes
+    ///
+    /// Each new AEAD key epoch starts from an empty copy of this window.
+    pub fn set_replay_window(&mut self, window: Box<dyn ReplayWindow>) {
+        self.anti_replay = Arc::new(AntiReplayContainer::with_window(window));
     }
 
     /// Enable recursive key chain for forward secrecy
@@ -135,49 +390,360 @@ impl WasifVernam {
         self.key_chain = Some(RecursiveChain::new(&initial_seed, is_alice));
     }
 
-    /// Generate a keystream using HKDF with the swarm seed
+    /// Run an ML-KEM exchange within key updates according to `policy`
+    ///
+    /// Requires [`enable_key_chain`](Self::enable_key_chain). Responders always
+    /// complete a PQ step the peer requests, with or without a policy of their own.
+    pub fn enable_pq_ratchet(&mut self, policy: PqRatchetPolicy) {
+        self.pq_ratchet = Some(PqRatchet::new(policy));
+    }
+
+    /// Check if the next key update will carry an ML-KEM exchange
+    pub fn pq_ratchet_due(&self) -> bool {
+        self.pq_ratchet.as_ref().is_some_and(|ratchet| ratchet.is_due())
+    }
+
+    /// Replace the swarm seed and the keystream keyed from it
     /// 
-    /// ⚠️ SECURITY NOTE: This uses a static swarm seed. For forward secrecy,
+    /// ⚠️ SECURITY NOTE: The seed stays until replaced. For forward secrecy,
     /// call refresh_entropy() periodically or use the recursive key chain feature.
-    fn generate_keystream(&self, offset: u64, length: usize) -> Vec<u8> {
-        // Use stack allocation for small sizes to avoid heap allocation in hot path
-        const SMALL_BUFFER_SIZE: usize = 1024;
-        
-        let hk = Hkdf::<Sha256>::new(Some(b"zks-vernam-keystream"), &*self.swarm_seed);
-        let info = format!("offset-{}", offset);
-        
-        if length <= SMALL_BUFFER_SIZE {
-            let mut small_buffer = [0u8; SMALL_BUFFER_SIZE];
-            if hk.expand(info.as_bytes(), &mut small_buffer[..length]).is_err() {
-                return Vec::new(); // Return empty vector on HKDF failure
+    fn set_swarm_seed(&mut self, seed: [u8; 32]) {
+        self.keystream = VernamKeystream::new(&seed);
+        self.swarm_seed = Zeroizing::new(seed);
+        self.has_swarm_entropy = true;
+    }
+
+    /// Enter the next key epoch, keeping the current keys for a grace window
+    ///
+    /// Must run before the swarm seed is replaced. With `new_cipher` the AEAD key
+    /// changes and the nonce counter restarts; otherwise the counter carries on.
+    fn begin_epoch(&mut self, new_cipher: Option<(AeadBackend, Zeroizing<[u8; 32]>)>) {
+        let epoch = self.key_epoch.load(Ordering::SeqCst);
+        let previous_scrambler = self.scrambler.clone();
+        let mut previous_commit_key = self.commit_key.clone();
+        let (cipher, anti_replay) = match new_cipher {
+            Some((cipher, key)) => {
+                let scrambler = self.scrambler.as_ref().map(|_| PacketScrambler::new(&key));
+                previous_commit_key = core::mem::replace(&mut self.commit_key, CommitmentKey::derive(&key));
+                self.aead_key = key;
+                self.nonce_counter.store(0, Ordering::SeqCst);
+                let anti_replay = Arc::new(self.anti_replay.fresh());
+                self.scrambler = scrambler;
+                (
+                    core::mem::replace(&mut self.cipher, cipher),
+                    core::mem::replace(&mut self.anti_replay, anti_replay),
+                )
             }
-            small_buffer[..length].to_vec()
+            None => (self.cipher.clone(), self.anti_replay.clone()),
+        };
+        self.previous_epoch = Some(PreviousEpoch {
+            epoch,
+            cipher,
+            commit_key: previous_commit_key,
+            swarm_seed: self.swarm_seed.clone(),
+            keystream: self.keystream.clone(),
+            scrambler: previous_scrambler,
+            anti_replay,
+            grace_remaining: AtomicU32::new(REKEY_GRACE_MESSAGES),
+        });
+        self.key_epoch.store(epoch.wrapping_add(1), Ordering::SeqCst);
+    }
+
+    /// Drop the previous epoch's keys once its grace window has been used up
+    fn expire_previous_epoch(&mut self) {
+        let expired = self
+            .previous_epoch
+            .as_ref()
+            .is_some_and(|prev| prev.grace_remaining.load(Ordering::SeqCst) == 0);
+        if expired {
+            self.previous_epoch = None;
+            debug!("🔑 Previous key epoch dropped after grace window");
+        }
+    }
+
+    /// Check if a key update should be initiated
+    pub fn needs_rekey(&self) -> bool {
+        self.key_chain.is_some()
+            && self.pending_rekey.is_none()
+            && (self.nonce_counter.load(Ordering::SeqCst) >= REKEY_INTERVAL || self.pq_ratchet_due())
+    }
+
+    /// Start a key update
+    ///
+    /// Returns a KeyUpdate envelope to send to the peer in-band. The new epoch is
+    /// entered once the peer's response is passed to [`handle_key_update`](Self::handle_key_update).
+    pub fn initiate_rekey(&mut self) -> Result<Vec<u8>, RekeyError> {
+        if self.pending_rekey.is_some() {
+            return Err(RekeyError::InProgress);
+        }
+        let keypair = if self.pq_ratchet_due() {
+            Some(ratchet_keypair()?)
         } else {
-            let mut keystream = vec![0u8; length];
-            if hk.expand(info.as_bytes(), &mut keystream).is_err() {
-                return Vec::new(); // Return empty vector on HKDF failure
+            None
+        };
+        let chain = self.key_chain.as_mut().ok_or(RekeyError::NoKeyChain)?;
+        let snapshot = chain.export_state();
+        chain.advance(&*fresh_contribution_entropy()?);
+
+        let epoch = self.key_epoch.load(Ordering::SeqCst).wrapping_add(1);
+        let update = KeyUpdate {
+            kind: KeyUpdateKind::Request,
+            epoch,
+            contribution: chain.our_contribution(),
+            kem: keypair.as_ref().map(|keypair| keypair.public_key().to_vec()),
+        };
+        self.pending_rekey = Some(PendingRekey {
+            epoch,
+            snapshot,
+            kem_secret: keypair.map(MlKemKeypair::into_secret_key),
+        });
+        self.seal_key_update(&update)
+    }
+
+    /// Process a KeyUpdate envelope from the peer
+    ///
+    /// For a request, returns the response envelope to send back; it is sealed
+    /// under the current epoch before this side moves to the new one.
+    pub fn handle_key_update(&mut self, envelope: &[u8]) -> Result<Option<Vec<u8>>, RekeyError> {
+        let mut buf = envelope.to_vec();
+        let update = {
+            let body = self.open_envelope(&mut buf, b"", true).map_err(|_| RekeyError::Crypto)?;
+            let update = KeyUpdate::from_bytes(body);
+            body.zeroize();
+            update?
+        };
+        self.expire_previous_epoch();
+
+        let expected = self.key_epoch.load(Ordering::SeqCst).wrapping_add(1);
+        if update.epoch != expected {
+            return Err(RekeyError::UnexpectedEpoch { expected, actual: update.epoch });
+        }
+
+        match update.kind {
+            KeyUpdateKind::Request => {
+                let is_alice = self.key_chain.as_ref().ok_or(RekeyError::NoKeyChain)?.is_alice();
+                if let Some(pending) = self.pending_rekey.take() {
+                    if is_alice {
+                        // Both sides initiated: Alice's request wins
+                        self.pending_rekey = Some(pending);
+                        return Ok(None);
+                    }
+                    // Bob yields and rolls back his own contribution
+                    self.key_chain = Some(RecursiveChain::import_state(pending.snapshot));
+                }
+
+                let encapsulation = match update.kem {
+                    Some(ref public_key) => Some(ratchet_encapsulate(public_key)?),
+                    None => None,
+                };
+                let chain = self.key_chain.as_mut().ok_or(RekeyError::NoKeyChain)?;
+                chain.update_peer_key(&update.contribution);
+                chain.advance(&*fresh_contribution_entropy()?);
+                let response = KeyUpdate {
+                    kind: KeyUpdateKind::Response,
+                    epoch: update.epoch,
+                    contribution: chain.our_contribution(),
+                    kem: encapsulation.as_ref().map(|encapsulation| encapsulation.ciphertext.clone()),
+                };
+                if let Some(ref encapsulation) = encapsulation {
+                    chain.mix_pq_secret(&encapsulation.shared_secret);
+                }
+                let new_key = Zeroizing::new(chain.derive_epoch_key(update.epoch));
+                let cipher = AeadBackend::new(self.cipher.algorithm(), &new_key)
+                    .map_err(|_| RekeyError::Crypto)?;
+
+                let sealed = self.seal_key_update(&response)?;
+                self.begin_epoch(Some((cipher, new_key)));
+                if encapsulation.is_some() {
+                    self.pq_step_completed();
+                }
+                info!("🔑 Entered key epoch {} (responder)", update.epoch);
+                Ok(Some(sealed))
+            }
+            KeyUpdateKind::Response => {
+                let pending = match self.pending_rekey.take() {
+                    Some(pending) if pending.epoch == update.epoch => pending,
+                    other => {
+                        self.pending_rekey = other;
+                        return Err(RekeyError::UnexpectedResponse);
+                    }
+                };
+                // A PQ request must be answered with a ciphertext and vice versa
+                let shared_secret = match (&pending.kem_secret, &update.kem) {
+                    (Some(secret_key), Some(ciphertext)) => {
+                        Some(MlKem::decapsulate(ciphertext, secret_key).map_err(|_| RekeyError::Kem)?)
+                    }
+                    (None, None) => None,
+                    _ => {
+                        self.pending_rekey = Some(pending);
+                        return Err(RekeyError::Malformed);
+                    }
+                };
+
+                let chain = self.key_chain.as_mut().ok_or(RekeyError::NoKeyChain)?;
+                chain.update_peer_key(&update.contribution);
+                if let Some(ref shared_secret) = shared_secret {
+                    chain.mix_pq_secret(shared_secret);
+                }
+                let new_key = Zeroizing::new(chain.derive_epoch_key(update.epoch));
+                let cipher = AeadBackend::new(self.cipher.algorithm(), &new_key)
+                    .map_err(|_| RekeyError::Crypto)?;
+
+                self.begin_epoch(Some((cipher, new_key)));
+                if shared_secret.is_some() {
+                    self.pq_step_completed();
+                }
+                info!("🔑 Entered key epoch {} (initiator)", update.epoch);
+                Ok(None)
             }
-            keystream
         }
     }
 
-    /// Update the cipher key (used during key rotation)
-    fn update_cipher_key(&mut self, new_key: [u8; 32]) -> Result<(), AeadError> {
-        self.cipher = ChaCha20Poly1305::new_from_slice(&new_key)
-            .map_err(|_| chacha20poly1305::aead::Error)?;
-        info!("🔑 Cipher key rotated successfully");
-        Ok(())
+    fn pq_step_completed(&mut self) {
+        if let Some(ref mut ratchet) = self.pq_ratchet {
+            ratchet.completed();
+        }
+        info!("🔑 Post-quantum ratchet step completed");
+    }
+
+    /// Check whether an envelope carries a key update rather than data
+    pub fn is_key_update(data: &[u8]) -> bool {
+        EnvelopeHeader::parse(data).is_ok_and(|header| header.has_flag(FLAG_KEY_UPDATE))
+    }
+
+    fn seal_key_update(&mut self, update: &KeyUpdate) -> Result<Vec<u8>, RekeyError> {
+        let header_len = self.envelope_header_len();
+        let body = update.to_bytes();
+        let mut envelope = vec![0u8; header_len + body.len() + self.trailer_len()];
+        envelope[header_len..header_len + body.len()].copy_from_slice(&body);
+        self.seal_in_place(&mut envelope, b"", FLAG_KEY_UPDATE)
+            .map_err(|_| RekeyError::Crypto)?;
+        Ok(envelope)
     }
 
     /// Encrypt data using the Wasif Vernam cipher
     pub fn encrypt(&mut self, data: &[u8]) -> Result<Vec<u8>, AeadError> {
-        
+        self.encrypt_with_aad(data, b"")
+    }
+
+    /// Encrypt data and authenticate `aad` alongside it
+    ///
+    /// `aad` is not part of the envelope; the receiver must supply the same bytes
+    /// to [`decrypt_with_aad`](Self::decrypt_with_aad). The envelope header is
+    /// always authenticated, whether or not `aad` is empty.
+    pub fn encrypt_with_aad(&mut self, data: &[u8], aad: &[u8]) -> Result<Vec<u8>, AeadError> {
+        let prefix = self.padding_prefix(data.len())?;
+        let header_len = self.envelope_header_len();
+        let mut envelope = vec![0u8; self.sealed_len(data.len())];
+        let body_end = envelope.len() - self.trailer_len();
+        let flags = match prefix {
+            None => {
+                envelope[header_len..body_end].copy_from_slice(data);
+                0
+            }
+            Some(prefix) => {
+                padding::write_padded(&mut envelope[header_len..body_end], prefix, data);
+                FLAG_PADDED
+            }
+        };
+        self.seal_in_place(&mut envelope, aad, flags)?;
+        Ok(envelope)
+    }
+
+    /// Encrypt in place inside a caller-provided `BytesMut`
+    ///
+    /// `buf` must hold [`envelope_header_len`](Self::envelope_header_len) bytes of
+    /// headroom followed by the plaintext. The tag and any padding are appended, so
+    /// reserve [`sealed_len`](Self::sealed_len) bytes of capacity to avoid a
+    /// reallocation. On success `buf` holds the envelope.
+    ///
+    /// If sealing fails, `buf` is zeroed rather than left partially encrypted and
+    /// truncated back to its original length: keep a copy of the plaintext if it
+    /// may need to be sent again.
+    pub fn encrypt_in_place(&mut self, buf: &mut BytesMut) -> Result<(), AeadError> {
+        let header_len = self.envelope_header_len();
+        if buf.len() < header_len {
+            return Err(AeadError);
+        }
+        let plain_len = buf.len();
+        let data_len = plain_len - header_len;
+        let flags = match self.padding_prefix(data_len)? {
+            None => {
+                buf.resize(plain_len + self.trailer_len(), 0);
+                0
+            }
+            Some(prefix) => {
+                // Shift the plaintext behind its length prefix and zero the padding
+                buf.resize(self.sealed_len(data_len), 0);
+                buf.copy_within(header_len..plain_len, header_len + PADDING_PREFIX_LEN);
+                buf[header_len..header_len + PADDING_PREFIX_LEN].copy_from_slice(&prefix);
+                buf[header_len + PADDING_PREFIX_LEN + data_len..].fill(0);
+                FLAG_PADDED
+            }
+        };
+        let result = self.seal_in_place(buf, b"", flags);
+        if result.is_err() {
+            buf.truncate(plain_len);
+        }
+        result
+    }
+
+    /// Length prefix of a padded body, or `None` without a padding policy
+    ///
+    /// Fails for payloads of 4 GiB or more, whose length the prefix cannot hold.
+    fn padding_prefix(&self, data_len: usize) -> Result<Option<[u8; PADDING_PREFIX_LEN]>, AeadError> {
+        if self.padding.is_none() {
+            return Ok(None);
+        }
+        match padding::length_prefix(data_len) {
+            Some(prefix) => Ok(Some(prefix)),
+            None => {
+                warn!("Refusing to pad a {} byte payload: length prefix is 32 bits", data_len);
+                Err(AeadError)
+            }
+        }
+    }
+
+    /// Encrypt in place inside a caller-provided slice
+    ///
+    /// Layout of `buf`: `[headroom (envelope_header_len) | plaintext | tailroom]`, where the
+    /// tailroom is [`TAG_LEN`] bytes, plus [`COMMITMENT_LEN`] with key commitment enabled.
+    /// On success `buf` holds the complete envelope, byte-identical to [`encrypt`](Self::encrypt)
+    /// without padding. The slice cannot grow, so the padding policy is not applied.
+    ///
+    /// If sealing fails, the whole slice is zeroed, plaintext included.
+    pub fn encrypt_in_place_slice(&mut self, buf: &mut [u8]) -> Result<(), AeadError> {
+        self.seal_in_place(buf, b"", 0)
+    }
+
+    fn seal_in_place(&mut self, buf: &mut [u8], aad: &[u8], control_flags: u8) -> Result<(), AeadError> {
+        if buf.len() < self.envelope_overhead() {
+            return Err(AeadError);
+        }
+        self.expire_previous_epoch();
+        let result = self.seal_envelope(buf, aad, control_flags);
+        if result.is_err() {
+            // Never leave (partially mixed) plaintext behind on failure
+            buf.zeroize();
+        }
+        result
+    }
+
+    fn seal_envelope(&mut self, buf: &mut [u8], aad: &[u8], control_flags: u8) -> Result<(), AeadError> {
+        let trailer_len = self.trailer_len();
+        let (header, body) = buf.split_at_mut(self.envelope_header_len());
+        let data_len = body.len() - trailer_len;
+
+        // A persisted session only sends within the range its last snapshot reserved
+        if self.snapshot_sequence != 0 && !self.within_snapshot_reserve(data_len) {
+            warn!("Session snapshot reserve exhausted; take and persist a new snapshot before sending");
+            return Err(AeadError);
+        }
+
         // Generate unique nonce and get counter
         let mut nonce_bytes = [0u8; 12];
         let counter = self.nonce_counter.fetch_add(1, Ordering::SeqCst);
-        
 
-        
         // Check for nonce wraparound - this would cause nonce reuse
         // Note: counter is the value BEFORE the increment, so 0 is valid for the first call
         if counter == u64::MAX {
@@ -185,130 +751,372 @@ impl WasifVernam {
             error!("🚨 CRITICAL: Nonce counter wrapped around - nonce reuse imminent!");
             return Err(AeadError);
         }
-        
+        if let Some(ref ratchet) = self.pq_ratchet {
+            ratchet.record_message();
+        }
+
         // Use counter as part of nonce for uniqueness
         nonce_bytes[4..12].copy_from_slice(&counter.to_be_bytes());
 
-        // Key rotation logic
-        if let Some(ref mut chain) = self.key_chain {
-            if counter % 1000 == 0 && counter > 0 {
-                let mut entropy = [0u8; 32];
-                if getrandom::getrandom(&mut entropy).is_err() {
-                    warn!("RNG unavailable during key rotation");
-                    return Err(AeadError);
-                }
-                let new_key = chain.advance(&entropy);
-                if let Err(_) = self.update_cipher_key(new_key) {
-                    warn!("Failed to update cipher key during rotation");
-                    return Err(AeadError);
-                }
-                // Reset nonce counter after key rotation to prevent correlation
-                self.nonce_counter.store(0, Ordering::SeqCst);
-                info!("🔑 Cipher key rotated successfully - nonce counter reset");
-                entropy.zeroize();
-            }
-        }
-
         // True Vernam XOR layer (if swarm entropy available)
-        let mut mixed_data = Zeroizing::new(data.to_vec());
-        let key_offset = if self.has_swarm_entropy {
-            // Use synchronized buffer if available (information-theoretic security)
-            if let Some(ref sync_buffer) = self.synchronized_buffer {
-                let keystream = sync_buffer.consume_sync(data.len());
-                for (i, byte) in mixed_data.iter_mut().enumerate() {
-                    *byte ^= keystream[i];
-                }
-                self.key_offset.fetch_add(data.len() as u64, Ordering::SeqCst)
-            } else {
-                // Fallback to static swarm seed (computational security)
-                let offset = self.key_offset.fetch_add(data.len() as u64, Ordering::SeqCst);
-                let keystream = self.generate_keystream(offset, data.len());
-                for (i, byte) in mixed_data.iter_mut().enumerate() {
-                    *byte ^= keystream[i];
-                }
-                offset
+        let plaintext = &mut body[..data_len];
+        let (mode, key_offset) = self.apply_xor_layer(plaintext)?;
+
+        // Header goes first so the AEAD can authenticate it
+        let mut flags = control_flags;
+        if self.scrambler.is_some() {
+            flags |= FLAG_SCRAMBLED;
+        }
+        if self.key_commitment {
+            flags |= FLAG_COMMITTED;
+        }
+        let key_epoch = self.key_epoch.load(Ordering::SeqCst);
+        let algorithm = self.cipher.algorithm();
+        let xnonce = if algorithm == AeadAlgorithm::XChaCha20Poly1305 {
+            let mut xnonce = [0u8; XNONCE_LEN];
+            if rng::fill(&mut xnonce).is_err() {
+                warn!("RNG unavailable for XChaCha20 nonce");
+                return Err(AeadError);
             }
+            Some(xnonce)
         } else {
-            0
+            None
         };
-
-        // ChaCha20-Poly1305 encryption
-        let nonce = Nonce::from_slice(&nonce_bytes);
-        let mut ciphertext = self.cipher.encrypt(nonce, mixed_data.as_ref())?;
+        let envelope = EnvelopeHeader::new(mode, flags, key_epoch, nonce_bytes, key_offset)
+            .with_aead(algorithm, xnonce);
+        envelope.write_to(header);
+
+        // AEAD encryption, then the commitment over header, AAD and tag
+        let (tag, commitment) = with_envelope_aad(header, aad, |full_aad| {
+            let tag = self.cipher.encrypt_in_place_detached(envelope.aead_nonce(), full_aad, plaintext)?;
+            let commitment = self.key_commitment.then(|| self.commit_key.commit(full_aad, &tag));
+            Ok::<_, AeadError>((tag, commitment))
+        })?;
+        body[data_len..data_len + TAG_LEN].copy_from_slice(&tag);
+        if let Some(commitment) = commitment {
+            body[data_len + TAG_LEN..].copy_from_slice(&commitment);
+        }
 
         // Scrambling (if enabled)
         if let Some(ref scrambler) = self.scrambler {
-            if ciphertext.len() == scrambler.size() {
-                scrambler.scramble(&mut ciphertext);
+            scrambler.scramble(&nonce_bytes, body);
+        }
+        Ok(())
+    }
+
+    /// XOR `plaintext` with the active layer, returning the mode and keystream position
+    fn apply_xor_layer(&self, plaintext: &mut [u8]) -> Result<(CipherMode, u64), AeadError> {
+        let data_len = plaintext.len();
+        #[cfg(feature = "std")]
+        if let Some(ref pad) = self.pad {
+            // Genuine OTP: refuse rather than fall back once the pad runs out
+            let (position, pad_bytes) = pad.consume(data_len).map_err(|e| {
+                warn!("One-time pad unavailable: {}", e);
+                AeadError
+            })?;
+            for (byte, k) in plaintext.iter_mut().zip(pad_bytes.iter()) {
+                *byte ^= k;
             }
+            return Ok((CipherMode::PadOtp, position));
+        }
+        if !self.has_swarm_entropy {
+            return Ok((CipherMode::AeadOnly, 0));
         }
 
-        // Build result envelope
-        let mut result = Vec::with_capacity(12 + 8 + ciphertext.len());
-        result.extend_from_slice(&nonce_bytes);
-        result.extend_from_slice(&key_offset.to_be_bytes());
-        result.append(&mut ciphertext);
-        Ok(result)
+        // Use synchronized buffer if available (information-theoretic security)
+        // The envelope records the keystream position, so the peer can
+        // decrypt it even if earlier envelopes were lost or reordered
+        #[cfg(feature = "drand")]
+        if let Some(ref sync_buffer) = self.synchronized_buffer {
+            let position = sync_buffer.reserve(data_len).map_err(|_| AeadError)?;
+            let keystream = sync_buffer.keystream_at(position, data_len);
+            for (byte, k) in plaintext.iter_mut().zip(keystream.iter()) {
+                *byte ^= k;
+            }
+            return Ok((CipherMode::TrueOtp, position));
+        }
+
+        // Fallback to static swarm seed (computational security)
+        let offset = self.key_offset.fetch_add(data_len as u64, Ordering::SeqCst);
+        self.keystream.apply(offset, plaintext);
+        Ok((CipherMode::StreamXor, offset))
+    }
+
+    /// Whether TRUE OTP envelopes can be opened
+    #[cfg(feature = "drand")]
+    fn has_synchronized_buffer(&self) -> bool {
+        self.synchronized_buffer.is_some()
+    }
+
+    /// Whether TRUE OTP envelopes can be opened
+    #[cfg(not(feature = "drand"))]
+    fn has_synchronized_buffer(&self) -> bool {
+        false
+    }
+
+    /// Whether one-time pad envelopes can be opened
+    #[cfg(feature = "std")]
+    fn has_pad(&self) -> bool {
+        self.pad.is_some()
+    }
+
+    /// Whether one-time pad envelopes can be opened
+    #[cfg(not(feature = "std"))]
+    fn has_pad(&self) -> bool {
+        false
+    }
+
+    /// Next TRUE OTP keystream position, if a synchronized buffer is active
+    #[cfg(feature = "drand")]
+    fn sync_position(&self) -> Option<u64> {
+        self.synchronized_buffer.as_ref().map(|buffer| buffer.current_position())
+    }
+
+    /// Next TRUE OTP keystream position, if a synchronized buffer is active
+    #[cfg(not(feature = "drand"))]
+    fn sync_position(&self) -> Option<u64> {
+        None
     }
 
     /// Decrypt data encrypted with the Wasif Vernam cipher
     pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, AeadError> {
-        if data.len() < 12 + 8 {
+        self.decrypt_with_aad(data, b"")
+    }
+
+    /// Decrypt data sealed by [`encrypt_with_aad`](Self::encrypt_with_aad)
+    ///
+    /// Fails unless `aad` matches the bytes supplied at encryption time.
+    pub fn decrypt_with_aad(&self, data: &[u8], aad: &[u8]) -> Result<Vec<u8>, AeadError> {
+        let mut buf = data.to_vec();
+        let range = self.open_envelope_range(&mut buf, aad, false)?;
+        buf.truncate(range.end);
+        buf.drain(..range.start);
+        Ok(buf)
+    }
+
+    /// Decrypt an envelope in place inside a `BytesMut`
+    ///
+    /// On success the header is split off and the tag and padding truncated
+    /// without copying, leaving exactly the plaintext in `buf`.
+    pub fn decrypt_in_place(&self, buf: &mut BytesMut) -> Result<(), AeadError> {
+        let range = self.open_envelope_range(buf, b"", false)?;
+        buf.truncate(range.end);
+        buf.advance(range.start);
+        Ok(())
+    }
+
+    /// Decrypt an envelope in place inside a slice
+    ///
+    /// Accepts both current and legacy (version 0) envelopes. Returns the
+    /// plaintext, which lives at `buf[header.header_len()..]`, behind a
+    /// [`PADDING_PREFIX_LEN`]-byte length prefix if the envelope is padded.
+    pub fn decrypt_in_place_slice<'a>(&self, buf: &'a mut [u8]) -> Result<&'a mut [u8], AeadError> {
+        self.open_envelope(buf, b"", false)
+    }
+
+    fn open_envelope<'a>(&self, buf: &'a mut [u8], aad: &[u8], key_update: bool) -> Result<&'a mut [u8], AeadError> {
+        let range = self.open_envelope_range(buf, aad, key_update)?;
+        Ok(&mut buf[range])
+    }
+
+    /// Authenticate and decrypt an envelope, returning where its plaintext lies in `buf`
+    fn open_envelope_range(&self, buf: &mut [u8], aad: &[u8], key_update: bool) -> Result<core::ops::Range<usize>, AeadError> {
+        let header = EnvelopeHeader::parse(buf).map_err(|e| {
+            warn!("Rejected envelope: {}", e);
+            AeadError
+        })?;
+        let committed = header.has_flag(FLAG_COMMITTED);
+        if self.key_commitment && !committed {
+            warn!("Envelope without key commitment refused");
+            return Err(AeadError);
+        }
+        let trailer_len = if committed { TAG_LEN + COMMITMENT_LEN } else { TAG_LEN };
+        if buf.len() < header.header_len() + trailer_len {
+            return Err(AeadError);
+        }
+        let (header_bytes, body) = buf.split_at_mut(header.header_len());
+        let key_offset = header.key_offset;
+        if header.has_flag(FLAG_KEY_UPDATE) != key_update {
+            warn!("Key update and data envelopes must not be mixed up");
+            return Err(AeadError);
+        }
+        if header.aead != self.cipher.algorithm() {
+            warn!("Envelope sealed with {:?}, cipher runs {:?}", header.aead, self.cipher.algorithm());
             return Err(AeadError);
         }
 
-        // Extract nonce and key offset
-        let nonce = Nonce::from_slice(&data[0..12]);
-        let key_offset = match data[12..20].try_into() {
-            Ok(bytes) => u64::from_be_bytes(bytes),
-            Err(_) => return Err(AeadError),
+        // Pick the epoch's keys: the previous epoch is accepted during its grace window
+        let legacy = header.version == LEGACY_ENVELOPE_VERSION;
+        let epoch = self.key_epoch.load(Ordering::SeqCst);
+        let previous = match self.previous_epoch {
+            Some(ref prev) if !legacy && header.key_epoch == prev.epoch && header.key_epoch != epoch => {
+                if prev.grace_remaining.load(Ordering::SeqCst) == 0 {
+                    warn!("Key epoch {} is past its grace window", prev.epoch);
+                    return Err(AeadError);
+                }
+                Some(prev)
+            }
+            _ => None,
+        };
+        let (cipher, commit_key, swarm_seed, keystream, scrambler, anti_replay) = match previous {
+            Some(prev) => (&prev.cipher, &prev.commit_key, &prev.swarm_seed, &prev.keystream, &prev.scrambler, &prev.anti_replay),
+            None => (&self.cipher, &self.commit_key, &self.swarm_seed, &self.keystream, &self.scrambler, &self.anti_replay),
         };
-        let ciphertext = &data[20..];
 
-        // Check for replay attacks using counter from nonce bytes 4-12
-        let counter_bytes = &data[4..12];
-        let pid = match counter_bytes.try_into() {
-            Ok(bytes) => u64::from_be_bytes(bytes),
-            Err(_) => return Err(AeadError),
+        // Resolve the XOR layer before touching any state
+        let mode = match header.mode {
+            Some(mode) => mode,
+            // Legacy envelopes did not record the mode, so guess as before
+            None if self.has_swarm_entropy && key_offset > 0 => {
+                if self.has_synchronized_buffer() {
+                    CipherMode::TrueOtp
+                } else {
+                    CipherMode::HkdfXor
+                }
+            }
+            None => CipherMode::AeadOnly,
         };
-        if !self.anti_replay.validate_pid(pid) {
+        match mode {
+            CipherMode::AeadOnly => {}
+            CipherMode::TrueOtp => {
+                if !self.has_synchronized_buffer() {
+                    warn!("TRUE OTP envelope received without a synchronized buffer");
+                    return Err(AeadError);
+                }
+            }
+            CipherMode::PadOtp => {
+                if !self.has_pad() {
+                    warn!("One-time pad envelope received without a pad");
+                    return Err(AeadError);
+                }
+            }
+            CipherMode::HkdfXor | CipherMode::StreamXor => {
+                if !self.has_swarm_entropy {
+                    warn!("Keystream envelope received without swarm entropy");
+                    return Err(AeadError);
+                }
+                if !legacy && previous.is_none() && header.key_epoch != epoch {
+                    warn!("Key epoch mismatch: envelope {}, local {}", header.key_epoch, epoch);
+                    return Err(AeadError);
+                }
+            }
+        }
+
+        // Check for replay attacks using counter from nonce bytes 4-12
+        // (recorded only once the envelope authenticates)
+        if !anti_replay.check_pid(header.packet_id()) {
             warn!("Replay attack detected!");
             return Err(AeadError);
         }
 
-        // Descramble (if enabled)
-        let mut ciphertext = ciphertext.to_vec();
-        if let Some(ref scrambler) = self.scrambler {
-            if ciphertext.len() == scrambler.size() {
-                scrambler.unscramble(&mut ciphertext);
+        // Descramble (legacy envelopes carry no flag and are never descrambled)
+        if header.has_flag(FLAG_SCRAMBLED) {
+            match scrambler {
+                Some(scrambler) => scrambler.unscramble(&header.nonce, body),
+                None => {
+                    warn!("Scrambled envelope received without scrambling enabled");
+                    return Err(AeadError);
+                }
             }
         }
 
-        // ChaCha20-Poly1305 decryption
-        let mut plaintext = Zeroizing::new(self.cipher.decrypt(nonce, ciphertext.as_slice())?);
+        // Key commitment is checked before the AEAD runs
+        let data_len = body.len() - trailer_len;
+        let (plaintext, trailer) = body.split_at_mut(data_len);
+        let (tag, commitment) = trailer.split_at_mut(TAG_LEN);
+        if committed && !with_envelope_aad(header_bytes, aad, |full_aad| commit_key.verify(full_aad, tag, commitment)) {
+            warn!("Key commitment mismatch");
+            return Err(AeadError);
+        }
+
+        // AEAD decryption
+        let nonce = header.aead_nonce();
+        if legacy {
+            // Legacy envelopes only ever authenticated the caller's AAD
+            cipher.decrypt_in_place_detached(nonce, aad, plaintext, tag)?;
+        } else {
+            with_envelope_aad(header_bytes, aad, |full_aad| {
+                cipher.decrypt_in_place_detached(nonce, full_aad, plaintext, tag)
+            })?;
+        }
+        if !anti_replay.validate_pid(header.packet_id()) {
+            warn!("Replay attack detected!");
+            plaintext.zeroize();
+            return Err(AeadError);
+        }
 
-        // Reverse XOR layer (if swarm entropy was used)
-        if self.has_swarm_entropy && key_offset > 0 {
-            // Use synchronized buffer if available (information-theoretic security)
-            if let Some(ref sync_buffer) = self.synchronized_buffer {
-                let keystream = sync_buffer.consume_sync(plaintext.len());
-                for (i, byte) in plaintext.iter_mut().enumerate() {
-                    *byte ^= keystream[i];
+        // Reverse XOR layer recorded in the envelope
+        match mode {
+            CipherMode::AeadOnly => {}
+            CipherMode::TrueOtp => {
+                #[cfg(feature = "drand")]
+                if let Some(ref sync_buffer) = self.synchronized_buffer {
+                    // Legacy envelopes carry no position and rely on lockstep order
+                    let keystream = if legacy {
+                        sync_buffer.consume_sync(data_len)
+                    } else {
+                        if sync_buffer.claim_received(key_offset, data_len).is_err() {
+                            plaintext.zeroize();
+                            return Err(AeadError);
+                        }
+                        sync_buffer.keystream_at(key_offset, data_len)
+                    };
+                    for (byte, k) in plaintext.iter_mut().zip(keystream.iter()) {
+                        *byte ^= k;
+                    }
                 }
-            } else {
-                // Fallback to static swarm seed (computational security)
-                let keystream = self.generate_keystream(key_offset, plaintext.len());
-                if keystream.len() != plaintext.len() {
-                    warn!("⚠️ HKDF keystream generation failed for decryption: expected {}, got {}", plaintext.len(), keystream.len());
+            }
+            CipherMode::PadOtp => {
+                #[cfg(feature = "std")]
+                if let Some(ref pad) = self.pad {
+                    let pad_bytes = match pad.consume_received(key_offset, data_len) {
+                        Ok(pad_bytes) => pad_bytes,
+                        Err(e) => {
+                            warn!("One-time pad refused envelope: {}", e);
+                            plaintext.zeroize();
+                            return Err(AeadError);
+                        }
+                    };
+                    for (byte, k) in plaintext.iter_mut().zip(pad_bytes.iter()) {
+                        *byte ^= k;
+                    }
+                }
+            }
+            CipherMode::HkdfXor => {
+                let Some(keystream) = hkdf_keystream(swarm_seed, key_offset, data_len) else {
+                    warn!("⚠️ HKDF keystream generation failed for {} bytes", data_len);
+                    plaintext.zeroize();
                     return Err(AeadError);
+                };
+                for (byte, k) in plaintext.iter_mut().zip(keystream.iter()) {
+                    *byte ^= k;
                 }
-                for (i, byte) in plaintext.iter_mut().enumerate() {
-                    *byte ^= keystream[i];
+            }
+            CipherMode::StreamXor => keystream.apply(key_offset, plaintext),
+        }
+
+        // Strip authenticated padding
+        let mut range = 0..data_len;
+        if header.has_flag(FLAG_PADDED) {
+            match padding::unpad(plaintext) {
+                Some(inner) => range = inner,
+                None => {
+                    warn!("Padded envelope carries a malformed length or padding");
+                    plaintext.zeroize();
+                    return Err(AeadError);
                 }
             }
         }
 
-        Ok((*plaintext).clone())
+        // Traffic under the current epoch uses up the previous epoch's grace window
+        if previous.is_none() {
+            if let Some(ref prev) = self.previous_epoch {
+                let _ = prev.grace_remaining.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+            }
+        }
+
+        let header_len = header.header_len();
+        Ok(header_len + range.start..header_len + range.end)
     }
 
     /// Encrypt data using TRUE Vernam mode with embedded XOR key
@@ -316,7 +1124,10 @@ impl WasifVernam {
     /// INFORMATION-THEORETIC SECURITY:
     /// - ZK:// (Direct): Messages ≤64 bytes get TRUE unbreakable encryption
     /// - ZKS:// (Swarm): Messages ≤32 bytes get TRUE unbreakable encryption
-    /// - Larger messages: Use HKDF expansion (256-bit computational security)
+    /// - Larger messages: Use the ChaCha20 keystream (256-bit computational security)
+    ///
+    /// The envelope carries a 96-bit nonce, so XChaCha20-Poly1305 ciphers reject this call.
+    #[cfg(feature = "drand")]
     pub fn encrypt_true_vernam(&mut self, data: &[u8]) -> Result<Vec<u8>, AeadError> {
         let mut nonce_bytes = [0u8; 12];
         let counter = self.nonce_counter.fetch_add(1, Ordering::SeqCst);
@@ -332,8 +1143,8 @@ impl WasifVernam {
         nonce_bytes[4..12].copy_from_slice(&counter.to_be_bytes());
 
         let mut mixed_data = Zeroizing::new(data.to_vec());
-        let mut xor_key = Zeroizing::new(vec![0u8; data.len()]);
         let mut mode_byte = 0x00u8;
+        let mut keystream_offset = None;
 
         // ⚠️ SECURITY LIMITATION: This is "synthetic" OTP, not true OTP.
         // TRUE OTP requires pre-synchronized entropy between parties.
@@ -347,9 +1158,8 @@ impl WasifVernam {
                 let keystream = sync_buffer.consume_sync(data.len());
                 
                 // XOR with synchronized keystream (information-theoretically secure)
-                for (i, byte) in mixed_data.iter_mut().enumerate() {
-                    *byte ^= keystream[i];
-                    xor_key[i] = keystream[i]; // Store for potential debugging
+                for (byte, key) in mixed_data.iter_mut().zip(keystream.iter()) {
+                    *byte ^= key;
                 }
                 mode_byte = 0x01; // 0x01 = TRUE Vernam mode (information-theoretic)
                 debug!("🔐 TRUE OTP: Generated {} synchronized bytes (unbreakable by physics)", data.len());
@@ -361,110 +1171,88 @@ impl WasifVernam {
                         match buffer.consume(data.len()) {
                         Ok(keystream) => {
                             // XOR with TRUE random data (information-theoretically secure)
-                            for (i, byte) in mixed_data.iter_mut().enumerate() {
-                                *byte ^= keystream[i];
-                                xor_key[i] = keystream[i];
+                            for (byte, key) in mixed_data.iter_mut().zip(keystream.iter()) {
+                                *byte ^= key;
                             }
                             mode_byte = 0x01; // 0x01 = True Vernam mode (information-theoretic)
                             debug!("🔐 INFORMATION-THEORETIC: Used {} TRUE random bytes for encryption (unbreakable by physics)", data.len());
                         },
                         Err(_) => {
-                            // Buffer empty/error - fallback to HKDF mode
-                            warn!("⚠️ True Vernam buffer unavailable! Falling back to HKDF mode");
-                            if self.has_swarm_entropy {
-                                let offset = self.key_offset.fetch_add(data.len() as u64, Ordering::SeqCst);
-                                let keystream = self.generate_keystream(offset, data.len());
-                                if keystream.len() != data.len() {
-                                    warn!("⚠️ HKDF keystream generation failed for {} bytes", data.len());
-                                    return Err(AeadError);
-                                }
-                                for (i, byte) in mixed_data.iter_mut().enumerate() {
-                                    *byte ^= keystream[i];
-                                    xor_key[i] = keystream[i];
-                                }
-                                mode_byte = 0x02; // 0x02 = HKDF fallback mode (computational)
-                            }
+                            // Buffer empty/error - fallback to keystream mode
+                            warn!("⚠️ True Vernam buffer unavailable! Falling back to keystream mode");
+                            keystream_offset = self.apply_fallback_keystream(&mut mixed_data);
                         }
                     }
                     }
                     Err(_) => {
-                        // Failed to acquire lock - log and fallback to HKDF mode
-                        warn!("⚠️ Failed to acquire TrueVernamBuffer lock! Falling back to HKDF mode");
-                        if self.has_swarm_entropy {
-                            let offset = self.key_offset.fetch_add(data.len() as u64, Ordering::SeqCst);
-                            let keystream = self.generate_keystream(offset, data.len());
-                            if keystream.len() != data.len() {
-                                warn!("⚠️ HKDF keystream generation failed for {} bytes", data.len());
-                                return Err(AeadError);
-                            }
-                            for (i, byte) in mixed_data.iter_mut().enumerate() {
-                                *byte ^= keystream[i];
-                                xor_key[i] = keystream[i];
-                            }
-                            mode_byte = 0x02; // 0x02 = HKDF fallback mode (computational)
-                        }
+                        // Failed to acquire lock - log and fallback to keystream mode
+                        warn!("⚠️ Failed to acquire TrueVernamBuffer lock! Falling back to keystream mode");
+                        keystream_offset = self.apply_fallback_keystream(&mut mixed_data);
                     }
                 }
-            } else if self.has_swarm_entropy {
-                // No synchronized buffer, use HKDF mode
-                let offset = self.key_offset.fetch_add(data.len() as u64, Ordering::SeqCst);
-                let keystream = self.generate_keystream(offset, data.len());
-                if keystream.len() != data.len() {
-                    warn!("⚠️ HKDF keystream generation failed for {} bytes", data.len());
-                    return Err(AeadError);
-                }
-                for (i, byte) in mixed_data.iter_mut().enumerate() {
-                    *byte ^= keystream[i];
-                    xor_key[i] = keystream[i];
-                }
-                mode_byte = 0x02; // 0x02 = HKDF fallback mode (computational)
+            } else {
+                // No synchronized buffer, use keystream mode
+                keystream_offset = self.apply_fallback_keystream(&mut mixed_data);
             }
         } else {
-            // LARGER MESSAGES: Use HKDF expansion (computational security, 256-bit)
-            if self.has_swarm_entropy {
-                let offset = self.key_offset.fetch_add(data.len() as u64, Ordering::SeqCst);
-                let keystream = self.generate_keystream(offset, data.len());
-                if keystream.len() != data.len() {
-                    warn!("⚠️ HKDF keystream generation failed for {} bytes", data.len());
-                    return Err(AeadError);
-                }
-                for (i, byte) in mixed_data.iter_mut().enumerate() {
-                    *byte ^= keystream[i];
-                    xor_key[i] = keystream[i];
-                }
-                mode_byte = 0x02; // 0x02 = HKDF mode (computational)
-                debug!("🔐 COMPUTATIONAL: Used HKDF for {} bytes (256-bit security)", data.len());
+            // LARGER MESSAGES: Use the ChaCha20 keystream (computational security, 256-bit)
+            keystream_offset = self.apply_fallback_keystream(&mut mixed_data);
+            if keystream_offset.is_some() {
+                debug!("🔐 COMPUTATIONAL: Used ChaCha20 keystream for {} bytes (256-bit security)", data.len());
             }
         }
 
-        // Base Layer: Encrypt with ChaCha20-Poly1305
-        let nonce = Nonce::from_slice(&nonce_bytes);
-        let ciphertext = self.cipher.encrypt(nonce, mixed_data.as_ref())?;
-
-        // Build result: [Nonce (12) | Mode (1) | Ciphertext]
+        // Build result: [Nonce (12) | Mode (1) | Offset (8, BE, mode 0x02 only) | Ciphertext]
         // CRITICAL: Never embed XOR key for true OTP - both parties must have synchronized entropy!
-        let mut result = Vec::with_capacity(12 + 1 + ciphertext.len());
+        let mut result = Vec::with_capacity(12 + 1 + 8 + mixed_data.len() + 16);
         result.extend_from_slice(&nonce_bytes);
-        result.push(mode_byte);
+        if let Some(offset) = keystream_offset {
+            mode_byte = 0x02; // 0x02 = keystream mode (computational)
+            result.push(mode_byte);
+            result.extend_from_slice(&offset.to_be_bytes());
+        } else {
+            result.push(mode_byte);
+        }
+
+        // Base Layer: Encrypt with the AEAD backend; the header is authenticated
+        let ciphertext = self.cipher.encrypt(&nonce_bytes, mixed_data.as_ref(), &result)?;
         result.extend_from_slice(&ciphertext);
 
         Ok(result)
     }
 
+    /// XOR `data` with the ChaCha20 keystream at a freshly reserved offset
+    ///
+    /// Returns the offset, or `None` (leaving `data` as is) without swarm entropy.
+    #[cfg(feature = "drand")]
+    fn apply_fallback_keystream(&self, data: &mut [u8]) -> Option<u64> {
+        if !self.has_swarm_entropy {
+            return None;
+        }
+        let offset = self.key_offset.fetch_add(data.len() as u64, Ordering::SeqCst);
+        self.keystream.apply(offset, data);
+        Some(offset)
+    }
+
     /// Decrypt data encrypted with TRUE Vernam mode
+    #[cfg(feature = "drand")]
     pub fn decrypt_true_vernam(&self, data: &[u8]) -> Result<Vec<u8>, AeadError> {
         if data.len() < 12 + 1 + 16 {
             return Err(AeadError);
         }
 
-        let nonce = Nonce::from_slice(&data[0..12]);
+        let nonce = &data[0..12];
         let mode = data[12];
+        let header_len = if mode == 0x02 { 12 + 1 + 8 } else { 12 + 1 };
+        if data.len() < header_len + 16 {
+            return Err(AeadError);
+        }
         
         // CRITICAL: For true OTP, never extract XOR key from ciphertext - use synchronized entropy!
-        let ciphertext = &data[13..];
+        let (header, ciphertext) = data.split_at(header_len);
 
-        // Base Layer: Decrypt with ChaCha20-Poly1305
-        let payload = Zeroizing::new(self.cipher.decrypt(nonce, ciphertext)?);
+        // Base Layer: Decrypt with the AEAD backend
+        let payload = Zeroizing::new(self.cipher.decrypt(nonce, ciphertext, header)?);
 
         // Extract and reverse XOR based on mode
         let plaintext: Vec<u8> = match mode {
@@ -509,9 +1297,11 @@ impl WasifVernam {
                 }
             }
             0x02 => {
-                // HKDF fallback mode
-                warn!("⚠️ HKDF fallback mode detected");
-                payload.to_vec()
+                // Keystream mode: remove the XOR at the offset the sender used
+                let offset = u64::from_be_bytes(header[13..21].try_into().map_err(|_| AeadError)?);
+                let mut result = payload.to_vec();
+                self.keystream.apply(offset, &mut result);
+                result
             }
             _ => {
                 // No XOR layer
@@ -523,6 +1313,7 @@ impl WasifVernam {
     }
 
     /// Fetch swarm entropy seed from zks-vernam worker
+    #[cfg(feature = "fetcher")]
     pub async fn fetch_remote_key(
         &mut self,
         vernam_url: &str,
@@ -541,8 +1332,8 @@ impl WasifVernam {
         // Hash to get seed
         let mut hasher = Sha256::new();
         hasher.update(&entropy);
-        self.swarm_seed = Zeroizing::new(hasher.finalize().into());
-        self.has_swarm_entropy = true;
+        self.begin_epoch(None);
+        self.set_swarm_seed(hasher.finalize().into());
         self.key_offset.store(0, Ordering::SeqCst);
 
         info!("Fetched Swarm Entropy seed from worker - Infinite Vernam active!");
@@ -554,8 +1345,8 @@ impl WasifVernam {
         if !key.is_empty() {
             let mut hasher = Sha256::new();
             hasher.update(&key);
-            self.swarm_seed = Zeroizing::new(hasher.finalize().into());
-            self.has_swarm_entropy = true;
+            self.begin_epoch(None);
+            self.set_swarm_seed(hasher.finalize().into());
             self.key_offset.store(0, Ordering::SeqCst);
             info!(
                 "Applied {} bytes of Swarm Entropy - Infinite Vernam active!",
@@ -606,9 +1397,9 @@ impl WasifVernam {
             return;
         }
 
-        // Update seed (old seed is now unreachable - forward secrecy!)
-        self.swarm_seed = Zeroizing::new(new_seed);
-        self.has_swarm_entropy = true;
+        // Update seed (old seed is dropped once the grace window ends - forward secrecy!)
+        self.begin_epoch(None);
+        self.set_swarm_seed(new_seed);
 
         info!(
             "🔄 Refreshed swarm entropy - Forward secrecy checkpoint! (generation: {})",
@@ -621,6 +1412,173 @@ impl WasifVernam {
         self.key_offset.load(Ordering::SeqCst)
     }
 
+    /// Get the current key epoch (bumped whenever the swarm seed or AEAD key changes)
+    pub fn get_key_epoch(&self) -> u32 {
+        self.key_epoch.load(Ordering::SeqCst)
+    }
+
+    /// Capture the session for crash-safe resumption
+    ///
+    /// The snapshot reserves the next [`SESSION_NONCE_RESERVE`] nonces and
+    /// [`SESSION_OFFSET_RESERVE`] keystream bytes; seal and persist it with
+    /// [`SessionState::seal`] before sending anything else.
+    pub fn snapshot(&mut self) -> SessionState {
+        let key_epoch = self.key_epoch.load(Ordering::SeqCst);
+        let nonce_counter = self.nonce_counter.load(Ordering::SeqCst).saturating_add(SESSION_NONCE_RESERVE);
+        let key_offset = self.key_offset.load(Ordering::SeqCst).saturating_add(SESSION_OFFSET_RESERVE);
+        let sync_position = self
+            .sync_position()
+            .map(|position| position.saturating_add(SESSION_OFFSET_RESERVE));
+        self.snapshot_sequence += 1;
+        self.snapshot_reserve = Some(SnapshotReserve {
+            key_epoch,
+            nonce_counter,
+            key_offset,
+            sync_position,
+        });
+
+        SessionState {
+            sequence: self.snapshot_sequence,
+            algorithm: self.cipher.algorithm(),
+            aead_key: *self.aead_key,
+            key_epoch,
+            nonce_counter,
+            key_offset,
+            swarm_seed: self.has_swarm_entropy.then(|| *self.swarm_seed),
+            replay: self.anti_replay.export_state(),
+            chain: self.key_chain.as_ref().map(|chain| chain.export_state()),
+            #[cfg(not(feature = "drand"))]
+            sync_buffer: None,
+            #[cfg(feature = "drand")]
+            sync_buffer: self.synchronized_buffer.as_ref().zip(sync_position).map(|(buffer, position)| {
+                let (shared_seed, starting_round, is_alice) = buffer.seed_round_and_role();
+                SyncBufferState {
+                    shared_seed,
+                    starting_round,
+                    is_alice,
+                    position,
+                }
+            }),
+            key_commitment: self.key_commitment,
+        }
+    }
+
+    /// Resume a session from a snapshot
+    ///
+    /// The restored cipher always [`needs_snapshot`](Self::needs_snapshot) and refuses to
+    /// encrypt until a new snapshot is taken: persist it before sending, so a second
+    /// crash cannot reuse the range.
+    ///
+    /// Scrambling, the padding policy, a one-time pad, the PQ ratchet and any pending
+    /// key update are not in the snapshot and must be re-applied before use. A TRUE OTP
+    /// buffer fetches drand rounds from mainnet; use
+    /// [`restore_with_drand`](Self::restore_with_drand) for any other chain.
+    ///
+    /// Fails if the snapshot's replay window is larger than [`MAX_WINDOW_SIZE`].
+    pub fn restore(state: SessionState) -> Result<Self, AeadError> {
+        if state.replay.window_size > MAX_WINDOW_SIZE {
+            warn!("Snapshot replay window of {} PIDs exceeds the {} limit", state.replay.window_size, MAX_WINDOW_SIZE);
+            return Err(AeadError);
+        }
+        let window = Box::new(BitmapWindow::new(state.replay.window_size));
+        Self::restore_with_replay_window(state, window)
+    }
+
+    /// Resume a session from a snapshot, fetching TRUE OTP rounds through `drand_client`
+    ///
+    /// Like [`restore`](Self::restore); `drand_client` must be on the chain the
+    /// session's starting round was agreed on.
+    #[cfg(feature = "drand")]
+    pub fn restore_with_drand(mut state: SessionState, drand_client: Arc<DrandEntropy>) -> Result<Self, AeadError> {
+        let sync_buffer = state.sync_buffer.take();
+        let mut cipher = Self::restore(state)?;
+        cipher.synchronized_buffer = sync_buffer.map(|sync| {
+            Arc::new(SynchronizedVernamBuffer::resume(
+                sync.shared_seed,
+                sync.starting_round,
+                sync.is_alice,
+                sync.position,
+                drand_client,
+            ))
+        });
+        Ok(cipher)
+    }
+
+    /// Resume a session from a snapshot into a custom replay window
+    ///
+    /// Like [`restore`](Self::restore), but the recorded PIDs are loaded into `window`,
+    /// which later key epochs start fresh copies of (see
+    /// [`set_replay_window`](Self::set_replay_window)).
+    pub fn restore_with_replay_window(mut state: SessionState, window: Box<dyn ReplayWindow>) -> Result<Self, AeadError> {
+        let mut cipher = Self::with_algorithm(state.aead_key, state.algorithm)?;
+        cipher.key_epoch.store(state.key_epoch, Ordering::SeqCst);
+        cipher.nonce_counter.store(state.nonce_counter, Ordering::SeqCst);
+        cipher.key_offset.store(state.key_offset, Ordering::SeqCst);
+        if let Some(seed) = state.swarm_seed {
+            cipher.set_swarm_seed(seed);
+        }
+        cipher.anti_replay = Arc::new(AntiReplayContainer::import_state_into(&state.replay, window));
+        cipher.key_chain = state.chain.take().map(RecursiveChain::import_state);
+        #[cfg(feature = "drand")]
+        {
+            cipher.synchronized_buffer = state.sync_buffer.as_ref().map(|sync| {
+                Arc::new(SynchronizedVernamBuffer::resume(
+                    sync.shared_seed,
+                    sync.starting_round,
+                    sync.is_alice,
+                    sync.position,
+                    Arc::new(DrandEntropy::new()),
+                ))
+            });
+        }
+        #[cfg(not(feature = "drand"))]
+        if state.sync_buffer.is_some() {
+            warn!("Snapshot carries a TRUE OTP buffer, which needs the `drand` feature");
+            return Err(AeadError);
+        }
+        cipher.key_commitment = state.key_commitment;
+        cipher.snapshot_sequence = state.sequence;
+
+        info!("Resumed session from snapshot {} (key epoch {})", state.sequence, state.key_epoch);
+        Ok(cipher)
+    }
+
+    /// Check if the last snapshot no longer covers the counters in use
+    ///
+    /// Once a session has been snapshotted or restored, encryption fails while this
+    /// holds, until the next [`snapshot`](Self::snapshot).
+    pub fn needs_snapshot(&self) -> bool {
+        match self.snapshot_reserve {
+            Some(ref reserve) => {
+                reserve.key_epoch != self.key_epoch.load(Ordering::SeqCst)
+                    || self.nonce_counter.load(Ordering::SeqCst) >= reserve.nonce_counter
+                    || self.key_offset.load(Ordering::SeqCst) >= reserve.key_offset
+                    || reserve
+                        .sync_position
+                        .zip(self.sync_position())
+                        .is_some_and(|(limit, position)| position >= limit)
+            }
+            None => true,
+        }
+    }
+
+    /// Whether the last snapshot covers one more message of `data_len` bytes
+    fn within_snapshot_reserve(&self, data_len: usize) -> bool {
+        let Some(ref reserve) = self.snapshot_reserve else {
+            return false;
+        };
+        let data_len = data_len as u64;
+        let fits = |position: u64, limit: u64| position.checked_add(data_len).is_some_and(|end| end <= limit);
+        reserve.key_epoch == self.key_epoch.load(Ordering::SeqCst)
+            && self.nonce_counter.load(Ordering::SeqCst) < reserve.nonce_counter
+            && fits(self.key_offset.load(Ordering::SeqCst), reserve.key_offset)
+            && reserve
+                .sync_position
+                .zip(self.sync_position())
+                .into_iter()
+                .all(|(limit, position)| fits(position, limit))
+    }
+
     /// Check if entropy refresh is recommended (e.g., after 1MB of traffic)
     pub fn needs_refresh(&self) -> bool {
         const REFRESH_THRESHOLD: u64 = 1024 * 1024; // 1MB
@@ -628,9 +1586,52 @@ impl WasifVernam {
     }
 }
 
+/// Draw a fresh 32-byte chain contribution
+fn fresh_contribution_entropy() -> Result<Zeroizing<[u8; 32]>, RekeyError> {
+    let mut entropy = Zeroizing::new([0u8; 32]);
+    rng::fill(&mut *entropy).map_err(|_| {
+        warn!("RNG unavailable during key update");
+        RekeyError::Rng
+    })?;
+    Ok(entropy)
+}
+
+/// Generate the ML-KEM keypair of a PQ ratchet request
+fn ratchet_keypair() -> Result<MlKemKeypair, RekeyError> {
+    let mut rng = FillRng::default();
+    let keypair = MlKem::generate_keypair_with_rng(&mut rng).map_err(|_| RekeyError::Kem)?;
+    rng.finish().map_err(|_| {
+        warn!("RNG unavailable during key update");
+        RekeyError::Rng
+    })?;
+    Ok(keypair)
+}
+
+/// Encapsulate to the peer's ML-KEM key in a PQ ratchet response
+fn ratchet_encapsulate(public_key: &[u8]) -> Result<MlKemEncapsulation, RekeyError> {
+    let mut rng = FillRng::default();
+    let encapsulation = MlKem::encapsulate_with_rng(public_key, &mut rng).map_err(|_| RekeyError::Kem)?;
+    rng.finish().map_err(|_| {
+        warn!("RNG unavailable during key update");
+        RekeyError::Rng
+    })?;
+    Ok(encapsulation)
+}
+
+/// Run `f` with the AEAD associated data: envelope header followed by caller AAD
+fn with_envelope_aad<R>(header: &[u8], aad: &[u8], f: impl FnOnce(&[u8]) -> R) -> R {
+    if aad.is_empty() {
+        return f(header);
+    }
+    let mut full_aad = Vec::with_capacity(header.len() + aad.len());
+    full_aad.extend_from_slice(header);
+    full_aad.extend_from_slice(aad);
+    f(&full_aad)
+}
+
 impl Drop for WasifVernam {
     fn drop(&mut self) {
-        // Zeroize the cipher state (this is handled by ChaCha20Poly1305's Drop)
+        // Zeroize the cipher state (this is handled by the AEAD backend's Drop)
         // Zeroize the nonce counter to prevent timing analysis
         self.nonce_counter.store(0, Ordering::SeqCst);
         self.key_offset.store(0, Ordering::SeqCst);
@@ -639,7 +1640,7 @@ impl Drop for WasifVernam {
         
         // Note: swarm_seed is already Zeroizing<[u8; 32]>, so it will auto-zeroize
         // Note: true_vernam_buffer contains TrueVernamBuffer which has its own Drop impl
-        // Note: scrambler contains only permutation maps (not sensitive cryptographic material)
+        // Note: scrambler keys are Zeroizing and clear themselves on drop
         
         info!("🔒 WasifVernam cipher zeroized - all sensitive data cleared");
     }
@@ -651,10 +1652,12 @@ impl Drop for WasifVernam {
 /// - Every 30 seconds (or after 1MB traffic), fetch fresh entropy from swarm/worker
 /// - Mix into existing seed using refresh_entropy()
 /// - Provides forward secrecy: past traffic is unrecoverable even if current seed leaks
+#[cfg(feature = "std")]
 pub struct ContinuousEntropyRefresher {
     cipher: Arc<Mutex<WasifVernam>>,
 }
 
+#[cfg(feature = "std")]
 impl ContinuousEntropyRefresher {
     /// Create a new continuous entropy refresher
     pub fn new(cipher: Arc<Mutex<WasifVernam>>) -> Self {
@@ -683,7 +1686,7 @@ impl ContinuousEntropyRefresher {
         // Use local CSPRNG instead of fetching from worker
         // (TrueVernamFetcher already handles worker+swarm mixing)
         let mut fresh_entropy = [0u8; 32];
-        getrandom::getrandom(&mut fresh_entropy).map_err(|e| format!("getrandom failed: {}", e))?;
+        rng::fill(&mut fresh_entropy).map_err(|e| format!("RNG failed: {}", e))?;
 
         // Refresh the cipher's seed with LOCAL fresh entropy
         {
@@ -697,6 +1700,7 @@ impl ContinuousEntropyRefresher {
 }
 
 /// Keep the old name as an alias for backward compatibility
+#[cfg(feature = "std")]
 pub type EntropyTaxPayer = ContinuousEntropyRefresher;
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -708,6 +1712,7 @@ pub type EntropyTaxPayer = ContinuousEntropyRefresher;
 mod unbreakability_tests {
     use super::*;
     use std::collections::HashSet;
+    #[cfg(feature = "drand")]
     use crate::true_vernam::SynchronizedVernamBuffer;
     
     // ═══════════════════════════════════════════════════════════════════════════
@@ -716,6 +1721,7 @@ mod unbreakability_tests {
     // This is the core of OTP security - NO key transmission required
     // ═══════════════════════════════════════════════════════════════════════════
     #[test]
+    #[cfg(feature = "drand")]
     fn test_synchronized_keystream_identical() {
         let seed = [42u8; 32];
         let alice = SynchronizedVernamBuffer::new(seed);
@@ -770,6 +1776,7 @@ mod unbreakability_tests {
     // Proves: Generated keystream passes basic randomness checks
     // ═══════════════════════════════════════════════════════════════════════════
     #[test]
+    #[cfg(feature = "drand")]
     fn test_entropy_quality() {
         let mut entropy = [0u8; 32];
         getrandom::getrandom(&mut entropy).expect("RNG failed");
@@ -839,7 +1846,8 @@ mod unbreakability_tests {
         // Store initial offset
         let offset_before = cipher.get_key_offset();
         
-        // Encrypt many messages to trigger key rotation (every 1000 messages)
+        // Encrypt enough messages to call for a key update
+        assert!(!cipher.needs_rekey());
         for i in 0..1005 {
             let msg = format!("Message number {}", i);
             let _ = cipher.encrypt(msg.as_bytes()).expect("Encryption failed");
@@ -850,10 +1858,12 @@ mod unbreakability_tests {
         // Offset should have advanced significantly
         assert!(offset_after > offset_before, "Key offset must advance");
         
-        // Key rotation resets nonce counter - verify it's reasonable
-        // (Can't directly access nonce counter, but encrypt should work)
-        let test_msg = cipher.encrypt(b"test").expect("Post-rotation encrypt failed");
-        assert!(!test_msg.is_empty(), "Post-rotation encryption must work");
+        // Rotation is negotiated with the peer, not done unilaterally
+        assert!(cipher.needs_rekey(), "Key update must be due after 1000 messages");
+        let update = cipher.initiate_rekey().expect("Key update request failed");
+        assert!(WasifVernam::is_key_update(&update));
+        assert!(!cipher.needs_rekey(), "No second request while one is pending");
+        assert_eq!(cipher.initiate_rekey(), Err(RekeyError::InProgress));
     }
     
     // ═══════════════════════════════════════════════════════════════════════════
@@ -872,11 +1882,9 @@ mod unbreakability_tests {
         for i in 0..10_000 {
             let ciphertext = cipher.encrypt(message).expect("Encryption failed");
             
-            // First 12 bytes are the nonce
-            let nonce: [u8; 12] = ciphertext.get(..12)
-                .expect("Ciphertext too short for nonce")
-                .try_into()
-                .expect("Nonce conversion failed");
+            let nonce = EnvelopeHeader::parse(&ciphertext)
+                .expect("Envelope header must parse")
+                .nonce;
             
             assert!(seen_nonces.insert(nonce), 
                 "CRITICAL SECURITY FAILURE: Nonce reused at message {}!", i);
@@ -946,6 +1954,7 @@ mod unbreakability_tests {
     // Proves: Synchronized buffer produces deterministic keystream for OTP
     // ═══════════════════════════════════════════════════════════════════════════
     #[test]
+    #[cfg(feature = "drand")]
     fn test_synchronized_vernam_keystream() {
         let shared_seed = [0xAB; 32];
         
@@ -1002,6 +2011,7 @@ mod unbreakability_tests {
     // Proves: Synchronization works with any random seed, not just test values
     // ═══════════════════════════════════════════════════════════════════════════
     #[test]
+    #[cfg(feature = "drand")]
     fn test_synchronized_keystream_random_seeds() {
         // Test with 10 different random seeds
         for iteration in 0..10 {
@@ -1059,6 +2069,245 @@ mod unbreakability_tests {
             assert!(!plain_cipher.has_swarm_entropy(), "Plain cipher should not have swarm entropy");
         }
     }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // TEST 13: TRUE OTP OVER A LOSSY, REORDERING TRANSPORT
+    // Proves: Envelopes decrypt in any order and keystream is never used twice
+    // ═══════════════════════════════════════════════════════════════════════════
+    #[test]
+    #[cfg(feature = "drand")]
+    fn test_true_otp_survives_loss_and_reordering() {
+        let key = [0x44; 32];
+        let seed = [0x55; 32];
+        let mut alice = WasifVernam::new(key).unwrap();
+        let mut bob = WasifVernam::new(key).unwrap();
+        alice.set_remote_key(vec![0x66; 32]);
+        bob.set_remote_key(vec![0x66; 32]);
+        alice.enable_synchronized_vernam_with_role(seed, true);
+        bob.enable_synchronized_vernam_with_role(seed, false);
+
+        let _lost = alice.encrypt(b"never arrives").unwrap();
+        let second = alice.encrypt(b"second").unwrap();
+        let third = alice.encrypt(b"third").unwrap();
+        let reply = bob.encrypt(b"sent concurrently").unwrap();
+        assert_eq!(EnvelopeHeader::parse(&second).unwrap().mode, Some(CipherMode::TrueOtp));
+
+        assert_eq!(bob.decrypt(&third).unwrap(), b"third".to_vec());
+        assert_eq!(bob.decrypt(&second).unwrap(), b"second".to_vec());
+        assert_eq!(alice.decrypt(&reply).unwrap(), b"sent concurrently".to_vec());
+
+        // An authentic envelope from our own lane is refused
+        let mut mirror = WasifVernam::new(key).unwrap();
+        mirror.set_remote_key(vec![0x66; 32]);
+        mirror.enable_synchronized_vernam_with_role(seed, false);
+        let overlapping = mirror.encrypt(b"same lane").unwrap();
+        assert!(bob.decrypt(&overlapping).is_err(), "Keystream reuse must be rejected");
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // TEST 14: PRE-SHARED ONE-TIME PAD
+    // Proves: Pad bytes are used once, out of order, and encryption stops when exhausted
+    // ═══════════════════════════════════════════════════════════════════════════
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_one_time_pad_mode() {
+        let mut id = [0u8; 8];
+        getrandom::getrandom(&mut id).unwrap();
+        let alice_path = std::env::temp_dir().join(format!("zks-wasif-pad-{}-alice", hex::encode(id)));
+        let bob_path = std::env::temp_dir().join(format!("zks-wasif-pad-{}-bob", hex::encode(id)));
+        PadFile::generate(&alice_path, 256).unwrap();
+        std::fs::copy(&alice_path, &bob_path).unwrap();
+
+        let key = [0x77; 32];
+        let mut alice = WasifVernam::new(key).unwrap();
+        let mut bob = WasifVernam::new(key).unwrap();
+        alice.enable_one_time_pad(Arc::new(PadFile::open(&alice_path, true).unwrap()));
+        bob.enable_one_time_pad(Arc::new(PadFile::open(&bob_path, false).unwrap()));
+
+        let first = alice.encrypt(&[0xAA; 60]).unwrap();
+        let second = alice.encrypt(b"second").unwrap();
+        let reply = bob.encrypt(b"reply").unwrap();
+        let header = EnvelopeHeader::parse(&second).unwrap();
+        assert_eq!((header.mode, header.key_offset), (Some(CipherMode::PadOtp), 60));
+
+        assert_eq!(bob.decrypt(&second).unwrap(), b"second".to_vec());
+        assert_eq!(bob.decrypt(&first).unwrap(), vec![0xAA; 60]);
+        assert_eq!(alice.decrypt(&reply).unwrap(), b"reply".to_vec());
+
+        // 62 of Alice's 128 pad bytes are left
+        assert!(alice.encrypt(&[0u8; 63]).is_err(), "Exhausted pad must refuse to encrypt");
+        assert!(alice.encrypt(&[0u8; 62]).is_ok());
+
+        for path in [&alice_path, &bob_path] {
+            let mut state = path.clone().into_os_string();
+            state.push(".state");
+            let _ = std::fs::remove_file(path);
+            let _ = std::fs::remove_file(state);
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // TEST 15: LENGTH-HIDING PADDING
+    // Proves: Envelopes of different plaintext sizes are indistinguishable by length
+    // ═══════════════════════════════════════════════════════════════════════════
+    #[test]
+    fn test_padding_hides_lengths() {
+        let key = [0x88; 32];
+        let mut sender = WasifVernam::new(key).unwrap();
+        let mut receiver = WasifVernam::new(key).unwrap();
+        sender.set_remote_key(vec![0x99; 32]);
+        receiver.set_remote_key(vec![0x99; 32]);
+        sender.set_padding_policy(PaddingPolicy::Buckets(vec![128, 512]));
+
+        let short = sender.encrypt(b"hi").unwrap();
+        let longer = sender.encrypt(&[0x11; 80]).unwrap();
+        let large = sender.encrypt(&[0x22; 200]).unwrap();
+        assert_eq!((short.len(), longer.len(), large.len()), (128, 128, 512));
+        assert_eq!(sender.sealed_len(80), 128);
+        assert!(EnvelopeHeader::parse(&short).unwrap().has_flag(FLAG_PADDED));
+
+        // The receiver strips padding without knowing the policy
+        assert_eq!(receiver.decrypt(&short).unwrap(), b"hi".to_vec());
+        assert_eq!(receiver.decrypt(&large).unwrap(), vec![0x22; 200]);
+        let mut in_place = BytesMut::from(&longer[..]);
+        receiver.decrypt_in_place(&mut in_place).unwrap();
+        assert_eq!(&in_place[..], &[0x11; 80][..]);
+
+        // In-place encryption pads the same way
+        sender.set_padding_policy(PaddingPolicy::Mtu(300));
+        let mut buf = BytesMut::from(&[0u8; ENVELOPE_HEADER_LEN][..]);
+        buf.extend_from_slice(b"in place");
+        sender.encrypt_in_place(&mut buf).unwrap();
+        assert_eq!(buf.len(), 300);
+        assert_eq!(receiver.decrypt(&buf).unwrap(), b"in place".to_vec());
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // TEST 16: PER-PACKET SCRAMBLING
+    // Proves: Every packet is scrambled regardless of length, under its own nonce
+    // ═══════════════════════════════════════════════════════════════════════════
+    #[test]
+    fn test_scrambling_applies_to_every_packet() {
+        let key = [0x77; 32];
+        let mut sender = WasifVernam::new(key).unwrap();
+        let mut receiver = WasifVernam::new(key).unwrap();
+        sender.enable_scrambling();
+        receiver.enable_scrambling();
+
+        for len in [0, 1, 37, 256, 1500, 70_000] {
+            let plaintext: Vec<u8> = (0..len).map(|i| (i % 253) as u8).collect();
+            let sealed = sender.encrypt(&plaintext).unwrap();
+            let header = EnvelopeHeader::parse(&sealed).unwrap();
+            assert!(header.has_flag(FLAG_SCRAMBLED), "length {} not scrambled", len);
+            assert_eq!(receiver.decrypt(&sealed).unwrap(), plaintext);
+        }
+
+        // The permutation is only reversible with the session key
+        let sealed = sender.encrypt(b"needs the scrambler").unwrap();
+        let plain_receiver = WasifVernam::new(key).unwrap();
+        assert!(plain_receiver.decrypt(&sealed).is_err());
+        let other_key = {
+            let mut other = WasifVernam::new([0x78; 32]).unwrap();
+            other.enable_scrambling();
+            other
+        };
+        assert!(other_key.decrypt(&sealed).is_err());
+        assert_eq!(receiver.decrypt(&sealed).unwrap(), b"needs the scrambler".to_vec());
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // TEST 17: KEY COMMITMENT
+    // Proves: Committed envelopes open under one key only, and committing peers
+    // refuse envelopes without a commitment
+    // ═══════════════════════════════════════════════════════════════════════════
+    #[test]
+    fn test_key_commitment() {
+        let key = [0x66; 32];
+        let mut sender = WasifVernam::new_multi_recipient(key).unwrap();
+        let receiver = WasifVernam::new_multi_recipient(key).unwrap();
+        assert!(sender.key_commitment());
+        assert_eq!(sender.envelope_overhead(), ENVELOPE_OVERHEAD + COMMITMENT_LEN);
+
+        let sealed = sender.encrypt_with_aad(b"room message", b"room-42").unwrap();
+        assert_eq!(sealed.len(), sender.sealed_len(12));
+        assert!(EnvelopeHeader::parse(&sealed).unwrap().has_flag(FLAG_COMMITTED));
+        assert_eq!(receiver.decrypt_with_aad(&sealed, b"room-42").unwrap(), b"room message".to_vec());
+
+        // A tampered commitment is refused before the AEAD runs
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(receiver.decrypt_with_aad(&tampered, b"room-42").is_err());
+
+        // The flag, not local configuration, decides how an envelope is parsed
+        let plain = WasifVernam::new(key).unwrap();
+        let sealed = sender.encrypt(b"to a plain peer").unwrap();
+        assert_eq!(plain.decrypt(&sealed).unwrap(), b"to a plain peer".to_vec());
+
+        // Committing peers refuse uncommitted envelopes
+        let mut plain_sender = WasifVernam::new(key).unwrap();
+        let uncommitted = plain_sender.encrypt(b"no commitment").unwrap();
+        assert!(receiver.decrypt(&uncommitted).is_err());
+
+        // Snapshots keep the requirement
+        let restored = WasifVernam::restore(sender.snapshot()).unwrap();
+        assert!(restored.key_commitment());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_password_ciphers_always_commit() {
+        let salt = b"per-room salt 16";
+        assert!(WasifVernam::from_password(b"correct horse", b"short").is_err());
+
+        let mut alice = WasifVernam::from_password(b"correct horse", salt).unwrap();
+        let bob = WasifVernam::from_password(b"correct horse", salt).unwrap();
+        let mallory = WasifVernam::from_password(b"battery staple", salt).unwrap();
+        assert!(alice.key_commitment());
+
+        let sealed = alice.encrypt(b"password protected").unwrap();
+        assert!(EnvelopeHeader::parse(&sealed).unwrap().has_flag(FLAG_COMMITTED));
+        assert_eq!(bob.decrypt(&sealed).unwrap(), b"password protected".to_vec());
+        assert!(mallory.decrypt(&sealed).is_err());
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // TEST 18: SEEKABLE KEYSTREAM
+    // Proves: The XOR layer handles messages past HKDF's 8160-byte limit, and
+    // envelopes sealed with the old HKDF keystream still open
+    // ═══════════════════════════════════════════════════════════════════════════
+    #[test]
+    fn test_keystream_handles_large_messages() {
+        let key = [0x77; 32];
+        let mut sender = WasifVernam::new(key).unwrap();
+        let mut receiver = WasifVernam::new(key).unwrap();
+        sender.set_remote_key(vec![4u8; 32]);
+        receiver.set_remote_key(vec![4u8; 32]);
+
+        let large = vec![0xA5u8; 100_000];
+        let sealed = sender.encrypt(&large).unwrap();
+        assert_eq!(EnvelopeHeader::parse(&sealed).unwrap().mode, Some(CipherMode::StreamXor));
+        assert_eq!(receiver.decrypt(&sealed).unwrap(), large);
+        let sealed = sender.encrypt(b"after a large one").unwrap();
+        assert_eq!(EnvelopeHeader::parse(&sealed).unwrap().key_offset, 100_000);
+        assert_eq!(receiver.decrypt(&sealed).unwrap(), b"after a large one".to_vec());
+
+        // Envelope from a peer still on the HKDF keystream
+        let offset = 300;
+        let header = EnvelopeHeader::new(CipherMode::HkdfXor, 0, receiver.get_key_epoch(), [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9], offset);
+        let mut body = b"legacy keystream".to_vec();
+        let keystream = hkdf_keystream(&receiver.get_swarm_seed(), offset, body.len()).unwrap();
+        for (byte, k) in body.iter_mut().zip(keystream.iter()) {
+            *byte ^= k;
+        }
+        let mut envelope = header.to_bytes();
+        let tag = AeadBackend::new(AeadAlgorithm::ChaCha20Poly1305, &key)
+            .unwrap()
+            .encrypt_in_place_detached(header.aead_nonce(), &envelope, &mut body)
+            .unwrap();
+        envelope.extend_from_slice(&body);
+        envelope.extend_from_slice(&tag);
+        assert_eq!(receiver.decrypt(&envelope).unwrap(), b"legacy keystream".to_vec());
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -1113,4 +2362,492 @@ mod post_quantum_tests {
         // Note: Decryption after entropy refresh requires synchronized state
         // between sender and receiver - this is by design for forward secrecy
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod in_place_tests {
+    use super::*;
+
+    #[test]
+    fn test_in_place_slice_matches_encrypt() {
+        let key = [7u8; 32];
+        let mut a = WasifVernam::new(key).unwrap();
+        let mut b = WasifVernam::new(key).unwrap();
+        a.set_remote_key(vec![9u8; 32]);
+        b.set_remote_key(vec![9u8; 32]);
+
+        for msg in [&b"first"[..], b"second message", b""] {
+            let expected = a.encrypt(msg).unwrap();
+
+            let mut buf = vec![0u8; ENVELOPE_OVERHEAD + msg.len()];
+            buf[ENVELOPE_HEADER_LEN..ENVELOPE_HEADER_LEN + msg.len()].copy_from_slice(msg);
+            b.encrypt_in_place_slice(&mut buf).unwrap();
+
+            assert_eq!(buf, expected, "In-place envelope must be byte-identical");
+        }
+    }
+
+    #[test]
+    fn test_failed_in_place_encrypt_wipes_plaintext() {
+        let mut cipher = WasifVernam::new([12u8; 32]).unwrap();
+        cipher.snapshot();
+        cipher.nonce_counter.store(cipher.nonce_counter.load(Ordering::SeqCst) + SESSION_NONCE_RESERVE, Ordering::SeqCst);
+
+        let msg = b"must not linger";
+        let mut buf = BytesMut::new();
+        buf.resize(ENVELOPE_HEADER_LEN, 0);
+        buf.extend_from_slice(msg);
+        assert!(cipher.encrypt_in_place(&mut buf).is_err());
+        assert_eq!(buf.len(), ENVELOPE_HEADER_LEN + msg.len());
+        assert!(buf.iter().all(|&b| b == 0), "Plaintext must be wiped on failure");
+
+        let mut slice = vec![0u8; ENVELOPE_OVERHEAD + msg.len()];
+        slice[ENVELOPE_HEADER_LEN..ENVELOPE_HEADER_LEN + msg.len()].copy_from_slice(msg);
+        assert!(cipher.encrypt_in_place_slice(&mut slice).is_err());
+        assert!(slice.iter().all(|&b| b == 0), "Plaintext must be wiped on failure");
+    }
+
+    #[test]
+    fn test_bytes_mut_roundtrip() {
+        let key = [11u8; 32];
+        let mut sender = WasifVernam::new(key).unwrap();
+        let receiver = WasifVernam::new(key).unwrap();
+
+        let msg = b"zero-copy relay payload";
+        let mut buf = BytesMut::with_capacity(ENVELOPE_OVERHEAD + msg.len());
+        buf.resize(ENVELOPE_HEADER_LEN, 0);
+        buf.extend_from_slice(msg);
+        let capacity = buf.capacity();
+
+        sender.encrypt_in_place(&mut buf).unwrap();
+        assert_eq!(buf.len(), ENVELOPE_OVERHEAD + msg.len());
+        assert_eq!(buf.capacity(), capacity, "Reserved tailroom must avoid reallocation");
+
+        // The envelope is also accepted by the allocating API
+        let copy = buf.clone();
+        let other = WasifVernam::new(key).unwrap();
+        assert_eq!(other.decrypt(&copy).unwrap(), msg.to_vec());
+
+        receiver.decrypt_in_place(&mut buf).unwrap();
+        assert_eq!(&buf[..], msg);
+    }
+
+    #[test]
+    fn test_in_place_rejects_missing_room_and_tampering() {
+        let key = [13u8; 32];
+        let mut cipher = WasifVernam::new(key).unwrap();
+
+        let mut short = [0u8; ENVELOPE_OVERHEAD - 1];
+        assert!(cipher.encrypt_in_place_slice(&mut short).is_err());
+
+        let mut buf = vec![0u8; ENVELOPE_OVERHEAD + 4];
+        buf[ENVELOPE_HEADER_LEN..ENVELOPE_HEADER_LEN + 4].copy_from_slice(b"data");
+        cipher.encrypt_in_place_slice(&mut buf).unwrap();
+        buf[ENVELOPE_HEADER_LEN] ^= 0x80;
+        assert!(cipher.decrypt_in_place_slice(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_envelope_records_mode_at_offset_zero() {
+        let key = [17u8; 32];
+        let mut sender = WasifVernam::new(key).unwrap();
+        let mut receiver = WasifVernam::new(key).unwrap();
+        sender.set_remote_key(vec![3u8; 32]);
+        receiver.set_remote_key(vec![3u8; 32]);
+
+        // The first message sits at keystream offset 0 but is still XOR'd
+        let ct = sender.encrypt(b"first message").unwrap();
+        let header = EnvelopeHeader::parse(&ct).unwrap();
+        assert_eq!(header.mode, Some(CipherMode::StreamXor));
+        assert_eq!(header.key_offset, 0);
+        assert_eq!(header.key_epoch, sender.get_key_epoch());
+        assert_eq!(receiver.decrypt(&ct).unwrap(), b"first message".to_vec());
+
+        // A receiver two epochs ahead refuses instead of returning garbage
+        receiver.refresh_entropy(&[5u8; 32]);
+        receiver.refresh_entropy(&[6u8; 32]);
+        let ct = sender.encrypt(b"second").unwrap();
+        assert!(receiver.decrypt(&ct).is_err());
+    }
+
+    #[test]
+    fn test_forged_envelope_does_not_burn_packet_id() {
+        let key = [29u8; 32];
+        let mut sender = WasifVernam::new(key).unwrap();
+        let mut receiver = WasifVernam::new(key).unwrap();
+        receiver.set_replay_window(Box::new(crate::anti_replay::BitmapWindow::new(64)));
+
+        let ct = sender.encrypt(b"genuine").unwrap();
+        let mut forged = ct.clone();
+        let last = forged.len() - 1;
+        forged[last] ^= 0x01;
+        assert!(receiver.decrypt(&forged).is_err());
+
+        assert_eq!(receiver.decrypt(&ct).unwrap(), b"genuine".to_vec());
+        assert!(receiver.decrypt(&ct).is_err(), "Replay must be rejected");
+    }
+
+    #[cfg(feature = "drand")]
+    #[test]
+    fn test_true_vernam_keystream_mode_roundtrip() {
+        let key = [37u8; 32];
+        let mut sender = WasifVernam::new(key).unwrap();
+        let mut receiver = WasifVernam::new(key).unwrap();
+        sender.set_remote_key(vec![4u8; 32]);
+        receiver.set_remote_key(vec![4u8; 32]);
+
+        for message in [&b"short message"[..], &[0x5Au8; 100][..], &[0xA5u8; 5000][..]] {
+            let ct = sender.encrypt_true_vernam(message).unwrap();
+            assert_eq!(ct[12], 0x02);
+            assert_eq!(receiver.decrypt_true_vernam(&ct).unwrap(), message.to_vec());
+        }
+
+        // The keystream offset is authenticated
+        let mut ct = sender.encrypt_true_vernam(&[1u8; 64]).unwrap();
+        ct[20] ^= 0x01;
+        assert!(receiver.decrypt_true_vernam(&ct).is_err());
+    }
+
+    #[test]
+    fn test_legacy_envelope_still_decrypts() {
+        let key = [19u8; 32];
+        let cipher = WasifVernam::new(key).unwrap();
+
+        // Pre-versioning layout: nonce (12) || key_offset (8) || ciphertext || tag
+        let mut nonce = [0u8; 12];
+        nonce[4..12].copy_from_slice(&5u64.to_be_bytes());
+        let aead = AeadBackend::new(AeadAlgorithm::ChaCha20Poly1305, &key).unwrap();
+        let sealed = aead.encrypt(&nonce, b"legacy", b"").unwrap();
+
+        let mut legacy = nonce.to_vec();
+        legacy.extend_from_slice(&0u64.to_be_bytes());
+        legacy.extend_from_slice(&sealed);
+        assert_eq!(cipher.decrypt(&legacy).unwrap(), b"legacy".to_vec());
+    }
+
+    #[test]
+    fn test_aad_binds_header_and_context() {
+        let key = [23u8; 32];
+        let mut cipher = WasifVernam::new(key).unwrap();
+
+        let ct = cipher.encrypt_with_aad(b"payload", b"wire-header").unwrap();
+        assert_eq!(cipher.decrypt_with_aad(&ct, b"wire-header").unwrap(), b"payload".to_vec());
+
+        let ct = cipher.encrypt_with_aad(b"payload", b"wire-header").unwrap();
+        assert!(cipher.decrypt_with_aad(&ct, b"wire-headeR").is_err());
+        assert!(cipher.decrypt(&ct).is_err());
+
+        // Tampering with the envelope's key offset is now detected
+        let mut ct = cipher.encrypt(b"payload").unwrap();
+        ct[ENVELOPE_HEADER_LEN - 1] ^= 0x01;
+        assert!(cipher.decrypt(&ct).is_err());
+    }
+
+    #[test]
+    fn test_every_backend_roundtrips() {
+        let key = [29u8; 32];
+        for algorithm in [AeadAlgorithm::ChaCha20Poly1305, AeadAlgorithm::Aes256Gcm, AeadAlgorithm::XChaCha20Poly1305] {
+            let mut sender = WasifVernam::with_algorithm(key, algorithm).unwrap();
+            let mut receiver = WasifVernam::with_algorithm(key, algorithm).unwrap();
+            sender.set_remote_key(vec![1u8; 32]);
+            receiver.set_remote_key(vec![1u8; 32]);
+
+            let ct = sender.encrypt_with_aad(b"backend payload", b"ctx").unwrap();
+            assert_eq!(ct.len(), sender.envelope_overhead() + b"backend payload".len());
+            let header = EnvelopeHeader::parse(&ct).unwrap();
+            assert_eq!(header.aead, algorithm);
+
+            assert_eq!(receiver.decrypt_with_aad(&ct, b"ctx").unwrap(), b"backend payload".to_vec());
+
+            // A receiver running a different backend refuses the envelope
+            let other = if algorithm == AeadAlgorithm::Aes256Gcm {
+                AeadAlgorithm::ChaCha20Poly1305
+            } else {
+                AeadAlgorithm::Aes256Gcm
+            };
+            let wrong = WasifVernam::with_algorithm(key, other).unwrap();
+            assert!(wrong.decrypt_with_aad(&ct, b"ctx").is_err());
+        }
+    }
+
+    #[test]
+    fn test_xchacha_nonces_are_random() {
+        let mut cipher = WasifVernam::with_algorithm([31u8; 32], AeadAlgorithm::XChaCha20Poly1305).unwrap();
+        let a = EnvelopeHeader::parse(&cipher.encrypt(b"x").unwrap()).unwrap();
+        let b = EnvelopeHeader::parse(&cipher.encrypt(b"x").unwrap()).unwrap();
+        assert_ne!(a.xnonce, b.xnonce);
+        assert_eq!(b.packet_id(), a.packet_id() + 1);
+    }
+}
+
+#[cfg(test)]
+mod rekey_tests {
+    use super::*;
+
+    fn peers() -> (WasifVernam, WasifVernam) {
+        let key = [23u8; 32];
+        let mut alice = WasifVernam::new(key).unwrap();
+        let mut bob = WasifVernam::new(key).unwrap();
+        alice.enable_key_chain([4u8; 32], true);
+        bob.enable_key_chain([4u8; 32], false);
+        alice.set_remote_key(vec![8u8; 32]);
+        bob.set_remote_key(vec![8u8; 32]);
+        (alice, bob)
+    }
+
+    #[test]
+    fn test_rekey_exchange_moves_both_peers() {
+        let (mut alice, mut bob) = peers();
+        let epoch = alice.get_key_epoch();
+
+        let request = alice.initiate_rekey().unwrap();
+        assert!(bob.decrypt(&request).is_err(), "Key updates never surface as data");
+        let response = bob.handle_key_update(&request).unwrap().expect("Bob must respond");
+        assert_eq!(bob.get_key_epoch(), epoch + 1);
+        assert_eq!(alice.handle_key_update(&response).unwrap(), None);
+        assert_eq!(alice.get_key_epoch(), epoch + 1);
+
+        let ct = alice.encrypt(b"new epoch").unwrap();
+        assert_eq!(EnvelopeHeader::parse(&ct).unwrap().key_epoch, epoch + 1);
+        assert_eq!(bob.decrypt(&ct).unwrap(), b"new epoch".to_vec());
+        let ct = bob.encrypt(b"and back").unwrap();
+        assert_eq!(alice.decrypt(&ct).unwrap(), b"and back".to_vec());
+
+        // A stale response is refused
+        assert!(alice.handle_key_update(&response).is_err());
+    }
+
+    #[test]
+    fn test_previous_epoch_accepted_during_grace_window() {
+        let (mut alice, mut bob) = peers();
+
+        // Alice keeps sending while the update is in flight
+        let request = alice.initiate_rekey().unwrap();
+        let in_flight = alice.encrypt(b"in flight").unwrap();
+        let late = alice.encrypt(b"late").unwrap();
+        let response = bob.handle_key_update(&request).unwrap().unwrap();
+        alice.handle_key_update(&response).unwrap();
+        assert_eq!(bob.decrypt(&in_flight).unwrap(), b"in flight".to_vec());
+
+        // Once the grace window is used up the old epoch is refused
+        for _ in 0..REKEY_GRACE_MESSAGES {
+            let ct = alice.encrypt(b"current").unwrap();
+            bob.decrypt(&ct).unwrap();
+        }
+        assert!(bob.decrypt(&late).is_err());
+    }
+
+    #[test]
+    fn test_simultaneous_rekey_resolves_to_alice() {
+        let (mut alice, mut bob) = peers();
+
+        let from_alice = alice.initiate_rekey().unwrap();
+        let from_bob = bob.initiate_rekey().unwrap();
+
+        // Alice ignores Bob's request, Bob yields to Alice's
+        assert_eq!(alice.handle_key_update(&from_bob).unwrap(), None);
+        let response = bob.handle_key_update(&from_alice).unwrap().unwrap();
+        alice.handle_key_update(&response).unwrap();
+
+        assert_eq!(alice.get_key_epoch(), bob.get_key_epoch());
+        let ct = bob.encrypt(b"agreed").unwrap();
+        assert_eq!(alice.decrypt(&ct).unwrap(), b"agreed".to_vec());
+    }
+
+    #[test]
+    fn test_rekey_requires_key_chain() {
+        let mut cipher = WasifVernam::new([1u8; 32]).unwrap();
+        assert!(!cipher.needs_rekey());
+        assert_eq!(cipher.initiate_rekey(), Err(RekeyError::NoKeyChain));
+    }
+}
+
+#[cfg(test)]
+mod session_tests {
+    use super::*;
+    use crate::session::SessionError;
+
+    #[test]
+    fn test_restored_session_keeps_talking() {
+        let storage_key = [61u8; 32];
+        let key = [31u8; 32];
+        let mut alice = WasifVernam::new(key).unwrap();
+        let mut bob = WasifVernam::new(key).unwrap();
+        alice.enable_key_chain([2u8; 32], true);
+        alice.set_remote_key(vec![7u8; 32]);
+        bob.set_remote_key(vec![7u8; 32]);
+
+        let seen = alice.encrypt(b"before").unwrap();
+        assert_eq!(bob.decrypt(&seen).unwrap(), b"before".to_vec());
+
+        assert!(alice.needs_snapshot());
+        let sealed_alice = alice.snapshot().seal(&storage_key).unwrap();
+        assert!(!alice.needs_snapshot());
+        let sealed_bob = bob.snapshot().seal(&storage_key).unwrap();
+        let lost = alice.encrypt(b"sent after snapshot").unwrap();
+
+        // Both processes restart
+        let mut alice = WasifVernam::restore(SessionState::open(&sealed_alice, &storage_key, 1).unwrap()).unwrap();
+        let bob = WasifVernam::restore(SessionState::open(&sealed_bob, &storage_key, 1).unwrap()).unwrap();
+        assert!(alice.needs_snapshot());
+        assert!(alice.encrypt(b"before snapshot").is_err(), "Restored cipher must wait for a snapshot");
+        assert_eq!(alice.snapshot().sequence(), 2);
+
+        let ct = alice.encrypt(b"after restart").unwrap();
+        let lost_nonce = EnvelopeHeader::parse(&lost).unwrap().nonce;
+        assert_ne!(EnvelopeHeader::parse(&ct).unwrap().nonce, lost_nonce, "Nonce reused after restore");
+        assert_eq!(bob.decrypt(&ct).unwrap(), b"after restart".to_vec());
+        assert!(bob.decrypt(&seen).is_err(), "Replay window must survive the restart");
+        assert!(alice.initiate_rekey().is_ok(), "Key chain must survive the restart");
+    }
+
+    #[test]
+    fn test_restore_keeps_custom_replay_window() {
+        let storage_key = [62u8; 32];
+        let key = [32u8; 32];
+        let mut alice = WasifVernam::new(key).unwrap();
+        let mut bob = WasifVernam::new(key).unwrap();
+        bob.set_replay_window(Box::new(BitmapWindow::new(64)));
+
+        let seen = alice.encrypt(b"before").unwrap();
+        assert_eq!(bob.decrypt(&seen).unwrap(), b"before".to_vec());
+        let sealed_bob = bob.snapshot().seal(&storage_key).unwrap();
+
+        let state = SessionState::open(&sealed_bob, &storage_key, 1).unwrap();
+        let bob = WasifVernam::restore_with_replay_window(state, Box::new(BitmapWindow::new(64))).unwrap();
+        assert_eq!(bob.anti_replay.window_size(), 64);
+        assert!(bob.decrypt(&seen).is_err(), "Recorded PIDs must be loaded into the custom window");
+        assert_eq!(bob.decrypt(&alice.encrypt(b"after").unwrap()).unwrap(), b"after".to_vec());
+    }
+
+    #[cfg(feature = "drand")]
+    #[test]
+    fn test_restore_with_drand_keeps_chain() {
+        use crate::drand::DrandConfig;
+
+        let storage_key = [63u8; 32];
+        let mut cipher = WasifVernam::new([34u8; 32]).unwrap();
+        cipher.enable_synchronized_vernam_with_role([3u8; 32], false);
+        let sealed = cipher.snapshot().seal(&storage_key).unwrap();
+
+        let quicknet = Arc::new(DrandEntropy::with_config(DrandConfig::quicknet()));
+        let state = SessionState::open(&sealed, &storage_key, 1).unwrap();
+        let restored = WasifVernam::restore_with_drand(state, quicknet.clone()).unwrap();
+        let buffer = restored.synchronized_buffer.as_ref().unwrap();
+        assert!(Arc::ptr_eq(buffer.drand_client(), &quicknet));
+        assert!(buffer.current_position() >= crate::keystream::BOB_LANE_START);
+    }
+
+    #[test]
+    fn test_restore_rejects_oversized_replay_window() {
+        let mut state = WasifVernam::new([35u8; 32]).unwrap().snapshot();
+        state.replay.window_size = MAX_WINDOW_SIZE + 1;
+        assert!(WasifVernam::restore(state).is_err());
+    }
+
+    #[test]
+    fn test_exhausted_reserve_refuses_to_seal() {
+        let mut cipher = WasifVernam::new([33u8; 32]).unwrap();
+        cipher.set_remote_key(vec![8u8; 32]);
+        cipher.snapshot();
+        assert!(cipher.encrypt(b"within reserve").is_ok());
+
+        cipher.nonce_counter.store(cipher.nonce_counter.load(Ordering::SeqCst) + SESSION_NONCE_RESERVE, Ordering::SeqCst);
+        assert!(cipher.needs_snapshot());
+        assert!(cipher.encrypt(b"past the reserve").is_err());
+        cipher.snapshot();
+        assert!(cipher.encrypt(b"new reserve").is_ok());
+
+        // A message running past the reserved keystream is refused up front
+        cipher.snapshot();
+        cipher.key_offset.store(cipher.key_offset.load(Ordering::SeqCst) + SESSION_OFFSET_RESERVE - 4, Ordering::SeqCst);
+        assert!(!cipher.needs_snapshot());
+        assert!(cipher.encrypt(b"crosses the end").is_err());
+    }
+
+    #[test]
+    fn test_snapshot_rejects_stale_wrong_key_and_tampering() {
+        let storage_key = [62u8; 32];
+        let mut cipher = WasifVernam::new([32u8; 32]).unwrap();
+        let first = cipher.snapshot().seal(&storage_key).unwrap();
+        let second = cipher.snapshot().seal(&storage_key).unwrap();
+        assert_eq!(SessionState::peek_sequence(&second), Ok(2));
+
+        assert_eq!(
+            SessionState::open(&first, &storage_key, 2).unwrap_err(),
+            SessionError::Stale { sequence: 1, last: 2 }
+        );
+        assert!(SessionState::open(&second, &storage_key, 2).is_ok());
+        assert_eq!(SessionState::open(&second, &[0u8; 32], 2).unwrap_err(), SessionError::Crypto);
+
+        // The sequence in the clear header is authenticated
+        let mut forged = first.clone();
+        forged[12] = 9;
+        assert_eq!(SessionState::open(&forged, &storage_key, 2).unwrap_err(), SessionError::Crypto);
+        assert_eq!(SessionState::open(&[0u8; 64], &storage_key, 0).unwrap_err(), SessionError::BadMagic);
+    }
+}
+
+#[cfg(test)]
+mod pq_ratchet_tests {
+    use super::*;
+
+    fn peers() -> (WasifVernam, WasifVernam) {
+        let key = [41u8; 32];
+        let mut alice = WasifVernam::new(key).unwrap();
+        let mut bob = WasifVernam::new(key).unwrap();
+        alice.enable_key_chain([6u8; 32], true);
+        bob.enable_key_chain([6u8; 32], false);
+        (alice, bob)
+    }
+
+    #[test]
+    fn test_pq_step_runs_when_due() {
+        let (mut alice, mut bob) = peers();
+        alice.enable_pq_ratchet(PqRatchetPolicy::by_messages(2));
+
+        let ct = alice.encrypt(b"one").unwrap();
+        bob.decrypt(&ct).unwrap();
+        assert!(!alice.needs_rekey());
+        alice.encrypt(b"two").unwrap();
+        assert!(alice.pq_ratchet_due());
+        assert!(alice.needs_rekey());
+
+        let request = alice.initiate_rekey().unwrap();
+        let response = bob.handle_key_update(&request).unwrap().unwrap();
+        alice.handle_key_update(&response).unwrap();
+        assert!(!alice.pq_ratchet_due(), "Completed step restarts the count");
+
+        let ct = alice.encrypt(b"post-quantum epoch").unwrap();
+        assert_eq!(bob.decrypt(&ct).unwrap(), b"post-quantum epoch".to_vec());
+    }
+
+    #[test]
+    fn test_pq_step_changes_epoch_key() {
+        // Same chains and contributions, with and without the ML-KEM step
+        let chain = |is_alice| RecursiveChain::new(&[6u8; 32], is_alice);
+        let (mut plain, mut pq) = (chain(true), chain(true));
+        pq.mix_pq_secret(&[3u8; 32]);
+        assert_ne!(plain.derive_epoch_key(1), pq.derive_epoch_key(1));
+
+        // The secret survives later chain updates
+        plain.update_peer_key(&[8u8; 32]);
+        pq.update_peer_key(&[8u8; 32]);
+        assert_ne!(plain.derive_epoch_key(2), pq.derive_epoch_key(2));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pq_response_without_ciphertext_rejected() {
+        let (mut alice, mut bob) = peers();
+        alice.enable_pq_ratchet(PqRatchetPolicy::by_interval(Duration::ZERO));
+
+        // Bob answers a plain request while Alice waits for a PQ one
+        let (mut other, _) = peers();
+        let plain_request = other.initiate_rekey().unwrap();
+        let plain_response = bob.handle_key_update(&plain_request).unwrap().unwrap();
+
+        let _ = alice.initiate_rekey().unwrap();
+        assert_eq!(alice.handle_key_update(&plain_response), Err(RekeyError::Malformed));
+        assert_eq!(alice.initiate_rekey(), Err(RekeyError::InProgress), "Request stays pending");
+    }
+}
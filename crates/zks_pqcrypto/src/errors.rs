@@ -1,5 +1,6 @@
 //! Error types for zks_pqcrypto crate
 
+use alloc::string::String;
 use thiserror::Error;
 
 /// Main error type for post-quantum cryptographic operations
@@ -43,4 +44,4 @@ pub enum PqcError {
 }
 
 /// Result type alias for post-quantum cryptographic operations
-pub type Result<T> = std::result::Result<T, PqcError>;
\ No newline at end of file
+pub type Result<T> = core::result::Result<T, PqcError>;
\ No newline at end of file
@@ -20,6 +20,14 @@
 //! - **No Unsafe Code**: `#![forbid(unsafe_code)]` for maximum safety
 //! - **Ergonomic API**: Simple, easy-to-use interfaces
 //!
+//! # `no_std`
+//!
+//! The default `std` feature provides the OS RNG. Without it the crate is
+//! `no_std` + `alloc`: ML-KEM takes its RNG from the caller through
+//! [`MlKem::generate_keypair_with_rng`] and [`MlKem::encapsulate_with_rng`], and
+//! ML-DSA key generation draws from `getrandom`, so targets without an OS need a
+//! custom `getrandom` backend (or keys provisioned elsewhere). Signing is deterministic.
+//!
 //! # Example
 //!
 //! ```rust
@@ -38,10 +46,13 @@
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 #![deny(clippy::all)]
 
+extern crate alloc;
+
 pub mod ml_kem;
 pub mod ml_dsa;
 pub mod errors;
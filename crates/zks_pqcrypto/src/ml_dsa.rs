@@ -41,6 +41,8 @@
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 
+use alloc::{format, string::ToString, vec::Vec};
+use core::fmt;
 use zeroize::Zeroizing;
 use crate::errors::{PqcError, Result};
 
@@ -246,8 +248,8 @@ pub struct MlDsaKeypair {
     signing_key: Zeroizing<Vec<u8>>,
 }
 
-impl std::fmt::Debug for MlDsaKeypair {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Debug for MlDsaKeypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("MlDsaKeypair")
             .field("verifying_key", &format!("{} bytes", self.verifying_key.len()))
             .field("signing_key", &"[REDACTED]")
@@ -259,6 +261,7 @@ impl MlDsaKeypair {
     /// Create a new keypair from raw bytes
     #[must_use]
     pub fn from_bytes(verifying_key: Vec<u8>, signing_key: Vec<u8>) -> Result<Self> {
+        let signing_key = Zeroizing::new(signing_key);
         if verifying_key.len() != PUBLIC_KEY_SIZE {
             return Err(PqcError::InvalidKey(format!(
                 "Invalid verifying key size: expected {}, got {}",
@@ -277,7 +280,7 @@ impl MlDsaKeypair {
 
         Ok(Self {
             verifying_key,
-            signing_key: Zeroizing::new(signing_key),
+            signing_key,
         })
     }
 
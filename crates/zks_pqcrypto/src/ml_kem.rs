@@ -34,14 +34,17 @@
 //! ```
 
 use crate::errors::{PqcError, Result};
+use alloc::{format, string::ToString, vec, vec::Vec};
 use ml_kem::{MlKem768, MlKem768Params, KemCore, EncodedSizeUser};
 use ml_kem::kem::{EncapsulationKey, DecapsulationKey, Encapsulate, Decapsulate};
 use zeroize::{Zeroize, Zeroizing};
 use rand_core::{RngCore, CryptoRng};
 
 /// Simple OS RNG wrapper that implements RngCore + CryptoRng for rand_core 0.6 compatibility
+#[cfg(feature = "std")]
 struct OsRngCompat;
 
+#[cfg(feature = "std")]
 impl RngCore for OsRngCompat {
     fn next_u32(&mut self) -> u32 {
         let mut buf = [0u8; 4];
@@ -63,11 +66,12 @@ impl RngCore for OsRngCompat {
             .expect("CRITICAL: Cryptographic RNG unavailable - cannot generate secure keys. This indicates a system-level issue with entropy sources.");
     }
     
-    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand_core::Error> {
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> core::result::Result<(), rand_core::Error> {
         getrandom::getrandom(dest).map_err(rand_core::Error::from)
     }
 }
 
+#[cfg(feature = "std")]
 impl CryptoRng for OsRngCompat {}
 
 /// ML-KEM public key size (1184 bytes for ML-KEM-768)
@@ -95,6 +99,7 @@ impl MlKemKeypair {
     /// Create a new keypair from raw bytes
     #[must_use]
     pub fn from_bytes(public_key: Vec<u8>, secret_key: Vec<u8>) -> Result<Self> {
+        let secret_key = Zeroizing::new(secret_key);
         if public_key.len() != PUBLIC_KEY_SIZE {
             return Err(PqcError::InvalidKey(format!(
                 "Invalid public key size: expected {}, got {}",
@@ -113,7 +118,7 @@ impl MlKemKeypair {
 
         Ok(Self {
             public_key,
-            secret_key: Zeroizing::new(secret_key),
+            secret_key,
         })
     }
 
@@ -160,13 +165,22 @@ impl MlKem {
     ///
     /// # Errors
     /// Returns error if key generation fails
+    #[cfg(feature = "std")]
     #[must_use]
     pub fn generate_keypair() -> Result<MlKemKeypair> {
         // Use our compatible OS RNG
-        let mut rng = OsRngCompat;
-        
+        Self::generate_keypair_with_rng(&mut OsRngCompat)
+    }
+
+    /// Generate a new ML-KEM keypair from a caller-supplied RNG
+    ///
+    /// This is the only way to generate keys without the `std` feature.
+    ///
+    /// # Errors
+    /// Returns error if key generation fails
+    pub fn generate_keypair_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Result<MlKemKeypair> {
         // Generate keypair using the standard generate method
-        let (dk, ek): (DecapsulationKey<MlKem768Params>, EncapsulationKey<MlKem768Params>) = MlKem768::generate(&mut rng);
+        let (dk, ek): (DecapsulationKey<MlKem768Params>, EncapsulationKey<MlKem768Params>) = MlKem768::generate(rng);
         
         let public_key_bytes = ek.as_bytes().as_slice().to_vec();
         let secret_key_bytes = Zeroizing::new(dk.as_bytes().as_slice().to_vec());
@@ -193,8 +207,21 @@ impl MlKem {
     ///
     /// # Errors
     /// Returns error if encapsulation fails or public key is invalid
+    #[cfg(feature = "std")]
     #[must_use]
     pub fn encapsulate(public_key: &[u8]) -> Result<MlKemEncapsulation> {
+        // Use our compatible OS RNG for encapsulation
+        Self::encapsulate_with_rng(public_key, &mut OsRngCompat)
+    }
+
+    /// Encapsulate a shared secret using a caller-supplied RNG
+    ///
+    /// # Errors
+    /// Returns error if encapsulation fails or public key is invalid
+    pub fn encapsulate_with_rng<R: RngCore + CryptoRng>(
+        public_key: &[u8],
+        rng: &mut R,
+    ) -> Result<MlKemEncapsulation> {
         if public_key.len() != PUBLIC_KEY_SIZE {
             return Err(PqcError::InvalidKey(format!(
                 "Invalid public key size: expected {}, got {}",
@@ -206,10 +233,7 @@ impl MlKem {
         // Create the encapsulation key from bytes
         let ek = EncapsulationKey::<MlKem768Params>::from_bytes(public_key.try_into().unwrap());
         
-        // Use our compatible OS RNG for encapsulation
-        let mut rng = OsRngCompat;
-        
-        let (ciphertext, shared_secret) = ek.encapsulate(&mut rng)
+        let (ciphertext, shared_secret) = ek.encapsulate(rng)
             .map_err(|()| PqcError::MlKem("Encapsulation failed".to_string()))?;
         
         let ciphertext_bytes: Vec<u8> = ciphertext.to_vec();
@@ -310,6 +334,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_keypair_generation() {
         let keypair = MlKem::generate_keypair().expect("Key generation should succeed");
         
@@ -318,6 +343,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_encapsulation_decapsulation() {
         // Generate keypair
         let keypair = MlKem::generate_keypair().expect("Key generation should succeed");
@@ -340,6 +366,26 @@ mod tests {
     }
 
     #[test]
+    fn test_caller_supplied_rng() {
+        use rand_chacha::rand_core::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        // The same seed yields the same keypair, so the RNG really is the caller's
+        let keypair = MlKem::generate_keypair_with_rng(&mut ChaCha20Rng::from_seed([1u8; 32]))
+            .expect("Key generation should succeed");
+        let again = MlKem::generate_keypair_with_rng(&mut ChaCha20Rng::from_seed([1u8; 32]))
+            .expect("Key generation should succeed");
+        assert_eq!(keypair.public_key, again.public_key);
+
+        let encapsulation = MlKem::encapsulate_with_rng(&keypair.public_key, &mut ChaCha20Rng::from_seed([2u8; 32]))
+            .expect("Encapsulation should succeed");
+        let shared_secret_bob = MlKem::decapsulate(&encapsulation.ciphertext, keypair.secret_key())
+            .expect("Decapsulation should succeed");
+        assert_eq!(encapsulation.shared_secret.as_ref() as &[u8], shared_secret_bob.as_ref() as &[u8]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
     fn test_invalid_key_sizes() {
         // Test invalid public key size
         let result = MlKem::encapsulate(&[0u8; 100]);
@@ -5,7 +5,7 @@ use url::Url;
 use crate::{
     connection::{ZkConnection, ZksConnection},
     error::{Result, SdkError},
-    config::{SecurityLevel, ConnectionConfig},
+    config::{SecurityLevel, ConnectionConfig, PaddingPolicy},
 };
 
 /// Builder for direct ZK connections (zk://)
@@ -14,6 +14,7 @@ pub struct ZkConnectionBuilder {
     security: Option<SecurityLevel>,
     timeout: Option<Duration>,
     buffer_size: Option<usize>,
+    padding: Option<PaddingPolicy>,
 }
 
 impl ZkConnectionBuilder {
@@ -24,6 +25,7 @@ impl ZkConnectionBuilder {
             security: None,
             timeout: None,
             buffer_size: None,
+            padding: None,
         }
     }
 
@@ -51,6 +53,12 @@ impl ZkConnectionBuilder {
         self
     }
 
+    /// Pad messages to hide their lengths (none by default)
+    pub fn padding(mut self, policy: PaddingPolicy) -> Self {
+        self.padding = Some(policy);
+        self
+    }
+
     /// Build the ZK connection
     pub async fn build(self) -> Result<ZkConnection> {
         let url = self.url.ok_or_else(|| SdkError::InvalidUrl("URL is required".to_string()))?;
@@ -67,6 +75,7 @@ impl ZkConnectionBuilder {
             security: self.security.unwrap_or_default(),
             timeout: self.timeout.unwrap_or_else(|| Duration::from_secs(30)),
             buffer_size: self.buffer_size.unwrap_or(64 * 1024),
+            padding: self.padding,
             ..Default::default()
         };
 
@@ -89,6 +98,7 @@ pub struct ZksConnectionBuilder {
     min_hops: Option<u8>,
     max_hops: Option<u8>,
     enable_scrambling: Option<bool>,
+    padding: Option<PaddingPolicy>,
 }
 
 impl ZksConnectionBuilder {
@@ -102,6 +112,7 @@ impl ZksConnectionBuilder {
             min_hops: None,
             max_hops: None,
             enable_scrambling: None,
+            padding: None,
         }
     }
 
@@ -129,6 +140,12 @@ impl ZksConnectionBuilder {
         self
     }
 
+    /// Override the padding that hides message lengths (bucketed by default)
+    pub fn padding(mut self, policy: PaddingPolicy) -> Self {
+        self.padding = Some(policy);
+        self
+    }
+
     /// Set minimum number of hops for onion routing
     pub fn min_hops(mut self, hops: u8) -> Self {
         self.min_hops = Some(hops);
@@ -164,6 +181,7 @@ impl ZksConnectionBuilder {
             timeout: self.timeout.unwrap_or_else(|| Duration::from_secs(30)),
             buffer_size: self.buffer_size.unwrap_or(64 * 1024),
             enable_scrambling: self.enable_scrambling.unwrap_or(true),
+            padding: self.padding,
             ..Default::default()
         };
 
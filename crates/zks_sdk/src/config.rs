@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+pub use zks_crypt::padding::PaddingPolicy;
+
 /// Security levels for connections
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SecurityLevel {
@@ -44,6 +46,10 @@ pub struct ConnectionConfig {
     
     /// Maximum message size
     pub max_message_size: usize,
+    
+    /// Length-hiding padding, `None` for the mode default
+    /// (bucketed for swarm connections, none for direct ones)
+    pub padding: Option<PaddingPolicy>,
 }
 
 impl Default for ConnectionConfig {
@@ -56,6 +62,7 @@ impl Default for ConnectionConfig {
             enable_anti_replay: true,
             enable_compression: false,
             max_message_size: 16 * 1024 * 1024, // 16MB
+            padding: None,
         }
     }
 }
@@ -120,4 +127,35 @@ impl ConnectionConfig {
         self.max_message_size = size;
         self
     }
+    
+    /// Set the padding policy, overriding the mode default
+    pub fn with_padding(mut self, policy: PaddingPolicy) -> Self {
+        self.padding = Some(policy);
+        self
+    }
+    
+    /// Padding policy in effect for a direct (`zk://`) or swarm (`zks://`) connection
+    pub fn padding_policy(&self, is_swarm: bool) -> PaddingPolicy {
+        match self.padding {
+            Some(ref policy) => policy.clone(),
+            None if is_swarm => PaddingPolicy::default_buckets(),
+            None => PaddingPolicy::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_swarm_defaults_to_bucketed_padding() {
+        let config = ConnectionConfig::default();
+        assert_eq!(config.padding_policy(true), PaddingPolicy::default_buckets());
+        assert_eq!(config.padding_policy(false), PaddingPolicy::None);
+        
+        let config = config.with_padding(PaddingPolicy::Padme);
+        assert_eq!(config.padding_policy(true), PaddingPolicy::Padme);
+        assert_eq!(config.padding_policy(false), PaddingPolicy::Padme);
+    }
 }
\ No newline at end of file
@@ -147,6 +147,11 @@ impl ZkConnection {
         &self.config
     }
     
+    /// Get the encrypted stream, for streaming bulk data through it
+    pub(crate) fn stream_mut(&mut self) -> &mut EncryptedStream<TcpStream> {
+        &mut self.stream
+    }
+    
     /// Check if the connection is still active
     pub fn is_connected(&self) -> bool {
         // Check if the encrypted stream handshake is complete
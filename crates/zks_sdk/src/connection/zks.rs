@@ -189,6 +189,11 @@ impl ZksConnection {
         &self.config
     }
     
+    /// Get the encrypted stream, for streaming bulk data through it
+    pub(crate) fn stream_mut(&mut self) -> &mut EncryptedStream<Box<dyn ZksStream>> {
+        &mut self.stream
+    }
+    
     /// Get the number of hops in the onion route
     pub fn hop_count(&self) -> u8 {
         self.hop_count
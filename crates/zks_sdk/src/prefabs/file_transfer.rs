@@ -1,9 +1,20 @@
 //! Secure file transfer with progress tracking
+//!
+//! File contents travel as one STREAM (see [`zks_crypt::stream_aead`]) under a fresh
+//! per-file key sent with the metadata, so files of any size are encrypted and
+//! verified in constant memory and a file cut short is detected.
 
 use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
 use tokio::fs::File;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tracing::{info, debug};
+use zeroize::Zeroizing;
+use zks_crypt::stream_aead::{
+    DecryptingReader, EncryptingWriter, MAX_SEGMENT_SIZE, STREAM_HEADER_LEN, STREAM_TAG_LEN,
+};
 
 use crate::{
     connection::{ZkConnection, ZksConnection},
@@ -54,6 +65,62 @@ fn sanitize_filename(name: &str) -> String {
     }
 }
 
+/// Length of the STREAM carrying `file_size` bytes in `segment_size` segments
+fn stream_len(file_size: u64, segment_size: usize) -> u64 {
+    // The last segment holds the remainder and may be empty
+    let segments = file_size / segment_size as u64 + 1;
+    STREAM_HEADER_LEN as u64 + file_size + segments * STREAM_TAG_LEN as u64
+}
+
+/// Parse the segment size announced in file metadata
+fn parse_segment_size(field: &str) -> Result<usize> {
+    match field.parse::<usize>() {
+        Ok(size) if size > 0 && size <= MAX_SEGMENT_SIZE => Ok(size),
+        _ => Err(SdkError::InvalidInput("Invalid segment size".to_string())),
+    }
+}
+
+/// Parse the per-file key message
+fn parse_file_key(message: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+    let key: [u8; 32] = message.try_into()
+        .map_err(|_| SdkError::InvalidInput("Invalid file key".to_string()))?;
+    Ok(Zeroizing::new(key))
+}
+
+/// Fresh random key for one file's STREAM
+fn new_file_key() -> Result<Zeroizing<[u8; 32]>> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    getrandom::getrandom(key.as_mut())
+        .map_err(|e| SdkError::CryptoError(format!("Failed to generate file key: {}", e)))?;
+    Ok(key)
+}
+
+/// Run an I/O step under the connection timeout
+async fn timed<T>(timeout: Duration, step: impl std::future::Future<Output = std::io::Result<T>>) -> Result<T> {
+    tokio::time::timeout(timeout, step)
+        .await
+        .map_err(|_| SdkError::Timeout)?
+        .map_err(SdkError::IoError)
+}
+
+/// Writer whose shutdown only flushes, so finishing a file's STREAM keeps the
+/// connection open
+struct KeepOpen<'a, W>(&'a mut W);
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for KeepOpen<'_, W> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut *self.get_mut().0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut *self.get_mut().0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut *self.get_mut().0).poll_flush(cx)
+    }
+}
+
 /// Secure file transfer with progress tracking
 pub struct SecureFileTransfer {
     chunk_size: usize,
@@ -68,6 +135,8 @@ impl SecureFileTransfer {
     }
     
     /// Set the chunk size for file transfer
+    ///
+    /// This is also the STREAM segment size, at most [`MAX_SEGMENT_SIZE`].
     pub fn with_chunk_size(mut self, size: usize) -> Self {
         self.chunk_size = size;
         self
@@ -95,34 +164,16 @@ impl SecureFileTransfer {
         
         info!("Sending file: {} ({} bytes)", file_name, file_size);
         
-        // Send file metadata
-        let metadata = format!("{}:{}", file_name, file_size);
+        // Send file metadata and the key of the file's STREAM
+        let file_key = new_file_key()?;
+        let metadata = format!("{}:{}:{}", file_name, file_size, self.chunk_size);
         connection.send_message(metadata.as_bytes()).await?;
+        connection.send_message(file_key.as_ref()).await?;
         
-        // Open file and send in chunks
-        let mut file = File::open(path).await
+        let file = File::open(path).await
             .map_err(SdkError::IoError)?;
-        
-        let mut buffer = vec![0u8; self.chunk_size];
-        let mut sent = 0u64;
-        
-        loop {
-            let n = file.read(&mut buffer).await
-                .map_err(SdkError::IoError)?;
-            
-            if n == 0 {
-                break;
-            }
-            
-            // Send chunk
-            connection.send_message(&buffer[..n]).await?;
-            sent += n as u64;
-            
-            // Call progress callback
-            on_progress(sent, file_size);
-            
-            debug!("Sent {} / {} bytes", sent, file_size);
-        }
+        let timeout = connection.config().timeout;
+        let sent = send_body(connection.stream_mut(), file, &file_key, file_size, self.chunk_size, timeout, &mut on_progress).await?;
         
         info!("File transfer complete: {} ({} bytes)", file_name, sent);
         Ok(())
@@ -147,13 +198,14 @@ impl SecureFileTransfer {
             .map_err(|e| SdkError::SerializationError(e.to_string()))?;
         
         let parts: Vec<&str> = metadata_str.split(':').collect();
-        if parts.len() != 2 {
+        if parts.len() != 3 {
             return Err(SdkError::InvalidUrl("Invalid file metadata format".to_string()));
         }
         
         let file_name = sanitize_filename(parts[0]);
         let file_size: u64 = parts[1].parse()
             .map_err(|_| SdkError::InvalidUrl("Invalid file size".to_string()))?;
+        let segment_size = parse_segment_size(parts[2])?;
         
         // Validate file size
         if file_size > MAX_FILE_SIZE {
@@ -162,31 +214,23 @@ impl SecureFileTransfer {
                 file_size, MAX_FILE_SIZE
             )));
         }
+        let file_key = parse_file_key(&connection.recv_message().await?)?;
         
         info!("Receiving file: {} ({} bytes)", file_name, file_size);
         
         // Create save path
         let save_path = save_path.as_ref().join(&file_name);
         debug!("Saving file to: {:?}", save_path);
-        let mut file = File::create(&save_path).await
-            .map_err(SdkError::IoError)?;
-        
-        let mut received = 0u64;
-        
-        // Receive file in chunks
-        while received < file_size {
-            let chunk = connection.recv_message().await?;
-            
-            file.write_all(&chunk).await
-                .map_err(SdkError::IoError)?;
-            
-            received += chunk.len() as u64;
-            
-            // Call progress callback
-            on_progress(received, file_size);
-            
-            debug!("Received {} / {} bytes", received, file_size);
-        }
+        let timeout = connection.config().timeout;
+        let received = recv_body(
+            connection.stream_mut(),
+            &save_path,
+            &file_key,
+            file_size,
+            segment_size,
+            timeout,
+            &mut on_progress,
+        ).await?;
         
         info!("File transfer complete: {} ({} bytes)", file_name, received);
         Ok(file_name.to_string())
@@ -229,32 +273,18 @@ impl SecureFileTransfer {
         
         info!("Sending file: {} ({} bytes)", path.display(), total_size);
         
-        // Send file metadata first
+        // Send file metadata and the key of the file's STREAM first
         let file_name = path.file_name()
             .and_then(|n| n.to_str())
             .ok_or_else(|| SdkError::InvalidInput("Invalid file name".to_string()))?;
         
-        let metadata_msg = format!("FILE:{}:{}", file_name, total_size);
-        connection.send(metadata_msg.as_bytes()).await?;
+        let file_key = new_file_key()?;
+        let metadata_msg = format!("FILE:{}:{}:{}", file_name, total_size, self.chunk_size);
+        connection.send_message(metadata_msg.as_bytes()).await?;
+        connection.send_message(file_key.as_ref()).await?;
         
-        // Send file data in chunks
-        let mut reader = tokio::io::BufReader::new(file);
-        let mut buffer = vec![0u8; self.chunk_size];
-        let mut sent = 0u64;
-        
-        loop {
-            let n = reader.read(&mut buffer).await
-                .map_err(SdkError::IoError)?;
-            
-            if n == 0 {
-                break;
-            }
-            
-            connection.send(&buffer[..n]).await?;
-            sent += n as u64;
-            
-            on_progress(sent, total_size);
-        }
+        let timeout = connection.config().timeout;
+        let sent = send_body(connection.stream_mut(), file, &file_key, total_size, self.chunk_size, timeout, &mut on_progress).await?;
         
         info!("File sent successfully: {} ({} bytes)", file_name, sent);
         Ok(())
@@ -290,54 +320,228 @@ impl SecureFileTransfer {
         let save_path = save_path.as_ref();
         
         // Receive file metadata first
-        let mut metadata_buf = vec![0u8; 1024];
-        let n = connection.recv(&mut metadata_buf).await?;
-        let metadata_str = String::from_utf8(metadata_buf[..n].to_vec())
+        let metadata = connection.recv_message().await?;
+        let metadata_str = String::from_utf8(metadata)
             .map_err(|e| SdkError::SerializationError(e.to_string()))?;
         
         let parts: Vec<&str> = metadata_str.split(':').collect();
-        if parts.len() != 3 || parts[0] != "FILE" {
+        if parts.len() != 4 || parts[0] != "FILE" {
             return Err(SdkError::InvalidInput("Invalid file metadata format".to_string()));
         }
         
         let file_name = parts[1];
         let total_size: u64 = parts[2].parse()
             .map_err(|e| SdkError::InvalidInput(format!("Invalid file size: {}", e)))?;
+        let segment_size = parse_segment_size(parts[3])?;
+        let file_key = parse_file_key(&connection.recv_message().await?)?;
         
         info!("Receiving file: {} ({} bytes)", file_name, total_size);
         
         // Create file path
         let file_path = save_path.join(file_name);
-        let mut file = tokio::fs::File::create(&file_path).await
+        let timeout = connection.config().timeout;
+        let received = recv_body(
+            connection.stream_mut(),
+            &file_path,
+            &file_key,
+            total_size,
+            segment_size,
+            timeout,
+            &mut on_progress,
+        ).await?;
+        
+        info!("File received successfully: {} ({} bytes)", file_name, received);
+        Ok(file_name.to_string())
+    }
+}
+
+/// Encrypt `file_size` bytes of `file` into `stream` as one STREAM
+async fn send_body<S, F>(
+    stream: &mut S,
+    file: File,
+    file_key: &[u8; 32],
+    file_size: u64,
+    segment_size: usize,
+    timeout: Duration,
+    on_progress: &mut F,
+) -> Result<u64>
+where
+    S: AsyncWrite + Unpin,
+    F: FnMut(u64, u64),
+{
+    let mut writer = EncryptingWriter::with_segment_size(KeepOpen(stream), file_key, segment_size)
+        .map_err(|e| SdkError::CryptoError(e.to_string()))?;
+    
+    // Never send more than announced, even if the file grows meanwhile
+    let mut file = file.take(file_size);
+    let mut buffer = vec![0u8; segment_size];
+    let mut sent = 0u64;
+    
+    loop {
+        let n = file.read(&mut buffer).await
             .map_err(SdkError::IoError)?;
         
-        // Receive file data in chunks
-        let mut buffer = vec![0u8; self.chunk_size];
-        let mut received = 0u64;
+        if n == 0 {
+            break;
+        }
+        
+        timed(timeout, writer.write_all(&buffer[..n])).await?;
+        sent += n as u64;
+        
+        // Call progress callback
+        on_progress(sent, file_size);
         
-        while received < total_size {
-            let n = connection.recv(&mut buffer).await?;
+        debug!("Sent {} / {} bytes", sent, file_size);
+    }
+    
+    if sent != file_size {
+        return Err(SdkError::InvalidInput(format!(
+            "File shrank while sending: {} of {} bytes", sent, file_size
+        )));
+    }
+    
+    // Seal the final segment; the connection itself stays open
+    timed(timeout, writer.shutdown()).await?;
+    Ok(sent)
+}
+
+/// Decrypt one STREAM of `file_size` bytes from `stream` into a new file at `path`
+///
+/// Only authenticated segments are written, and the file is removed again if the
+/// STREAM fails to verify or ends early.
+async fn recv_body<S, F>(
+    stream: &mut S,
+    path: &Path,
+    file_key: &[u8; 32],
+    file_size: u64,
+    segment_size: usize,
+    timeout: Duration,
+    on_progress: &mut F,
+) -> Result<u64>
+where
+    S: AsyncRead + Unpin,
+    F: FnMut(u64, u64),
+{
+    let mut file = File::create(path).await
+        .map_err(SdkError::IoError)?;
+    
+    // The STREAM ends exactly here, which is where its last segment is expected
+    let source = stream.take(stream_len(file_size, segment_size));
+    let mut reader = DecryptingReader::new(source, file_key);
+    let mut buffer = vec![0u8; segment_size];
+    let mut received = 0u64;
+    
+    let result = async {
+        loop {
+            let n = timed(timeout, reader.read(&mut buffer)).await?;
             if n == 0 {
                 break;
             }
             
-            file.write_all(&buffer[..n]).await
-                .map_err(SdkError::IoError)?;
-            
+            timed(timeout, file.write_all(&buffer[..n])).await?;
             received += n as u64;
-            on_progress(received, total_size);
+            
+            // Call progress callback
+            on_progress(received, file_size);
+            
+            debug!("Received {} / {} bytes", received, file_size);
         }
-        
-        file.flush().await
-            .map_err(SdkError::IoError)?;
-        
-        info!("File received successfully: {} ({} bytes)", file_name, received);
-        Ok(file_name.to_string())
+        if received != file_size {
+            return Err(SdkError::InvalidInput(format!(
+                "File ended after {} of {} bytes", received, file_size
+            )));
+        }
+        timed(timeout, file.flush()).await
+    }.await;
+    
+    if let Err(e) = result {
+        drop(file);
+        let _ = tokio::fs::remove_file(path).await;
+        return Err(e);
     }
+    Ok(received)
 }
 
 impl Default for SecureFileTransfer {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConnectionConfig;
+    use crate::stream::EncryptedStream;
+    
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    
+    fn temp_path(label: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("zks-{}-{}", label, uuid::Uuid::new_v4()))
+    }
+    
+    fn stream_pair() -> (EncryptedStream<tokio::io::DuplexStream>, EncryptedStream<tokio::io::DuplexStream>) {
+        let config = ConnectionConfig::default();
+        let (a, b) = tokio::io::duplex(8 * 1024);
+        (
+            EncryptedStream::new(a, [11u8; 32], &config, false).unwrap(),
+            EncryptedStream::new(b, [11u8; 32], &config, false).unwrap(),
+        )
+    }
+    
+    #[tokio::test]
+    async fn test_file_body_streams_through_connection() {
+        let data: Vec<u8> = (0..300_000u32).map(|i| (i * 7) as u8).collect();
+        let source = temp_path("send");
+        let target = temp_path("recv");
+        tokio::fs::write(&source, &data).await.unwrap();
+        let (mut sender, mut receiver) = stream_pair();
+        let key = [3u8; 32];
+        let size = data.len() as u64;
+        
+        let send = async {
+            let file = File::open(&source).await.unwrap();
+            send_body(&mut sender, file, &key, size, 4096, TIMEOUT, &mut |_, _| {}).await.unwrap();
+            // The connection stays usable after the file
+            sender.write_all(b"next").await.unwrap();
+            sender.flush().await.unwrap();
+        };
+        let mut progress = 0;
+        let recv = async {
+            let received = recv_body(&mut receiver, &target, &key, size, 4096, TIMEOUT, &mut |done, _| progress = done).await.unwrap();
+            let mut next = [0u8; 4];
+            receiver.read_exact(&mut next).await.unwrap();
+            (received, next)
+        };
+        let (_, (received, next)) = tokio::join!(send, recv);
+        
+        assert_eq!(received, size);
+        assert_eq!(progress, size);
+        assert_eq!(&next, b"next");
+        assert_eq!(tokio::fs::read(&target).await.unwrap(), data);
+        let _ = tokio::fs::remove_file(&source).await;
+        let _ = tokio::fs::remove_file(&target).await;
+    }
+    
+    #[tokio::test]
+    async fn test_short_file_body_is_rejected_and_removed() {
+        let source = temp_path("short-send");
+        let target = temp_path("short-recv");
+        tokio::fs::write(&source, vec![5u8; 10_000]).await.unwrap();
+        let (mut sender, mut receiver) = stream_pair();
+        let key = [4u8; 32];
+        
+        // The sender announces more than the STREAM it then finishes
+        let send = async {
+            let file = File::open(&source).await.unwrap();
+            send_body(&mut sender, file, &key, 10_000, 4096, TIMEOUT, &mut |_, _| {}).await.unwrap();
+            sender.shutdown().await.unwrap();
+        };
+        let mut on_progress = |_, _| {};
+        let recv = recv_body(&mut receiver, &target, &key, 20_000, 4096, TIMEOUT, &mut on_progress);
+        let (_, result) = tokio::join!(send, recv);
+        
+        assert!(result.is_err());
+        assert!(!target.exists(), "Unverified file must not be kept");
+        let _ = tokio::fs::remove_file(&source).await;
+    }
+}
@@ -6,7 +6,8 @@
 //! - Algorithm selection enums
 //! - Security level definitions
 
-use std::fmt;
+use alloc::vec::Vec;
+use core::fmt;
 use zeroize::Zeroize;
 
 /// Security buffer for encrypted data
@@ -58,8 +59,8 @@ impl SecBuffer {
 }
 
 /// Secure debug implementation that doesn't expose sensitive data
-impl std::fmt::Debug for SecBuffer {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Debug for SecBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "SecBuffer([REDACTED; {} bytes])", self.data.len())
     }
 }
@@ -124,6 +125,8 @@ pub enum EncryptionAlgorithm {
     ChaCha20,
     /// AES-256-GCM
     Aes256Gcm,
+    /// XChaCha20-Poly1305 with 192-bit random nonces
+    XChaCha20,
 }
 
 impl Default for EncryptionAlgorithm {
@@ -137,6 +140,7 @@ impl fmt::Display for EncryptionAlgorithm {
         match self {
             EncryptionAlgorithm::ChaCha20 => write!(f, "ChaCha20"),
             EncryptionAlgorithm::Aes256Gcm => write!(f, "AES-256-GCM"),
+            EncryptionAlgorithm::XChaCha20 => write!(f, "XChaCha20"),
         }
     }
 }
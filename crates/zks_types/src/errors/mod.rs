@@ -3,8 +3,9 @@
 //! This module provides comprehensive error handling for the ZKS Protocol ecosystem,
 //! including categorized error types and convenience result types.
 
-use std::fmt;
-use std::error::Error as StdError;
+use alloc::string::String;
+use core::error::Error as StdError;
+use core::fmt;
 
 /// Main error type for ZKS Protocol operations
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -80,4 +81,4 @@ impl fmt::Display for ErrorKind {
 }
 
 /// Convenience type alias for results with ZksError
-pub type Result<T> = std::result::Result<T, ZksError>;
\ No newline at end of file
+pub type Result<T> = core::result::Result<T, ZksError>;
\ No newline at end of file
@@ -1,10 +1,14 @@
 //! ZKS Protocol Core Types
 //! 
 //! This crate provides fundamental types used across the ZKS Protocol ecosystem.
+//! It is `no_std` + `alloc` when the default `std` feature is disabled.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
+extern crate alloc;
+
 pub mod crypto;
 pub mod errors;
 
@@ -85,6 +85,7 @@ impl SwarmCircuit {
     
     /// Onion encrypt data for transmission through the circuit
     /// Data is encrypted in reverse order (exit first, entry last)
+    /// Every layer binds the circuit ID as associated data
     pub fn onion_encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
         if self.layer_keys.is_empty() {
             return Err(WireError::other("No layer keys available for encryption"));
@@ -97,7 +98,7 @@ impl SwarmCircuit {
             if let Some(key) = self.get_layer_key(i) {
                 let mut cipher = WasifVernam::new(key)
                     .map_err(|e| WireError::other(&format!("Failed to create cipher: {}", e)))?;
-                encrypted = cipher.encrypt(&encrypted)
+                encrypted = cipher.encrypt_with_aad(&encrypted, &self.circuit_id)
                     .map_err(|e| WireError::other(&format!("Encryption failed: {}", e)))?;
             } else {
                 return Err(WireError::other("Failed to get layer key"));
@@ -122,7 +123,7 @@ impl SwarmCircuit {
             if let Some(key) = self.get_layer_key(i) {
                 let cipher = WasifVernam::new(key)
                     .map_err(|e| WireError::other(&format!("Failed to create cipher: {}", e)))?;
-                decrypted = cipher.decrypt(&decrypted)
+                decrypted = cipher.decrypt_with_aad(&decrypted, &self.circuit_id)
                     .map_err(|e| WireError::other(&format!("Decryption failed: {}", e)))?;
             } else {
                 return Err(WireError::other("Failed to get layer key"));
@@ -292,6 +293,11 @@ mod tests {
         
         let decrypted = circuit.onion_decrypt(&encrypted).unwrap();
         assert_eq!(decrypted, plaintext);
+        
+        // Same keys on a different circuit must not decrypt
+        let mut other = SwarmCircuit::new();
+        other.set_layer_keys(vec![[1u8; 32], [2u8; 32], [3u8; 32]]);
+        assert!(other.onion_decrypt(&encrypted).is_err());
     }
     
     #[tokio::test]
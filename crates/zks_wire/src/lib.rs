@@ -50,8 +50,8 @@ pub use relay::{RelayServer, RelayClient, RelayId, RelayConfig, RelayCredentials
 pub use stun::{StunClient, StunServer, IceCandidate};
 pub use swarm::{Swarm, Peer, PeerId, SwarmEvent};
 pub use circuit::{SwarmCircuit, CircuitBuilder};
-pub use wire::{WireMessage, WireProtocol, MessageType};
-pub use signaling::{SignalingClient, SignalingMessage, PeerInfo, PeerCapabilities};
+pub use wire::{WireMessage, WireHeader, WireProtocol, MessageType, WIRE_HEADER_LEN};
+pub use signaling::{SignalingClient, SignalingMessage, PeerInfo, PeerCapabilities, SwarmEntropySource};
 pub use p2p::{NativeP2PTransport, NativeP2PError};
 pub use swarm_controller::{SwarmController, SwarmControllerError, Platform, TransportCapabilities, OnionStream};
 
@@ -11,6 +11,7 @@ use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn, error};
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use zks_crypt::entropy::{EntropySource, EntropySourceError};
 
 /// Information about a discovered peer
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -278,6 +279,37 @@ impl SignalingClient {
     }
 }
 
+/// Swarm entropy of a room as an [`EntropySource`]
+pub struct SwarmEntropySource {
+    client: Arc<Mutex<SignalingClient>>,
+    room_id: String,
+}
+
+impl SwarmEntropySource {
+    /// Request entropy for `room_id` through a shared signaling client
+    pub fn new(client: Arc<Mutex<SignalingClient>>, room_id: impl Into<String>) -> Self {
+        Self {
+            client,
+            room_id: room_id.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl EntropySource for SwarmEntropySource {
+    fn name(&self) -> &str {
+        "swarm"
+    }
+
+    async fn fetch(&self) -> Result<[u8; 32], EntropySourceError> {
+        let mut client = self.client.lock().await;
+        client.get_swarm_entropy(&self.room_id).await.map_err(|e| match e {
+            SignalingError::InvalidEntropy(_) => EntropySourceError::Invalid(e.to_string()),
+            e => EntropySourceError::Unavailable(e.to_string()),
+        })
+    }
+}
+
 /// Errors that can occur during signaling
 #[derive(Debug, thiserror::Error)]
 pub enum SignalingError {
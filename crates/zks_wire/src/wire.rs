@@ -15,6 +15,9 @@ pub const WIRE_PROTOCOL_VERSION: u8 = 1;
 /// Maximum message size (64KB)
 pub const MAX_MESSAGE_SIZE: usize = 65536;
 
+/// Encoded header length, padded to 16 bytes
+pub const WIRE_HEADER_LEN: usize = 16;
+
 /// Message type identifiers
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(u8)]
@@ -67,6 +70,56 @@ pub struct WireHeader {
     pub payload_length: u32,
 }
 
+impl WireHeader {
+    /// Encode the header exactly as it appears on the wire
+    ///
+    /// These bytes double as associated data when the payload is encrypted,
+    /// so any tampering with type, sequence or length fails authentication.
+    pub fn encode(&self) -> [u8; WIRE_HEADER_LEN] {
+        let mut out = [0u8; WIRE_HEADER_LEN];
+        out[0] = self.version;
+        out[1] = self.message_type as u8;
+        out[2..6].copy_from_slice(&self.sequence.to_be_bytes());
+        out[6..10].copy_from_slice(&self.payload_length.to_be_bytes());
+        out
+    }
+
+    /// Decode and validate a header from the first [`WIRE_HEADER_LEN`] bytes
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < WIRE_HEADER_LEN {
+            return Err(WireError::invalid_message("Message too short".to_string()));
+        }
+
+        let mut cursor = Cursor::new(bytes);
+        let version = cursor.get_u8();
+        let message_type_u8 = cursor.get_u8();
+        let sequence = cursor.get_u32();
+        let payload_length = cursor.get_u32();
+
+        // Validate version
+        if version != WIRE_PROTOCOL_VERSION {
+            return Err(WireError::ProtocolVersionMismatch {
+                expected: WIRE_PROTOCOL_VERSION,
+                actual: version,
+            });
+        }
+
+        // Validate message type
+        let message_type = MessageType::from_u8(message_type_u8)?;
+
+        if payload_length as usize > MAX_MESSAGE_SIZE - WIRE_HEADER_LEN {
+            return Err(WireError::invalid_message("Payload too large"));
+        }
+
+        Ok(Self {
+            version,
+            message_type,
+            sequence,
+            payload_length,
+        })
+    }
+}
+
 /// Complete wire protocol message
 #[derive(Debug, Clone)]
 pub struct WireMessage {
@@ -92,22 +145,14 @@ impl WireMessage {
     /// Serialize the message to bytes
     pub fn to_bytes(&self) -> Result<Bytes> {
         // Validate message size before serialization
-        if self.payload.len() > MAX_MESSAGE_SIZE - 16 {
+        if self.payload.len() > MAX_MESSAGE_SIZE - WIRE_HEADER_LEN {
             return Err(WireError::invalid_message("Payload too large"));
         }
         
-        let mut buf = BytesMut::with_capacity(16 + self.payload.len());
-        
-        // Write header
-        buf.put_u8(self.header.version);
-        buf.put_u8(self.header.message_type as u8);
-        buf.put_u32(self.header.sequence);
-        buf.put_u32(self.header.payload_length);
+        let mut buf = BytesMut::with_capacity(WIRE_HEADER_LEN + self.payload.len());
         
-        // Add padding to align to 16 bytes
-        while buf.len() < 16 {
-            buf.put_u8(0);
-        }
+        // Write header (padded to 16 bytes)
+        buf.put_slice(&self.header.encode());
         
         // Write payload
         buf.put(self.payload.clone());
@@ -117,35 +162,12 @@ impl WireMessage {
     
     /// Deserialize from bytes
     pub fn from_bytes(bytes: Bytes) -> Result<Self> {
-        if bytes.len() < 16 {
-            return Err(WireError::invalid_message("Message too short".to_string()));
-        }
-        
-        let mut cursor = Cursor::new(bytes);
-        
-        // Read header
-        let version = cursor.get_u8();
-        let message_type_u8 = cursor.get_u8();
-        let sequence = cursor.get_u32();
-        let payload_length = cursor.get_u32();
-        
-        // Skip padding
-        cursor.set_position(16);
-        
-        // Validate version
-        if version != WIRE_PROTOCOL_VERSION {
-            return Err(WireError::ProtocolVersionMismatch {
-                expected: WIRE_PROTOCOL_VERSION,
-                actual: version,
-            });
-        }
-        
-        // Validate message type
-        let message_type = MessageType::from_u8(message_type_u8)?;
+        let header = WireHeader::decode(&bytes)?;
+        let payload_length = header.payload_length as usize;
         
         // Validate payload length
-        let remaining = cursor.remaining();
-        if remaining < payload_length as usize {
+        let remaining = bytes.len() - WIRE_HEADER_LEN;
+        if remaining < payload_length {
             return Err(WireError::invalid_message(format!(
                 "Payload length mismatch: expected {}, got {}",
                 payload_length, remaining
@@ -153,16 +175,7 @@ impl WireMessage {
         }
         
         // Read payload
-        let mut payload_bytes = vec![0u8; payload_length as usize];
-        cursor.copy_to_slice(&mut payload_bytes);
-        let payload = Bytes::from(payload_bytes);
-        
-        let header = WireHeader {
-            version,
-            message_type,
-            sequence,
-            payload_length,
-        };
+        let payload = bytes.slice(WIRE_HEADER_LEN..WIRE_HEADER_LEN + payload_length);
         
         Ok(Self { header, payload })
     }
@@ -364,4 +377,15 @@ mod tests {
         assert_eq!(decoded.header.message_type, MessageType::Error);
         assert!(decoded.payload.len() > 0);
     }
+    
+    #[test]
+    fn test_header_encode_matches_wire_bytes() {
+        let message = WireMessage::encrypted_data(7, &[0xAA; 10]);
+        let bytes = message.to_bytes().unwrap();
+        
+        assert_eq!(&bytes[..WIRE_HEADER_LEN], &message.header.encode());
+        let header = WireHeader::decode(&bytes).unwrap();
+        assert_eq!(header.sequence, 7);
+        assert_eq!(header.payload_length, 10);
+    }
 }
\ No newline at end of file